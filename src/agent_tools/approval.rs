@@ -0,0 +1,23 @@
+use crate::ui::prompt;
+
+/// What the user answered when a tool asked before acting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Approval {
+    Once,
+    /// Approved for the rest of the session
+    Session,
+    Denied,
+}
+
+/// Asks on the terminal whether a tool may go ahead, anything unexpected counts as no
+pub fn ask_approval(question: &str) -> Approval {
+    let answer = prompt(&format!(
+        "{} [y]es / [a]lways this session / [N]o:",
+        question
+    ));
+    match answer.to_lowercase().as_str() {
+        "y" | "yes" => Approval::Once,
+        "a" | "always" => Approval::Session,
+        _ => Approval::Denied,
+    }
+}
@@ -0,0 +1,198 @@
+use super::{
+    edits::{apply_edit, read_before, EditOutput},
+    workspace::{Workspace, WorkspaceError},
+};
+use crate::agent_tools::ToToolResult;
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// One `@@` section of a unified diff
+#[derive(Debug, PartialEq)]
+struct Hunk {
+    /// 1-based line the hunk claims to start at, only a hint for where to look
+    old_start: usize,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+/// Reads the hunks of a single-file unified diff, ignoring the `---`/`+++` headers and
+/// anything else before the first `@@`
+fn parse_patch(patch: &str) -> Result<Vec<Hunk>, String> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    // a trailing blank line would otherwise read as an empty context line
+    for line in patch.trim_end_matches(['\n', '\r']).lines() {
+        if let Some(header) = line.strip_prefix("@@") {
+            let old_start = header
+                .split_whitespace()
+                .find_map(|range| range.strip_prefix('-'))
+                .and_then(|range| range.split(',').next()?.parse().ok())
+                .ok_or_else(|| format!("malformed hunk header '{}'", line))?;
+            hunks.push(Hunk {
+                old_start,
+                old: Vec::new(),
+                new: Vec::new(),
+            });
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        match line.chars().next() {
+            Some('-') => hunk.old.push(line[1..].to_string()),
+            Some('+') => hunk.new.push(line[1..].to_string()),
+            Some(' ') => {
+                hunk.old.push(line[1..].to_string());
+                hunk.new.push(line[1..].to_string());
+            }
+            // blank context lines often lose their leading space
+            None => {
+                hunk.old.push(String::new());
+                hunk.new.push(String::new());
+            }
+            Some('\\') => {}
+            _ => return Err(format!("unexpected line '{}' in a hunk", line)),
+        }
+    }
+    if hunks.is_empty() {
+        return Err("no @@ hunks found".to_string());
+    }
+    Ok(hunks)
+}
+
+/// Where `old` occurs in `lines` at or after `from`, closest to `hint`, comparing exactly first
+/// and then ignoring trailing whitespace
+fn find_hunk(lines: &[&str], old: &[String], from: usize, hint: usize) -> Option<usize> {
+    if old.is_empty() {
+        return Some(hint.clamp(from, lines.len()));
+    }
+    if old.len() > lines.len() {
+        return None;
+    }
+    let exact = |a: &str, b: &String| a == b;
+    let loose = |a: &str, b: &String| a.trim_end() == b.trim_end();
+    [exact, loose].into_iter().find_map(|same| {
+        (from..=lines.len() - old.len())
+            .filter(|&start| {
+                lines[start..start + old.len()]
+                    .iter()
+                    .zip(old)
+                    .all(|(a, b)| same(a, b))
+            })
+            .min_by_key(|&start| start.abs_diff(hint))
+    })
+}
+
+/// Applies `patch` to `content`, keeping its line endings and final newline
+fn apply_patch(content: &str, patch: &str) -> Result<String, String> {
+    let hunks = parse_patch(patch)?;
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut lines: Vec<&str> = content.lines().collect();
+    let mut from = 0;
+    let mut offset: isize = 0;
+    for (number, hunk) in hunks.iter().enumerate() {
+        let hint = (hunk.old_start.saturating_sub(1) as isize + offset).max(0) as usize;
+        let start = find_hunk(&lines, &hunk.old, from, hint).ok_or_else(|| {
+            format!(
+                "hunk {} does not match the file, read the file again and retry with its current content",
+                number + 1
+            )
+        })?;
+        lines.splice(
+            start..start + hunk.old.len(),
+            hunk.new.iter().map(String::as_str),
+        );
+        from = start + hunk.new.len();
+        offset += hunk.new.len() as isize - hunk.old.len() as isize;
+    }
+    let mut patched = lines.join(newline);
+    if !patched.is_empty() && (content.is_empty() || content.ends_with('\n')) {
+        patched.push_str(newline);
+    }
+    Ok(patched)
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ApplyPatchArgs {
+    #[schemars(description = "Path of the file, relative to the workspace root")]
+    pub path: String,
+    #[schemars(
+        description = "Unified diff with @@ hunks and 3 lines of context. Line numbers in the @@ headers are only hints, the context lines must match the file"
+    )]
+    pub patch: String,
+}
+
+pub struct ApplyPatch {
+    workspace: Arc<Workspace>,
+}
+
+impl ApplyPatch {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+}
+
+impl Tool for ApplyPatch {
+    const NAME: &'static str = "apply_patch";
+    type Args = ApplyPatchArgs;
+    type Output = EditOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Change a text file in the local workspace by applying a unified diff. The user sees the resulting diff and approves it first. Read the file before patching it."
+                .to_string(),
+            parameters: serde_json::to_value(schema_for!(ApplyPatchArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let path = self.workspace.resolve_new(&args.path).to_tool_result()?;
+        let before = read_before(&self.workspace, &path).to_tool_result()?;
+        let after = apply_patch(before.as_deref().unwrap_or_default(), &args.patch)
+            .map_err(|message| WorkspaceError::PatchFailed {
+                path: args.path.clone(),
+                message,
+            })
+            .to_tool_result()?;
+        apply_edit(&self.workspace, Self::NAME, &path, before, after).to_tool_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_patch() {
+        let content = "fn main() {\n    let x = 1;\n\n    println!(\"{}\", x);\n}\n";
+        // wrong line numbers and a blank context line without its space
+        let patch = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -7,4 +7,4 @@\n fn main() {\n-    let x = 1;\n+    let x = 2;\n\n     println!(\"{}\", x);\n";
+        assert_eq!(
+            apply_patch(content, patch).unwrap(),
+            "fn main() {\n    let x = 2;\n\n    println!(\"{}\", x);\n}\n"
+        );
+        assert_eq!(
+            apply_patch("", "@@ -0,0 +1,2 @@\n+one\n+two\n").unwrap(),
+            "one\ntwo\n"
+        );
+        assert_eq!(
+            apply_patch("a\r\nb\r\n", "@@ -2 +2 @@\n-b\n+c\n").unwrap(),
+            "a\r\nc\r\n"
+        );
+        assert!(apply_patch(content, "@@ -1 +1 @@\n-fn other() {\n+fn main() {\n").is_err());
+        assert!(apply_patch(content, "just some text").is_err());
+        // more lines in the hunk than in the file
+        assert!(apply_patch("", "@@ -1 +1,2 @@\n a\n+b\n").is_err());
+        assert!(apply_patch("a\n", "@@ -1,3 +1,3 @@\n a\n-b\n+c\n d\n").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use super::workspace::{Workspace, WorkspaceError};
+use crate::{
+    agent_tools::approval::{ask_approval, Approval},
+    ui::horizontal_line,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+/// Lines of unchanged context around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+
+/// A write the user approved, kept so it can be reverted
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub tool: &'static str,
+    pub path: PathBuf,
+    /// `None` when the edit created the file
+    pub before: Option<String>,
+    pub after: String,
+    pub applied_at: DateTime<Utc>,
+}
+
+/// Edits applied this run, newest last, and whether the user approved edits for the session
+#[derive(Default)]
+pub struct EditLog {
+    edits: Mutex<Vec<Edit>>,
+    approved_for_session: AtomicBool,
+}
+
+#[derive(Serialize)]
+pub struct EditOutput {
+    pub path: String,
+    pub created: bool,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+impl EditLog {
+    fn lock(&self) -> MutexGuard<'_, Vec<Edit>> {
+        self.edits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    /// Asks again before the next edit
+    pub fn start_session(&self) {
+        self.approved_for_session.store(false, Ordering::Relaxed);
+    }
+    pub fn edits(&self) -> Vec<Edit> {
+        self.lock().clone()
+    }
+    /// Shows the diff and asks before writing, unless edits were approved for the session
+    fn approve(&self, tool: &str, path: &str, diff: &str) -> bool {
+        println!();
+        horizontal_line();
+        println!("{} wants to change {}:", tool, path);
+        print_diff(diff);
+        horizontal_line();
+        if self.approved_for_session.load(Ordering::Relaxed) {
+            println!("Applied, edits are approved for this session");
+            return true;
+        }
+        match ask_approval("Apply this edit?") {
+            Approval::Once => true,
+            Approval::Session => {
+                self.approved_for_session.store(true, Ordering::Relaxed);
+                true
+            }
+            Approval::Denied => false,
+        }
+    }
+    /// Undoes edit `index`, as long as the file still holds what the edit wrote or `force` is set
+    pub fn revert(&self, index: usize, force: bool) -> Result<Edit, WorkspaceError> {
+        let mut edits = self.lock();
+        let edit = edits
+            .get(index)
+            .ok_or(WorkspaceError::NoSuchEdit(index + 1))?;
+        let current = std::fs::read_to_string(&edit.path).ok();
+        if !force && current.as_deref() != Some(edit.after.as_str()) {
+            return Err(WorkspaceError::ChangedSinceEdit(
+                edit.path.display().to_string(),
+            ));
+        }
+        match &edit.before {
+            Some(before) => std::fs::write(&edit.path, before)?,
+            None => std::fs::remove_file(&edit.path)?,
+        }
+        Ok(edits.remove(index))
+    }
+}
+
+/// A unified diff of `before` and `after` with `a/` and `b/` headers, and the lines added and
+/// removed
+pub fn unified_diff(path: &str, before: &str, after: &str) -> (String, usize, usize) {
+    let diff = TextDiff::from_lines(before, after);
+    let (added, removed) = diff
+        .iter_all_changes()
+        .fold((0, 0), |(added, removed), change| match change.tag() {
+            ChangeTag::Insert => (added + 1, removed),
+            ChangeTag::Delete => (added, removed + 1),
+            ChangeTag::Equal => (added, removed),
+        });
+    let text = diff
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string();
+    (text, added, removed)
+}
+
+/// Prints a diff, in colour on a terminal
+pub fn print_diff(diff: &str) {
+    let colour = std::io::stdout().is_terminal();
+    for line in diff.lines() {
+        let code = match line {
+            _ if !colour => None,
+            line if line.starts_with("+++") || line.starts_with("---") => Some("1"),
+            line if line.starts_with('+') => Some("32"),
+            line if line.starts_with('-') => Some("31"),
+            line if line.starts_with("@@") => Some("36"),
+            _ => None,
+        };
+        match code {
+            Some(code) => println!("\x1b[{}m{}\x1b[0m", code, line),
+            None => println!("{}", line),
+        }
+    }
+}
+
+/// Replaces the file at `path`, which `Workspace::resolve_new` vetted, with `after` once the
+/// user approves the diff, and logs the edit
+pub fn apply_edit(
+    workspace: &Workspace,
+    tool: &'static str,
+    path: &Path,
+    before: Option<String>,
+    after: String,
+) -> Result<EditOutput, WorkspaceError> {
+    let relative = workspace.relative(path);
+    let (diff, lines_added, lines_removed) =
+        unified_diff(&relative, before.as_deref().unwrap_or_default(), &after);
+    let output = EditOutput {
+        path: relative.clone(),
+        created: before.is_none(),
+        lines_added,
+        lines_removed,
+    };
+    if before.as_deref() == Some(after.as_str()) {
+        return Ok(output);
+    }
+    if !workspace.edits().approve(tool, &relative, &diff) {
+        return Err(WorkspaceError::EditRejected(relative));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, &after)?;
+    workspace.edits().lock().push(Edit {
+        tool,
+        path: path.to_path_buf(),
+        before,
+        after,
+        applied_at: Utc::now(),
+    });
+    Ok(output)
+}
+
+/// The current text of a file about to be edited, `None` if it does not exist yet
+pub fn read_before(workspace: &Workspace, path: &Path) -> Result<Option<String>, WorkspaceError> {
+    if path.symlink_metadata().is_err() {
+        return Ok(None);
+    }
+    workspace.read_text(path).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_tools::files::workspace::DEFAULT_FILE_SIZE_LIMIT;
+
+    #[test]
+    fn test_revert_edits() {
+        let root = std::env::temp_dir().join(format!("marvin-edits-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.txt"), "one\n").unwrap();
+        let workspace = Workspace::new(&root, DEFAULT_FILE_SIZE_LIMIT).unwrap();
+        workspace
+            .edits()
+            .approved_for_session
+            .store(true, Ordering::Relaxed);
+        let edit = |path: &str, after: &str| {
+            let path = workspace.resolve_new(path).unwrap();
+            let before = read_before(&workspace, &path).unwrap();
+            apply_edit(&workspace, "write_file", &path, before, after.to_string()).unwrap()
+        };
+        assert!(!edit("a.txt", "two\n").created);
+        assert!(edit("new/b.txt", "fresh\n").created);
+        let a = workspace.root().join("a.txt");
+        let b = workspace.root().join("new/b.txt");
+
+        // undoing a created file removes it
+        assert_eq!(workspace.edits().revert(1, false).unwrap().path, b);
+        assert!(!b.exists());
+        std::fs::write(&a, "changed by hand\n").unwrap();
+        assert!(matches!(
+            workspace.edits().revert(0, false),
+            Err(WorkspaceError::ChangedSinceEdit(_))
+        ));
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "changed by hand\n");
+        workspace.edits().revert(0, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "one\n");
+        assert!(workspace.edits().edits().is_empty());
+        assert!(matches!(
+            workspace.edits().revert(0, false),
+            Err(WorkspaceError::NoSuchEdit(1))
+        ));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+use super::workspace::{Workspace, WorkspaceError};
+use crate::agent_tools::ToToolResult;
+use globset::{GlobBuilder, GlobMatcher};
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const MAX_MATCHES: usize = 500;
+
+/// Compiles a glob where `*` stays within a path segment and `**` crosses them
+pub fn glob_matcher(pattern: &str) -> Result<GlobMatcher, WorkspaceError> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| WorkspaceError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: e.kind().to_string(),
+        })
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct GlobArgs {
+    #[schemars(
+        description = "Glob matched against paths relative to `path`, e.g. \"**/*.rs\" or \"src/*/mod.rs\""
+    )]
+    pub pattern: String,
+    #[schemars(
+        description = "Directory to search from, relative to the workspace root. Default: the root"
+    )]
+    pub path: Option<String>,
+    #[schemars(description = "Include hidden files and directories. Default: false")]
+    pub include_hidden: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct GlobOutput {
+    /// Paths relative to the workspace root
+    pub matches: Vec<String>,
+    /// Set when there were more than `MAX_MATCHES` matches
+    pub truncated: bool,
+}
+
+pub struct Glob {
+    workspace: Arc<Workspace>,
+}
+
+impl Glob {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+}
+
+impl Tool for Glob {
+    const NAME: &'static str = "glob";
+    type Args = GlobArgs;
+    type Output = GlobOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Find files in the local workspace whose paths match a glob pattern, skipping .gitignored paths"
+                .to_string(),
+            parameters: serde_json::to_value(schema_for!(GlobArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let matcher = glob_matcher(&args.pattern).to_tool_result()?;
+        let base = self
+            .workspace
+            .resolve(args.path.as_deref().unwrap_or("."))
+            .to_tool_result()?;
+        let mut matches = self
+            .workspace
+            .walk(&base, None, args.include_hidden.unwrap_or(false))
+            .filter(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(&base)
+                    .is_ok_and(|relative| matcher.is_match(relative))
+            })
+            .map(|entry| self.workspace.relative(entry.path()));
+        let listed: Vec<String> = matches.by_ref().take(MAX_MATCHES).collect();
+        Ok(GlobOutput {
+            matches: listed,
+            truncated: matches.next().is_some(),
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use super::{
+    glob::glob_matcher,
+    workspace::{Workspace, WorkspaceError},
+};
+use crate::agent_tools::ToToolResult;
+use regex::RegexBuilder;
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const DEFAULT_MAX_MATCHES: usize = 100;
+const MAX_MATCHES: usize = 1000;
+/// Characters of a matching line returned, long minified lines are cut
+const MAX_LINE_LEN: usize = 300;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct GrepFilesArgs {
+    #[schemars(description = "Regular expression to search for, in Rust regex syntax")]
+    pub pattern: String,
+    #[schemars(
+        description = "File or directory to search, relative to the workspace root. Default: the root"
+    )]
+    pub path: Option<String>,
+    #[schemars(description = "Only search files whose paths match this glob, e.g. \"**/*.rs\"")]
+    pub glob: Option<String>,
+    #[schemars(description = "Match regardless of case. Default: false")]
+    pub case_insensitive: Option<bool>,
+    #[schemars(description = "Maximum matching lines to return (1-1000). Default: 100")]
+    pub max_matches: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct GrepMatch {
+    pub path: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+#[derive(Serialize)]
+pub struct GrepFilesOutput {
+    pub matches: Vec<GrepMatch>,
+    pub files_searched: usize,
+    /// Binary files and files over the size limit
+    pub files_skipped: usize,
+    /// Set when the search stopped at `max_matches`
+    pub truncated: bool,
+}
+
+pub struct GrepFiles {
+    workspace: Arc<Workspace>,
+}
+
+impl GrepFiles {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+    fn search(&self, args: GrepFilesArgs) -> Result<GrepFilesOutput, WorkspaceError> {
+        let regex = RegexBuilder::new(&args.pattern)
+            .case_insensitive(args.case_insensitive.unwrap_or(false))
+            .build()
+            .map_err(|e| WorkspaceError::InvalidPattern {
+                pattern: args.pattern.clone(),
+                message: e.to_string(),
+            })?;
+        let glob = args.glob.as_deref().map(glob_matcher).transpose()?;
+        let max_matches = args
+            .max_matches
+            .unwrap_or(DEFAULT_MAX_MATCHES)
+            .clamp(1, MAX_MATCHES);
+        let base = self
+            .workspace
+            .resolve(args.path.as_deref().unwrap_or("."))?;
+        let files: Vec<_> = if base.is_file() {
+            vec![base.clone()]
+        } else {
+            self.workspace
+                .walk(&base, None, false)
+                .filter(|entry| entry.path().is_file())
+                .filter(|entry| {
+                    glob.as_ref().is_none_or(|glob| {
+                        entry
+                            .path()
+                            .strip_prefix(&base)
+                            .is_ok_and(|relative| glob.is_match(relative))
+                    })
+                })
+                .map(|entry| entry.into_path())
+                .collect()
+        };
+        let mut output = GrepFilesOutput {
+            matches: Vec::new(),
+            files_searched: 0,
+            files_skipped: 0,
+            truncated: false,
+        };
+        for file in files {
+            let Ok(content) = self.workspace.read_text(&file) else {
+                output.files_skipped += 1;
+                continue;
+            };
+            output.files_searched += 1;
+            for (i, line) in content.lines().enumerate() {
+                if !regex.is_match(line) {
+                    continue;
+                }
+                if output.matches.len() == max_matches {
+                    output.truncated = true;
+                    return Ok(output);
+                }
+                output.matches.push(GrepMatch {
+                    path: self.workspace.relative(&file),
+                    line_number: i + 1,
+                    line: line.chars().take(MAX_LINE_LEN).collect(),
+                });
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl Tool for GrepFiles {
+    const NAME: &'static str = "grep_files";
+    type Args = GrepFilesArgs;
+    type Output = GrepFilesOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Search the contents of text files in the local workspace for a regular expression, skipping .gitignored paths and binary files"
+                .to_string(),
+            parameters: serde_json::to_value(schema_for!(GrepFilesArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.search(args).to_tool_result()
+    }
+}
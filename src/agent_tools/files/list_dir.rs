@@ -0,0 +1,109 @@
+use super::workspace::{Workspace, WorkspaceError};
+use crate::agent_tools::ToToolResult;
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const MAX_DEPTH: usize = 5;
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ListDirArgs {
+    #[schemars(
+        description = "Directory to list, relative to the workspace root. Default: the root"
+    )]
+    pub path: Option<String>,
+    #[schemars(description = "How many levels deep to list (1-5). Default: 1")]
+    pub depth: Option<usize>,
+    #[schemars(description = "Include hidden files and directories. Default: false")]
+    pub include_hidden: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+#[derive(Serialize)]
+pub struct Entry {
+    pub path: String,
+    pub kind: EntryKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct ListDirOutput {
+    pub path: String,
+    pub entries: Vec<Entry>,
+    /// Set when there were more than `MAX_ENTRIES` entries
+    pub truncated: bool,
+}
+
+pub struct ListDir {
+    workspace: Arc<Workspace>,
+}
+
+impl ListDir {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+}
+
+impl Tool for ListDir {
+    const NAME: &'static str = "list_dir";
+    type Args = ListDirArgs;
+    type Output = ListDirOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description:
+                "List files and directories in the local workspace, skipping .gitignored paths"
+                    .to_string(),
+            parameters: serde_json::to_value(schema_for!(ListDirArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let requested = args.path.as_deref().unwrap_or(".");
+        let dir = self.workspace.resolve(requested).to_tool_result()?;
+        if !dir.is_dir() {
+            return Err(WorkspaceError::NotDirectory(requested.to_string())).to_tool_result();
+        }
+        let depth = args.depth.unwrap_or(1).clamp(1, MAX_DEPTH);
+        let mut entries = self
+            .workspace
+            .walk(&dir, Some(depth), args.include_hidden.unwrap_or(false))
+            .map(|entry| {
+                let file_type = entry.file_type();
+                let (kind, size) = match file_type {
+                    Some(file_type) if file_type.is_symlink() => (EntryKind::Symlink, None),
+                    Some(file_type) if file_type.is_dir() => (EntryKind::Dir, None),
+                    _ => (
+                        EntryKind::File,
+                        entry.metadata().ok().map(|metadata| metadata.len()),
+                    ),
+                };
+                Entry {
+                    path: self.workspace.relative(entry.path()),
+                    kind,
+                    size,
+                }
+            });
+        let listed: Vec<Entry> = entries.by_ref().take(MAX_ENTRIES).collect();
+        Ok(ListDirOutput {
+            path: self.workspace.relative(&dir),
+            entries: listed,
+            truncated: entries.next().is_some(),
+        })
+    }
+}
@@ -0,0 +1,35 @@
+use apply_patch::ApplyPatch;
+use glob::Glob;
+use grep_files::GrepFiles;
+use list_dir::ListDir;
+use read_file::ReadFile;
+use rig::tool::ToolDyn;
+use std::sync::Arc;
+use workspace::Workspace;
+use write_file::WriteFile;
+
+pub mod apply_patch;
+pub mod edits;
+pub mod glob;
+pub mod grep_files;
+pub mod list_dir;
+pub mod read_file;
+pub mod workspace;
+pub mod write_file;
+
+pub trait FileTools {
+    fn file_tools(&self) -> Vec<Box<dyn ToolDyn>>;
+}
+
+impl FileTools for Arc<Workspace> {
+    fn file_tools(&self) -> Vec<Box<dyn ToolDyn>> {
+        vec![
+            Box::new(ReadFile::new(self.clone())),
+            Box::new(ListDir::new(self.clone())),
+            Box::new(Glob::new(self.clone())),
+            Box::new(GrepFiles::new(self.clone())),
+            Box::new(WriteFile::new(self.clone())),
+            Box::new(ApplyPatch::new(self.clone())),
+        ]
+    }
+}
@@ -0,0 +1,122 @@
+use super::workspace::{Workspace, WorkspaceError};
+use crate::agent_tools::ToToolResult;
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ReadFileArgs {
+    #[schemars(description = "Path of the file, relative to the workspace root")]
+    pub path: String,
+    #[schemars(description = "First line to read, counting from 1. Default: 1")]
+    pub start_line: Option<usize>,
+    #[schemars(
+        description = "Number of lines to read. Default: the whole file, which must be within the size limit"
+    )]
+    pub line_count: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct ReadFileOutput {
+    pub path: String,
+    pub content: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Only known when the file was read to the end
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_lines: Option<usize>,
+    /// Set when the size limit cut the requested lines short
+    pub truncated: bool,
+}
+
+pub struct ReadFile {
+    workspace: Arc<Workspace>,
+}
+
+impl ReadFile {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+    /// Reads a line range without loading the whole file, stopping at the size limit
+    fn read_lines(
+        &self,
+        args: &ReadFileArgs,
+        path: &std::path::Path,
+    ) -> Result<ReadFileOutput, WorkspaceError> {
+        if path.is_dir() {
+            return Err(WorkspaceError::IsDirectory(args.path.clone()));
+        }
+        self.workspace.check_text(path)?;
+        let start_line = args.start_line.unwrap_or(1).max(1);
+        let limit = self.workspace.file_size_limit() as usize;
+        let mut content = String::new();
+        let mut end_line = start_line - 1;
+        let mut truncated = false;
+        let mut lines = BufReader::new(File::open(path)?)
+            .lines()
+            .skip(start_line - 1);
+        for line in lines.by_ref().take(args.line_count.unwrap_or(usize::MAX)) {
+            let line = line?;
+            if content.len() + line.len() + 1 > limit {
+                truncated = true;
+                break;
+            }
+            content.push_str(&line);
+            content.push('\n');
+            end_line += 1;
+        }
+        let total_lines = (!truncated && lines.next().is_none()).then_some(end_line);
+        Ok(ReadFileOutput {
+            path: self.workspace.relative(path),
+            content,
+            start_line,
+            end_line,
+            total_lines,
+            truncated,
+        })
+    }
+}
+
+impl Tool for ReadFile {
+    const NAME: &'static str = "read_file";
+    type Args = ReadFileArgs;
+    type Output = ReadFileOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: format!(
+                "Read a text file in the local workspace at {}. Files over {} bytes must be read in line ranges.",
+                self.workspace.root().display(),
+                self.workspace.file_size_limit()
+            ),
+            parameters: serde_json::to_value(schema_for!(ReadFileArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let path = self.workspace.resolve(&args.path).to_tool_result()?;
+        if args.start_line.is_none() && args.line_count.is_none() {
+            let content = self.workspace.read_text(&path).to_tool_result()?;
+            let total_lines = content.lines().count();
+            return Ok(ReadFileOutput {
+                path: self.workspace.relative(&path),
+                content,
+                start_line: 1,
+                end_line: total_lines,
+                total_lines: Some(total_lines),
+                truncated: false,
+            });
+        }
+        self.read_lines(&args, &path).to_tool_result()
+    }
+}
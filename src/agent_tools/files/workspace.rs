@@ -0,0 +1,250 @@
+use super::edits::EditLog;
+use crate::chat::config::Config;
+use ignore::{DirEntry, WalkBuilder};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+pub const DEFAULT_FILE_SIZE_LIMIT: u64 = 256 * 1024;
+/// Bytes looked at to tell binary files from text
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+#[derive(Debug, Error)]
+pub enum WorkspaceError {
+    #[error("{0} is outside the workspace, only paths under the workspace root can be used")]
+    OutsideWorkspace(String),
+    #[error("{0} does not exist")]
+    NotFound(String),
+    #[error("{path} is {size} bytes, over the {limit} byte limit, read it in smaller line ranges")]
+    TooLarge { path: String, size: u64, limit: u64 },
+    #[error("{0} looks like a binary file and cannot be read as text")]
+    Binary(String),
+    #[error("{0} is a directory")]
+    IsDirectory(String),
+    #[error("{0} is not a directory")]
+    NotDirectory(String),
+    #[error("invalid pattern '{pattern}': {message}")]
+    InvalidPattern { pattern: String, message: String },
+    #[error("{0} is not a usable file path")]
+    InvalidPath(String),
+    #[error(
+        "the user rejected the edit to {0}, ask them what they want changed instead of retrying"
+    )]
+    EditRejected(String),
+    #[error("the patch does not apply to {path}: {message}")]
+    PatchFailed { path: String, message: String },
+    #[error("there is no edit {0}")]
+    NoSuchEdit(usize),
+    #[error("{0} changed since the edit")]
+    ChangedSinceEdit(String),
+    #[error("workspace root {path}: {source}")]
+    Root { path: PathBuf, source: io::Error },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// The directory the file tools are confined to, from `MARVIN_WORKSPACE`
+pub struct Workspace {
+    /// Canonical, so symlinks in the root itself do not count as escapes
+    root: PathBuf,
+    file_size_limit: u64,
+    edits: EditLog,
+}
+
+impl Workspace {
+    pub fn new(root: impl AsRef<Path>, file_size_limit: u64) -> Result<Self, WorkspaceError> {
+        let root = root.as_ref();
+        let root = root.canonicalize().map_err(|source| WorkspaceError::Root {
+            path: root.to_path_buf(),
+            source,
+        })?;
+        Ok(Self {
+            root,
+            file_size_limit,
+            edits: EditLog::default(),
+        })
+    }
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    pub fn file_size_limit(&self) -> u64 {
+        self.file_size_limit
+    }
+    pub fn edits(&self) -> &EditLog {
+        &self.edits
+    }
+    /// Whether the real location of `path`, after following symlinks, is under the root
+    pub fn contains(&self, path: &Path) -> bool {
+        path.canonicalize()
+            .is_ok_and(|real| real.starts_with(&self.root))
+    }
+    /// Resolves `path`, relative to the root unless absolute, to the real location of an
+    /// existing file or directory under the root
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, WorkspaceError> {
+        let real = self
+            .root
+            .join(path.trim())
+            .canonicalize()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => WorkspaceError::NotFound(path.to_string()),
+                _ => WorkspaceError::Io(e),
+            })?;
+        if real.starts_with(&self.root) {
+            Ok(real)
+        } else {
+            Err(WorkspaceError::OutsideWorkspace(path.to_string()))
+        }
+    }
+    /// Like `resolve`, but for a file that may not exist yet. The nearest existing ancestor
+    /// must be under the root and the rest of the path cannot contain `..`.
+    pub fn resolve_new(&self, path: &str) -> Result<PathBuf, WorkspaceError> {
+        let joined = self.root.join(path.trim());
+        let mut existing = joined.as_path();
+        let mut missing = Vec::new();
+        // symlink_metadata, so a dangling symlink counts as existing and fails to canonicalize
+        while existing.symlink_metadata().is_err() {
+            missing.push(
+                existing
+                    .file_name()
+                    .ok_or_else(|| WorkspaceError::InvalidPath(path.to_string()))?,
+            );
+            existing = existing
+                .parent()
+                .ok_or_else(|| WorkspaceError::InvalidPath(path.to_string()))?;
+        }
+        let mut real = existing.canonicalize()?;
+        real.extend(missing.into_iter().rev());
+        if real.starts_with(&self.root) && real != self.root {
+            Ok(real)
+        } else {
+            Err(WorkspaceError::OutsideWorkspace(path.to_string()))
+        }
+    }
+    /// `path` relative to the root with `/` separators, `.` for the root itself
+    pub fn relative(&self, path: &Path) -> String {
+        match path.strip_prefix(&self.root) {
+            Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+            Ok(relative) => relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => path.display().to_string(),
+        }
+    }
+    /// Fails for binary files, judged by NUL bytes or invalid UTF-8 near the start
+    pub fn check_text(&self, path: &Path) -> Result<(), WorkspaceError> {
+        let mut start = Vec::with_capacity(BINARY_SNIFF_LEN);
+        File::open(path)?
+            .take(BINARY_SNIFF_LEN as u64)
+            .read_to_end(&mut start)?;
+        let valid_utf8 = match std::str::from_utf8(&start) {
+            Ok(_) => true,
+            // a character cut off by the sniff length is fine
+            Err(e) => e.error_len().is_none(),
+        };
+        if start.contains(&0) || !valid_utf8 {
+            return Err(WorkspaceError::Binary(self.relative(path)));
+        }
+        Ok(())
+    }
+    /// Reads a whole text file within the size limit
+    pub fn read_text(&self, path: &Path) -> Result<String, WorkspaceError> {
+        let metadata = path.metadata()?;
+        if metadata.is_dir() {
+            return Err(WorkspaceError::IsDirectory(self.relative(path)));
+        }
+        if metadata.len() > self.file_size_limit {
+            return Err(WorkspaceError::TooLarge {
+                path: self.relative(path),
+                size: metadata.len(),
+                limit: self.file_size_limit,
+            });
+        }
+        self.check_text(path)?;
+        Ok(String::from_utf8_lossy(&std::fs::read(path)?).into_owned())
+    }
+    /// Entries under `dir`, skipping `.gitignore`d paths, `.git` and symlinks that lead out
+    /// of the workspace. Hidden files are included on request.
+    pub fn walk(
+        &self,
+        dir: &Path,
+        max_depth: Option<usize>,
+        include_hidden: bool,
+    ) -> impl Iterator<Item = DirEntry> + '_ {
+        WalkBuilder::new(dir)
+            .hidden(!include_hidden)
+            .require_git(false)
+            .follow_links(false)
+            .max_depth(max_depth)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .sort_by_file_path(|a, b| a.cmp(b))
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.depth() > 0)
+            .filter(|entry| !entry.path_is_symlink() || self.contains(entry.path()))
+    }
+}
+
+impl TryFrom<&Config> for Workspace {
+    type Error = WorkspaceError;
+
+    fn try_from(value: &Config) -> Result<Self, Self::Error> {
+        Self::new(value.workspace(), value.file_size_limit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_stays_in_workspace() {
+        let dir = std::env::temp_dir().join(format!("marvin-workspace-{}", std::process::id()));
+        let root = dir.join("root");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("blob.bin"), [0u8, 159, 146, 150]).unwrap();
+        std::fs::write(dir.join("secret.txt"), "hunter2").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("secret.txt"), root.join("escape.txt")).unwrap();
+        let workspace = Workspace::new(&root, DEFAULT_FILE_SIZE_LIMIT).unwrap();
+
+        let main = workspace.resolve("src/../src/main.rs").unwrap();
+        assert_eq!(workspace.relative(&main), "src/main.rs");
+        assert_eq!(workspace.read_text(&main).unwrap(), "fn main() {}\n");
+        assert!(matches!(
+            workspace.resolve("../secret.txt"),
+            Err(WorkspaceError::OutsideWorkspace(_))
+        ));
+        #[cfg(unix)]
+        assert!(matches!(
+            workspace.resolve("escape.txt"),
+            Err(WorkspaceError::OutsideWorkspace(_))
+        ));
+        assert_eq!(
+            workspace.resolve_new("src/new/lib.rs").unwrap(),
+            workspace.root().join("src/new/lib.rs")
+        );
+        assert!(workspace.resolve_new("missing/../../secret.txt").is_err());
+        #[cfg(unix)]
+        assert!(matches!(
+            workspace.resolve_new("escape.txt"),
+            Err(WorkspaceError::OutsideWorkspace(_))
+        ));
+        let blob = workspace.resolve("blob.bin").unwrap();
+        assert!(matches!(
+            workspace.read_text(&blob),
+            Err(WorkspaceError::Binary(_))
+        ));
+        let walked: Vec<String> = workspace
+            .walk(workspace.root(), None, false)
+            .map(|entry| workspace.relative(entry.path()))
+            .collect();
+        assert_eq!(walked, ["blob.bin", "src", "src/main.rs"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+use super::{
+    edits::{apply_edit, read_before, EditOutput},
+    workspace::Workspace,
+};
+use crate::agent_tools::ToToolResult;
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct WriteFileArgs {
+    #[schemars(
+        description = "Path of the file, relative to the workspace root. Missing directories are created"
+    )]
+    pub path: String,
+    #[schemars(description = "The complete new content of the file")]
+    pub content: String,
+}
+
+pub struct WriteFile {
+    workspace: Arc<Workspace>,
+}
+
+impl WriteFile {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+}
+
+impl Tool for WriteFile {
+    const NAME: &'static str = "write_file";
+    type Args = WriteFileArgs;
+    type Output = EditOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Create a text file in the local workspace or replace its whole content. The user sees a diff and approves it first. Prefer apply_patch for small changes to existing files."
+                .to_string(),
+            parameters: serde_json::to_value(schema_for!(WriteFileArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let path = self.workspace.resolve_new(&args.path).to_tool_result()?;
+        let before = read_before(&self.workspace, &path).to_tool_result()?;
+        apply_edit(&self.workspace, Self::NAME, &path, before, args.content).to_tool_result()
+    }
+}
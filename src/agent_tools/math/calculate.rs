@@ -0,0 +1,78 @@
+use super::expression::{evaluate, CalcError, Number, Precision};
+use crate::agent_tools::ToToolResult;
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct CalculateArgs {
+    #[schemars(
+        description = "The expression to evaluate, e.g. \"(1.5 + 2) * sqrt(16) / 3\". Several steps can be chained with ';' or new lines, and `name = expression` stores a value for later steps."
+    )]
+    pub expression: String,
+    #[schemars(description = "Values for variables used in the expression, e.g. {\"x\": 2.5}")]
+    pub variables: Option<BTreeMap<String, f64>>,
+}
+
+#[derive(Serialize)]
+pub struct CalculateOutput {
+    /// The value of the last statement
+    pub result: String,
+    pub precision: Precision,
+    /// Variables assigned by the expression
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+}
+
+pub struct Calculate;
+
+impl Tool for Calculate {
+    const NAME: &'static str = "calculate";
+    type Args = CalculateArgs;
+    type Output = CalculateOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Evaluates arithmetic expressions with arbitrary-precision decimals, so 0.1 + 0.2 is exactly 0.3. \
+                Supports + - * / % ^ (or **), factorial !, parentheses, the constants pi, e and tau, and the functions \
+                sqrt, cbrt, abs, exp, ln, log(x) (base 10) or log(x, base), log2, log10, sin, cos, tan, asin, acos, atan, \
+                atan2(y, x), sinh, cosh, tanh, asinh, acosh, atanh (all in radians), deg, rad, floor, ceil, round(x, places), \
+                min and max. The precision of the result is exact, rounded (a division cut off after 40 decimal places) \
+                or float (a function computed with 64-bit floating point)."
+                .to_string(),
+            parameters: serde_json::to_value(schema_for!(CalculateArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let given = args.variables.unwrap_or_default();
+        let mut variables = given
+            .iter()
+            .map(|(name, value)| {
+                Number::float(*value)
+                    .map(|number| (name.clone(), Number::exact(number.value)))
+                    .ok_or_else(|| CalcError::Domain {
+                        function: "a variable".to_string(),
+                        value: value.to_string(),
+                    })
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()
+            .to_tool_result()?;
+        let result = evaluate(&args.expression, &mut variables).to_tool_result()?;
+        Ok(CalculateOutput {
+            result: result.to_string(),
+            precision: result.precision,
+            variables: variables
+                .into_iter()
+                .filter(|(name, _)| !given.contains_key(name))
+                .map(|(name, value)| (name, value.to_string()))
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,60 @@
+use super::units::convert;
+use crate::agent_tools::ToToolResult;
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ConvertUnitsArgs {
+    #[schemars(description = "The amount to convert")]
+    pub value: f64,
+    #[schemars(
+        description = "The unit of the value, e.g. \"km/h\", \"GiB\", \"degF\" or \"kg*m/s^2\""
+    )]
+    pub from: String,
+    #[schemars(description = "The unit to convert to, of the same dimension")]
+    pub to: String,
+}
+
+#[derive(Serialize)]
+pub struct ConvertUnitsOutput {
+    pub value: f64,
+    pub unit: String,
+    /// What the units measure, e.g. "speed"
+    pub dimension: String,
+}
+
+pub struct ConvertUnits;
+
+impl Tool for ConvertUnits {
+    const NAME: &'static str = "convert_units";
+    type Args = ConvertUnitsArgs;
+    type Output = ConvertUnitsOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Converts a quantity between units of length, area, volume, mass, time, speed, force, pressure, \
+                energy, power, temperature and data size, checking that the dimensions match. Units take SI prefixes \
+                (km, ms, MW) and data units also binary ones: KB and kB are 1000 bytes, KiB is 1024 bytes, b is a bit \
+                and B a byte. Compound units are written with * and /, powers with ^, e.g. \"m/s^2\" or \"MiB/s\". \
+                A lone temperature unit converts absolute temperatures (0 degC is 32 degF), inside a compound unit \
+                it is a temperature difference."
+                .to_string(),
+            parameters: serde_json::to_value(schema_for!(ConvertUnitsArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let (value, quantity) = convert(args.value, &args.from, &args.to).to_tool_result()?;
+        Ok(ConvertUnitsOutput {
+            value,
+            unit: args.to,
+            dimension: quantity.dimension_name(),
+        })
+    }
+}
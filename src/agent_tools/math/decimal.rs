@@ -0,0 +1,403 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// Fraction digits kept when a division does not terminate
+pub const DIVISION_DIGITS: usize = 40;
+/// Digits past which exact results give way to floating point
+pub const MAX_DIGITS: usize = 1000;
+
+/// Arbitrary-precision decimal, `digits` least significant first with `scale` of them after
+/// the decimal point. Always normalized: no leading zeros, no trailing fraction zeros and
+/// zero is never negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    scale: usize,
+}
+
+fn trim_leading(digits: &mut Vec<u8>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn cmp_magnitude(a: &[u8], b: &[u8]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let digit = a.get(i).copied().unwrap_or(0) + b.get(i).copied().unwrap_or(0) + carry;
+        sum.push(digit % 10);
+        carry = digit / 10;
+    }
+    if carry > 0 {
+        sum.push(carry);
+    }
+    sum
+}
+
+/// `a - b` for `a >= b`
+fn sub_magnitude(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, digit) in a.iter().enumerate() {
+        let mut digit = *digit as i8 - b.get(i).copied().unwrap_or(0) as i8 - borrow;
+        borrow = 0;
+        if digit < 0 {
+            digit += 10;
+            borrow = 1;
+        }
+        difference.push(digit as u8);
+    }
+    trim_leading(&mut difference);
+    difference
+}
+
+fn mul_magnitude(a: &[u8], b: &[u8]) -> Vec<u8> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += *x as u32 * *y as u32;
+        }
+        // carry as we go so the accumulators never overflow
+        let mut carry = 0;
+        for digit in product.iter_mut().skip(i) {
+            *digit += carry;
+            carry = *digit / 10;
+            *digit %= 10;
+        }
+    }
+    let mut product: Vec<u8> = product.into_iter().map(|digit| digit as u8).collect();
+    trim_leading(&mut product);
+    product
+}
+
+/// Quotient and remainder of `a / b` by long division, `b` non-zero
+fn divmod_magnitude(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder: Vec<u8> = Vec::new();
+    for i in (0..a.len()).rev() {
+        remainder.insert(0, a[i]);
+        trim_leading(&mut remainder);
+        let mut digit = 0;
+        while cmp_magnitude(&remainder, b) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, b);
+            digit += 1;
+        }
+        quotient[i] = digit;
+    }
+    trim_leading(&mut quotient);
+    (quotient, remainder)
+}
+
+/// Multiplies by `10^n`
+fn shift(digits: &[u8], n: usize) -> Vec<u8> {
+    if digits.is_empty() {
+        return Vec::new();
+    }
+    let mut shifted = vec![0; n];
+    shifted.extend_from_slice(digits);
+    shifted
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError;
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not a decimal number")
+    }
+}
+
+impl Decimal {
+    fn new(negative: bool, mut digits: Vec<u8>, mut scale: usize) -> Self {
+        let trailing = digits
+            .iter()
+            .take(scale)
+            .take_while(|digit| **digit == 0)
+            .count();
+        digits.drain(..trailing);
+        scale -= trailing;
+        trim_leading(&mut digits);
+        Self {
+            negative: negative && !digits.is_empty(),
+            digits,
+            scale,
+        }
+    }
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+    pub fn is_integer(&self) -> bool {
+        self.scale == 0
+    }
+    /// Total digits, used to keep exact results to a sensible size
+    pub fn len(&self) -> usize {
+        self.digits.len().max(self.scale)
+    }
+    pub fn neg(&self) -> Self {
+        Self::new(!self.negative, self.digits.clone(), self.scale)
+    }
+    pub fn abs(&self) -> Self {
+        Self::new(false, self.digits.clone(), self.scale)
+    }
+    /// Digits of both numbers at a common scale
+    fn aligned(&self, other: &Self) -> (Vec<u8>, Vec<u8>, usize) {
+        let scale = self.scale.max(other.scale);
+        (
+            shift(&self.digits, scale - self.scale),
+            shift(&other.digits, scale - other.scale),
+            scale,
+        )
+    }
+    pub fn add(&self, other: &Self) -> Self {
+        let (a, b, scale) = self.aligned(other);
+        if self.negative == other.negative {
+            return Self::new(self.negative, add_magnitude(&a, &b), scale);
+        }
+        match cmp_magnitude(&a, &b) {
+            Ordering::Less => Self::new(other.negative, sub_magnitude(&b, &a), scale),
+            _ => Self::new(self.negative, sub_magnitude(&a, &b), scale),
+        }
+    }
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::new(
+            self.negative != other.negative,
+            mul_magnitude(&self.digits, &other.digits),
+            self.scale + other.scale,
+        )
+    }
+    /// `self / other` to `DIVISION_DIGITS` fraction digits, with `true` when that is exact.
+    /// `None` for division by zero.
+    pub fn div(&self, other: &Self) -> Option<(Self, bool)> {
+        if other.is_zero() {
+            return None;
+        }
+        let numerator = shift(&self.digits, DIVISION_DIGITS + other.scale);
+        let denominator = shift(&other.digits, self.scale);
+        let (quotient, remainder) = divmod_magnitude(&numerator, &denominator);
+        Some((
+            Self::new(self.negative != other.negative, quotient, DIVISION_DIGITS),
+            remainder.is_empty(),
+        ))
+    }
+    /// Remainder with the sign of `self`, `None` for a zero divisor
+    pub fn rem(&self, other: &Self) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        let (a, b, scale) = self.aligned(other);
+        let (_, remainder) = divmod_magnitude(&a, &b);
+        Some(Self::new(self.negative, remainder, scale))
+    }
+    /// Integer power, `None` when the result would exceed `MAX_DIGITS` or divide by zero
+    pub fn powi(&self, exponent: i64) -> Option<(Self, bool)> {
+        if (self.len() as u64).saturating_mul(exponent.unsigned_abs()) > MAX_DIGITS as u64 {
+            return None;
+        }
+        let mut result = Self::from(1);
+        let mut base = self.clone();
+        let mut n = exponent.unsigned_abs();
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            n >>= 1;
+        }
+        if exponent < 0 {
+            Self::from(1).div(&result)
+        } else {
+            Some((result, true))
+        }
+    }
+    /// Rounds toward zero to `places` fraction digits
+    pub fn trunc(&self, places: usize) -> Self {
+        if self.scale <= places {
+            return self.clone();
+        }
+        let drop = self.scale - places;
+        let digits = self.digits.get(drop..).unwrap_or_default().to_vec();
+        Self::new(self.negative, digits, places)
+    }
+    pub fn floor(&self) -> Self {
+        let truncated = self.trunc(0);
+        if self.negative && truncated != *self {
+            truncated.sub(&Self::from(1))
+        } else {
+            truncated
+        }
+    }
+    pub fn ceil(&self) -> Self {
+        self.neg().floor().neg()
+    }
+    /// Rounds half away from zero to `places` fraction digits
+    pub fn round(&self, places: usize) -> Self {
+        let half = Self::new(self.negative, vec![5], places + 1);
+        self.add(&half).trunc(places)
+    }
+    pub fn to_i64(&self) -> Option<i64> {
+        if !self.is_integer() || self.digits.len() > 18 {
+            return None;
+        }
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0i64, |n, digit| n * 10 + *digit as i64);
+        Some(if self.negative { -magnitude } else { magnitude })
+    }
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+    /// The shortest decimal that reads back as `value`, `None` for infinities and NaN
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        format!("{:e}", value).parse().ok()
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        value.to_string().parse().expect("integers are decimals")
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    /// Parses `123`, `-0.5`, `1_000` or `6.02e23`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (
+                &s[..i],
+                s[i + 1..].parse::<i64>().map_err(|_| ParseDecimalError)?,
+            ),
+            None => (s, 0),
+        };
+        let mantissa = mantissa.replace('_', "");
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((&mantissa, ""));
+        if whole.is_empty() && fraction.is_empty()
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+            || exponent.unsigned_abs() > MAX_DIGITS as u64
+        {
+            return Err(ParseDecimalError);
+        }
+        let digits: Vec<u8> = whole
+            .bytes()
+            .chain(fraction.bytes())
+            .rev()
+            .map(|b| b - b'0')
+            .collect();
+        let scale = fraction.len() as i64 - exponent;
+        Ok(if scale >= 0 {
+            Self::new(negative, digits, scale as usize)
+        } else {
+            Self::new(negative, shift(&digits, scale.unsigned_abs() as usize), 0)
+        })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let whole: String = self
+            .digits
+            .iter()
+            .skip(self.scale)
+            .rev()
+            .map(|digit| (b'0' + digit) as char)
+            .collect();
+        write!(f, "{}", if whole.is_empty() { "0" } else { &whole })?;
+        if self.scale > 0 {
+            let fraction: String = (0..self.scale)
+                .rev()
+                .map(|i| (b'0' + self.digits.get(i).copied().unwrap_or(0)) as char)
+                .collect();
+            write!(f, ".{}", fraction)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (negative, _) => {
+                let (a, b, _) = self.aligned(other);
+                let ordering = cmp_magnitude(&a, &b);
+                if negative {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_decimal_arithmetic() {
+        assert_eq!(d("0.1").add(&d("0.2")), d("0.3"));
+        assert_eq!(d("1.50").sub(&d("2")).to_string(), "-0.5");
+        assert_eq!(d("-1.5e3").mul(&d("0.002")).to_string(), "-3");
+        assert_eq!(d("1").div(&d("8")), Some((d("0.125"), true)));
+        let (third, exact) = d("1").div(&d("3")).unwrap();
+        assert!(!exact);
+        assert_eq!(
+            third.to_string(),
+            format!("0.{}", "3".repeat(DIVISION_DIGITS))
+        );
+        assert_eq!(
+            d("2").powi(100).unwrap().0.to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(d("-2.5").floor(), d("-3"));
+        assert_eq!(d("2.345").round(2), d("2.35"));
+        assert_eq!(d("7.5").rem(&d("2")), Some(d("1.5")));
+        assert_eq!(Decimal::from_f64(0.1), Some(d("0.1")));
+        assert!(d("-1") < d("0.5"));
+    }
+}
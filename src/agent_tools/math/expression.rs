@@ -0,0 +1,640 @@
+use super::decimal::{Decimal, MAX_DIGITS};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+use thiserror::Error;
+
+const PI: &str = "3.1415926535897932384626433832795028841972";
+const E: &str = "2.7182818284590452353602874713526624977572";
+const TAU: &str = "6.2831853071795864769252867665590057683943";
+/// Largest factorial that stays within `MAX_DIGITS`, 450! has 1001 digits
+const MAX_FACTORIAL: i64 = 449;
+/// Parentheses, signs and exponents nested deeper than this are refused before the
+/// recursion can exhaust the stack
+const MAX_NESTING: usize = 100;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CalcError {
+    #[error("syntax error at character {position}: {message}")]
+    Syntax { position: usize, message: String },
+    #[error("unknown variable '{0}'")]
+    UnknownVariable(String),
+    #[error("unknown function '{0}'")]
+    UnknownFunction(String),
+    #[error("{function} takes {expected} argument(s) but was given {given}")]
+    WrongArity {
+        function: String,
+        expected: &'static str,
+        given: usize,
+    },
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("{value} is outside the domain of {function}")]
+    Domain { function: String, value: String },
+    #[error("the result is too large to represent")]
+    Overflow,
+}
+
+/// How far a result can be trusted, the least precise step wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    /// Every step was exact
+    Exact,
+    /// A division was cut off after `DIVISION_DIGITS` fraction digits
+    Rounded,
+    /// A step went through 64-bit floating point
+    Float,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Number {
+    pub value: Decimal,
+    pub precision: Precision,
+}
+
+impl Number {
+    pub fn exact(value: Decimal) -> Self {
+        Self {
+            value,
+            precision: Precision::Exact,
+        }
+    }
+    /// `None` for infinities and NaN
+    pub fn float(value: f64) -> Option<Self> {
+        Decimal::from_f64(value).map(|value| Self {
+            value,
+            precision: Precision::Float,
+        })
+    }
+    fn constant(digits: &str) -> Self {
+        Self {
+            value: digits.parse().expect("constants are decimals"),
+            precision: Precision::Rounded,
+        }
+    }
+    fn at_most(mut self, precision: Precision) -> Self {
+        self.precision = self.precision.max(precision);
+        self
+    }
+    /// Falls back to floating point when the exact value has grown past `MAX_DIGITS`
+    fn limited(self) -> Result<Self, CalcError> {
+        if self.value.len() <= MAX_DIGITS {
+            return Ok(self);
+        }
+        let value = self.value.to_f64();
+        if value.is_infinite() {
+            return Err(CalcError::Overflow);
+        }
+        Self::float(value).ok_or(CalcError::Overflow)
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.precision {
+            // digits past what an f64 holds would only be noise
+            Precision::Float => match Decimal::from_f64(self.value.to_f64()) {
+                Some(value) => write!(f, "{}", value),
+                None => write!(f, "{}", self.value),
+            },
+            _ => write!(f, "{}", self.value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(String),
+    Name(String),
+    Symbol(char),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    /// 1-based character position, for error messages
+    position: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let position = i + 1;
+        let kind = if c == '\n' {
+            i += 1;
+            TokenKind::Symbol(';')
+        } else if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            // an exponent only when digits follow, so `2e` stays a syntax error rather than 2·e
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                let sign = matches!(chars.get(i + 1), Some('+' | '-')) as usize;
+                if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            TokenKind::Number(chars[start..i].iter().collect())
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            TokenKind::Name(chars[start..i].iter().collect())
+        } else if c == '*' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            TokenKind::Symbol('^')
+        } else if "+-*/%^!(),=;".contains(c) {
+            i += 1;
+            TokenKind::Symbol(c)
+        } else {
+            return Err(CalcError::Syntax {
+                position,
+                message: format!("unexpected '{}'", c),
+            });
+        };
+        tokens.push(Token { kind, position });
+    }
+    tokens.push(Token {
+        kind: TokenKind::End,
+        position: chars.len() + 1,
+    });
+    Ok(tokens)
+}
+
+fn add(lhs: Number, rhs: Number) -> Result<Number, CalcError> {
+    Number::exact(lhs.value.add(&rhs.value))
+        .at_most(lhs.precision.max(rhs.precision))
+        .limited()
+}
+
+fn sub(lhs: Number, rhs: Number) -> Result<Number, CalcError> {
+    Number::exact(lhs.value.sub(&rhs.value))
+        .at_most(lhs.precision.max(rhs.precision))
+        .limited()
+}
+
+fn mul(lhs: Number, rhs: Number) -> Result<Number, CalcError> {
+    Number::exact(lhs.value.mul(&rhs.value))
+        .at_most(lhs.precision.max(rhs.precision))
+        .limited()
+}
+
+fn div(lhs: Number, rhs: Number) -> Result<Number, CalcError> {
+    let (value, exact) = lhs.value.div(&rhs.value).ok_or(CalcError::DivisionByZero)?;
+    let precision = if exact {
+        Precision::Exact
+    } else {
+        Precision::Rounded
+    };
+    Number::exact(value)
+        .at_most(lhs.precision.max(rhs.precision).max(precision))
+        .limited()
+}
+
+fn rem(lhs: Number, rhs: Number) -> Result<Number, CalcError> {
+    let value = lhs.value.rem(&rhs.value).ok_or(CalcError::DivisionByZero)?;
+    Ok(Number::exact(value).at_most(lhs.precision.max(rhs.precision)))
+}
+
+fn pow(base: Number, exponent: Number) -> Result<Number, CalcError> {
+    let precision = base.precision.max(exponent.precision);
+    if let Some(n) = exponent.value.to_i64() {
+        if base.value.is_zero() && n < 0 {
+            return Err(CalcError::DivisionByZero);
+        }
+        if let Some((value, exact)) = base.value.powi(n) {
+            let rounded = if exact {
+                Precision::Exact
+            } else {
+                Precision::Rounded
+            };
+            return Number::exact(value)
+                .at_most(precision.max(rounded))
+                .limited();
+        }
+    }
+    let (b, e) = (base.value.to_f64(), exponent.value.to_f64());
+    if b < 0.0 && e.fract() != 0.0 {
+        return Err(CalcError::Domain {
+            function: "a fractional power".to_string(),
+            value: base.to_string(),
+        });
+    }
+    let value = b.powf(e);
+    if value.is_infinite() {
+        return Err(CalcError::Overflow);
+    }
+    Number::float(value)
+        .map(|value| value.at_most(precision))
+        .ok_or(CalcError::Overflow)
+}
+
+fn factorial(n: Number) -> Result<Number, CalcError> {
+    let Some(k) = n.value.to_i64().filter(|k| *k >= 0) else {
+        return Err(CalcError::Domain {
+            function: "factorial".to_string(),
+            value: n.to_string(),
+        });
+    };
+    if k > MAX_FACTORIAL {
+        return Err(CalcError::Overflow);
+    }
+    let value = (2..=k).fold(Decimal::from(1), |product, i| {
+        product.mul(&Decimal::from(i))
+    });
+    Number::exact(value).at_most(n.precision).limited()
+}
+
+fn check_arity(
+    function: &str,
+    args: &[Number],
+    expected: &'static str,
+    valid: impl Fn(usize) -> bool,
+) -> Result<(), CalcError> {
+    if valid(args.len()) {
+        Ok(())
+    } else {
+        Err(CalcError::WrongArity {
+            function: function.to_string(),
+            expected,
+            given: args.len(),
+        })
+    }
+}
+
+/// Applies a floating point function, treating NaN and infinite results as domain errors
+fn float_fn(function: &str, x: &Number, f: impl Fn(f64) -> f64) -> Result<Number, CalcError> {
+    Number::float(f(x.value.to_f64()))
+        .map(|value| value.at_most(x.precision))
+        .ok_or_else(|| CalcError::Domain {
+            function: function.to_string(),
+            value: x.to_string(),
+        })
+}
+
+fn call(function: &str, mut args: Vec<Number>) -> Result<Number, CalcError> {
+    match function {
+        "min" | "max" => {
+            check_arity(function, &args, "at least 1", |n| n > 0)?;
+            let mut numbers = args.into_iter();
+            let first = numbers.next().expect("arity is checked");
+            Ok(numbers.fold(first, |best, n| {
+                let better = if function == "min" {
+                    n.value < best.value
+                } else {
+                    n.value > best.value
+                };
+                if better {
+                    n
+                } else {
+                    best
+                }
+            }))
+        }
+        "log" | "round" => {
+            check_arity(function, &args, "1 or 2", |n| n == 1 || n == 2)?;
+            let second = (args.len() == 2).then(|| args.remove(1));
+            let x = args.remove(0);
+            if function == "round" {
+                let places = match second {
+                    Some(places) => places
+                        .value
+                        .to_i64()
+                        .filter(|p| (0..=100).contains(p))
+                        .ok_or_else(|| CalcError::Domain {
+                            function: "round places".to_string(),
+                            value: places.to_string(),
+                        })? as usize,
+                    None => 0,
+                };
+                return Ok(Number::exact(x.value.round(places)).at_most(x.precision));
+            }
+            match second {
+                Some(base) => {
+                    let b = base.value.to_f64();
+                    float_fn(function, &x, |x| x.ln() / b.ln())
+                        .map(|value| value.at_most(base.precision))
+                }
+                None => float_fn(function, &x, f64::log10),
+            }
+        }
+        "atan2" => {
+            check_arity(function, &args, "2", |n| n == 2)?;
+            let x = args.remove(1);
+            let y = args.remove(0);
+            let x_value = x.value.to_f64();
+            float_fn(function, &y, |y| y.atan2(x_value)).map(|value| value.at_most(x.precision))
+        }
+        _ => {
+            check_arity(function, &args, "1", |n| n == 1)?;
+            let x = args.remove(0);
+            match function {
+                "abs" => Ok(Number::exact(x.value.abs()).at_most(x.precision)),
+                "floor" => Ok(Number::exact(x.value.floor()).at_most(x.precision)),
+                "ceil" => Ok(Number::exact(x.value.ceil()).at_most(x.precision)),
+                "sqrt" => {
+                    if x.value.is_negative() {
+                        return Err(CalcError::Domain {
+                            function: function.to_string(),
+                            value: x.to_string(),
+                        });
+                    }
+                    let root = float_fn(function, &x, f64::sqrt)?;
+                    // perfect squares stay exact
+                    if root.value.mul(&root.value) == x.value {
+                        Ok(Number::exact(root.value).at_most(x.precision))
+                    } else {
+                        Ok(root)
+                    }
+                }
+                "cbrt" => float_fn(function, &x, f64::cbrt),
+                "exp" => float_fn(function, &x, f64::exp),
+                "ln" => float_fn(function, &x, f64::ln),
+                "log2" => float_fn(function, &x, f64::log2),
+                "log10" => float_fn(function, &x, f64::log10),
+                "sin" => float_fn(function, &x, f64::sin),
+                "cos" => float_fn(function, &x, f64::cos),
+                "tan" => float_fn(function, &x, f64::tan),
+                "asin" => float_fn(function, &x, f64::asin),
+                "acos" => float_fn(function, &x, f64::acos),
+                "atan" => float_fn(function, &x, f64::atan),
+                "sinh" => float_fn(function, &x, f64::sinh),
+                "cosh" => float_fn(function, &x, f64::cosh),
+                "tanh" => float_fn(function, &x, f64::tanh),
+                "asinh" => float_fn(function, &x, f64::asinh),
+                "acosh" => float_fn(function, &x, f64::acosh),
+                "atanh" => float_fn(function, &x, f64::atanh),
+                "deg" => float_fn(function, &x, f64::to_degrees),
+                "rad" => float_fn(function, &x, f64::to_radians),
+                _ => Err(CalcError::UnknownFunction(function.to_string())),
+            }
+        }
+    }
+}
+
+/// Recursive descent evaluator, lowest precedence first:
+/// statement := name '=' expression | expression
+/// expression := term (('+' | '-') term)*
+/// term := unary (('*' | '/' | '%') unary)*
+/// unary := ('-' | '+') unary | power, at most `MAX_NESTING` deep
+/// power := postfix ('^' unary)?
+/// postfix := primary '!'*
+/// primary := number | name | name '(' arguments ')' | '(' expression ')'
+struct Evaluator<'a> {
+    tokens: Vec<Token>,
+    next: usize,
+    /// Calls of `unary` in progress, every recursion passes through it
+    depth: usize,
+    variables: &'a mut BTreeMap<String, Number>,
+}
+
+impl Evaluator<'_> {
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.next].kind
+    }
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].clone();
+        if token.kind != TokenKind::End {
+            self.next += 1;
+        }
+        token
+    }
+    fn eat(&mut self, symbol: char) -> bool {
+        if *self.peek() == TokenKind::Symbol(symbol) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn error(&self, message: impl Into<String>) -> CalcError {
+        CalcError::Syntax {
+            position: self.tokens[self.next].position,
+            message: message.into(),
+        }
+    }
+    fn expect(&mut self, symbol: char) -> Result<(), CalcError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", symbol)))
+        }
+    }
+
+    /// Evaluates every statement, returning the value of the last one
+    fn statements(&mut self) -> Result<Number, CalcError> {
+        let mut last = None;
+        loop {
+            while self.eat(';') {}
+            if *self.peek() == TokenKind::End {
+                break;
+            }
+            last = Some(self.statement()?);
+            if *self.peek() != TokenKind::End && !self.eat(';') {
+                return Err(self.error("expected an operator"));
+            }
+        }
+        last.ok_or_else(|| self.error("the expression is empty"))
+    }
+    fn statement(&mut self) -> Result<Number, CalcError> {
+        if let (TokenKind::Name(name), Some(TokenKind::Symbol('='))) = (
+            self.peek().clone(),
+            self.tokens.get(self.next + 1).map(|token| &token.kind),
+        ) {
+            self.next += 2;
+            let value = self.expression()?;
+            self.variables.insert(name, value.clone());
+            return Ok(value);
+        }
+        self.expression()
+    }
+    fn expression(&mut self) -> Result<Number, CalcError> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value = add(value, self.term()?)?;
+            } else if self.eat('-') {
+                value = sub(value, self.term()?)?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+    fn term(&mut self) -> Result<Number, CalcError> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value = mul(value, self.unary()?)?;
+            } else if self.eat('/') {
+                value = div(value, self.unary()?)?;
+            } else if self.eat('%') {
+                value = rem(value, self.unary()?)?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+    fn unary(&mut self) -> Result<Number, CalcError> {
+        if self.depth >= MAX_NESTING {
+            return Err(self.error(format!(
+                "the expression is nested more than {} levels deep",
+                MAX_NESTING
+            )));
+        }
+        self.depth += 1;
+        let value = self.signed();
+        self.depth -= 1;
+        value
+    }
+    fn signed(&mut self) -> Result<Number, CalcError> {
+        if self.eat('-') {
+            let value = self.unary()?;
+            Ok(Number {
+                value: value.value.neg(),
+                ..value
+            })
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+    fn power(&mut self) -> Result<Number, CalcError> {
+        let base = self.postfix()?;
+        if self.eat('^') {
+            pow(base, self.unary()?)
+        } else {
+            Ok(base)
+        }
+    }
+    fn postfix(&mut self) -> Result<Number, CalcError> {
+        let mut value = self.primary()?;
+        while self.eat('!') {
+            value = factorial(value)?;
+        }
+        Ok(value)
+    }
+    fn primary(&mut self) -> Result<Number, CalcError> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Number(digits) => {
+                digits
+                    .parse()
+                    .map(Number::exact)
+                    .map_err(|_| CalcError::Syntax {
+                        position: token.position,
+                        message: format!("'{}' is not a number", digits),
+                    })
+            }
+            TokenKind::Name(name) if self.eat('(') => {
+                let mut args = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                call(&name.to_lowercase(), args)
+            }
+            TokenKind::Name(name) => {
+                if let Some(value) = self.variables.get(&name) {
+                    return Ok(value.clone());
+                }
+                match name.to_lowercase().as_str() {
+                    "pi" | "π" => Ok(Number::constant(PI)),
+                    "e" => Ok(Number::constant(E)),
+                    "tau" | "τ" => Ok(Number::constant(TAU)),
+                    _ => Err(CalcError::UnknownVariable(name)),
+                }
+            }
+            TokenKind::Symbol('(') => {
+                let value = self.expression()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            TokenKind::End => Err(CalcError::Syntax {
+                position: token.position,
+                message: "the expression ends too early".to_string(),
+            }),
+            TokenKind::Symbol(c) => Err(CalcError::Syntax {
+                position: token.position,
+                message: format!("unexpected '{}'", c),
+            }),
+        }
+    }
+}
+
+/// Evaluates `input`, one or more statements separated by `;` or new lines. Assignments are
+/// stored in `variables`, and the value of the last statement is returned.
+pub fn evaluate(
+    input: &str,
+    variables: &mut BTreeMap<String, Number>,
+) -> Result<Number, CalcError> {
+    Evaluator {
+        tokens: tokenize(input)?,
+        next: 0,
+        depth: 0,
+        variables,
+    }
+    .statements()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(input: &str) -> Result<String, CalcError> {
+        evaluate(input, &mut BTreeMap::new()).map(|n| n.to_string())
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(eval("0.1 + 0.2"), Ok("0.3".to_string()));
+        assert_eq!(eval("-2^2 + 2**3 * (1 + 1)"), Ok("12".to_string()));
+        assert_eq!(eval("2^3^2"), Ok("512".to_string()));
+        assert_eq!(eval("7 % 3 + 5!"), Ok("121".to_string()));
+        assert_eq!(eval("x = 1.5\ny = x * 4; y / 8"), Ok("0.75".to_string()));
+        assert_eq!(eval("sqrt(144) + max(1, 3, 2)"), Ok("15".to_string()));
+        assert_eq!(eval("round(sin(pi / 2), 6)"), Ok("1".to_string()));
+        assert_eq!(eval("log(8, 2)"), Ok("3".to_string()));
+        assert_eq!(eval("1 / 0"), Err(CalcError::DivisionByZero));
+        assert_eq!(
+            eval("foo + 1"),
+            Err(CalcError::UnknownVariable("foo".to_string()))
+        );
+        assert!(matches!(eval("sqrt(-1)"), Err(CalcError::Domain { .. })));
+        assert!(matches!(
+            eval("2 * (3 + 4"),
+            Err(CalcError::Syntax { position: 11, .. })
+        ));
+        assert_eq!(eval("10^5000"), Err(CalcError::Overflow));
+        assert_eq!(eval("450!"), Err(CalcError::Overflow));
+        assert_eq!(
+            eval(&format!("{}1{}", "(".repeat(99), ")".repeat(99))),
+            Ok("1".to_string())
+        );
+        let deep = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert!(matches!(eval(&deep), Err(CalcError::Syntax { .. })));
+        assert!(matches!(
+            eval(&format!("{}1", "-".repeat(100_000))),
+            Err(CalcError::Syntax { .. })
+        ));
+    }
+}
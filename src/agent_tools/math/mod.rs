@@ -1,16 +1,12 @@
-use crate::agent_tools::math::{add::Add, divide::Divide, multiply::Multiply, subtract::Subtract};
+use crate::agent_tools::math::{calculate::Calculate, convert_units::ConvertUnits};
 use rig::tool::ToolDyn;
 
-pub mod add;
-pub mod divide;
-pub mod multiply;
-pub mod subtract;
+pub mod calculate;
+pub mod convert_units;
+pub mod decimal;
+pub mod expression;
+pub mod units;
 
 pub fn math_tools() -> Vec<Box<dyn ToolDyn>> {
-    vec![
-        Box::new(Add),
-        Box::new(Subtract),
-        Box::new(Multiply),
-        Box::new(Divide),
-    ]
+    vec![Box::new(Calculate), Box::new(ConvertUnits)]
 }
@@ -0,0 +1,570 @@
+use thiserror::Error;
+
+/// Exponents of length, mass, time, temperature and data
+pub type Dimension = [i8; 5];
+
+const BASE_DIMENSIONS: [&str; 5] = ["length", "mass", "time", "temperature", "data"];
+const NONE: Dimension = [0, 0, 0, 0, 0];
+const LENGTH: Dimension = [1, 0, 0, 0, 0];
+const AREA: Dimension = [2, 0, 0, 0, 0];
+const VOLUME: Dimension = [3, 0, 0, 0, 0];
+const MASS: Dimension = [0, 1, 0, 0, 0];
+const TIME: Dimension = [0, 0, 1, 0, 0];
+const FREQUENCY: Dimension = [0, 0, -1, 0, 0];
+const SPEED: Dimension = [1, 0, -1, 0, 0];
+const ACCELERATION: Dimension = [1, 0, -2, 0, 0];
+const FORCE: Dimension = [1, 1, -2, 0, 0];
+const PRESSURE: Dimension = [-1, 1, -2, 0, 0];
+const ENERGY: Dimension = [2, 1, -2, 0, 0];
+const POWER: Dimension = [2, 1, -3, 0, 0];
+const TEMPERATURE: Dimension = [0, 0, 0, 1, 0];
+const DATA: Dimension = [0, 0, 0, 0, 1];
+const DATA_RATE: Dimension = [0, 0, -1, 0, 1];
+
+/// Names for the dimensions people ask about, anything else is spelled out from the base ones
+const NAMED_DIMENSIONS: [(Dimension, &str); 17] = [
+    (NONE, "dimensionless"),
+    (LENGTH, "length"),
+    (AREA, "area"),
+    (VOLUME, "volume"),
+    (MASS, "mass"),
+    (TIME, "time"),
+    (FREQUENCY, "frequency"),
+    (SPEED, "speed"),
+    (ACCELERATION, "acceleration"),
+    (FORCE, "force"),
+    (PRESSURE, "pressure"),
+    (ENERGY, "energy"),
+    (POWER, "power"),
+    (TEMPERATURE, "temperature"),
+    (DATA, "data"),
+    (DATA_RATE, "data rate"),
+    ([-3, 1, 0, 0, 0], "density"),
+];
+
+#[derive(Debug, Error, PartialEq)]
+pub enum UnitError {
+    #[error("unknown unit '{0}'")]
+    UnknownUnit(String),
+    #[error("'{unit}' is not a valid unit: {message}")]
+    Invalid { unit: String, message: String },
+    #[error("cannot convert {from} ({from_dimension}) to {to} ({to_dimension})")]
+    IncompatibleDimensions {
+        from: String,
+        from_dimension: String,
+        to: String,
+        to_dimension: String,
+    },
+    #[error("the value must be a finite number")]
+    NotFinite,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Prefixes {
+    None,
+    Metric,
+    /// Only the multiplying metric prefixes, plus the binary ones
+    Data,
+}
+
+struct Unit {
+    /// Case sensitive symbols, prefixed by prefix symbols
+    symbols: &'static [&'static str],
+    /// Case insensitive names that may be plural, prefixed by prefix names
+    names: &'static [&'static str],
+    /// Size in SI base units, with bytes for data
+    factor: f64,
+    /// Added after scaling to reach the SI zero, only for temperatures
+    offset: f64,
+    dimension: Dimension,
+    prefixes: Prefixes,
+}
+
+const fn unit(
+    symbols: &'static [&'static str],
+    names: &'static [&'static str],
+    factor: f64,
+    dimension: Dimension,
+    prefixes: Prefixes,
+) -> Unit {
+    Unit {
+        symbols,
+        names,
+        factor,
+        offset: 0.0,
+        dimension,
+        prefixes,
+    }
+}
+
+const UNITS: &[Unit] = &[
+    // length
+    unit(&["m"], &["meter", "metre"], 1.0, LENGTH, Prefixes::Metric),
+    unit(
+        &["in", "\""],
+        &["inch", "inches"],
+        0.0254,
+        LENGTH,
+        Prefixes::None,
+    ),
+    unit(
+        &["ft", "'"],
+        &["foot", "feet"],
+        0.3048,
+        LENGTH,
+        Prefixes::None,
+    ),
+    unit(&["yd"], &["yard"], 0.9144, LENGTH, Prefixes::None),
+    unit(&["mi"], &["mile"], 1609.344, LENGTH, Prefixes::None),
+    unit(&["nmi"], &["nautical_mile"], 1852.0, LENGTH, Prefixes::None),
+    unit(
+        &["au"],
+        &["astronomical_unit"],
+        149_597_870_700.0,
+        LENGTH,
+        Prefixes::None,
+    ),
+    unit(
+        &["ly"],
+        &["light_year", "lightyear"],
+        9_460_730_472_580_800.0,
+        LENGTH,
+        Prefixes::None,
+    ),
+    unit(
+        &["pc"],
+        &["parsec"],
+        3.085_677_581_491_367e16,
+        LENGTH,
+        Prefixes::Metric,
+    ),
+    // area and volume
+    unit(&["ha"], &["hectare"], 10_000.0, AREA, Prefixes::None),
+    unit(&["ac"], &["acre"], 4_046.856_422_4, AREA, Prefixes::None),
+    unit(
+        &["L", "l"],
+        &["liter", "litre"],
+        0.001,
+        VOLUME,
+        Prefixes::Metric,
+    ),
+    unit(
+        &["gal"],
+        &["gallon"],
+        0.003_785_411_784,
+        VOLUME,
+        Prefixes::None,
+    ),
+    unit(
+        &["qt"],
+        &["quart"],
+        0.000_946_352_946,
+        VOLUME,
+        Prefixes::None,
+    ),
+    unit(
+        &["pt"],
+        &["pint"],
+        0.000_473_176_473,
+        VOLUME,
+        Prefixes::None,
+    ),
+    unit(
+        &["cup"],
+        &["cup"],
+        0.000_236_588_236_5,
+        VOLUME,
+        Prefixes::None,
+    ),
+    unit(
+        &["floz"],
+        &["fluid_ounce"],
+        0.000_029_573_529_562_5,
+        VOLUME,
+        Prefixes::None,
+    ),
+    // mass
+    unit(&["g"], &["gram", "gramme"], 0.001, MASS, Prefixes::Metric),
+    unit(
+        &["t"],
+        &["tonne", "metric_ton"],
+        1000.0,
+        MASS,
+        Prefixes::None,
+    ),
+    unit(
+        &["lb", "lbs"],
+        &["pound"],
+        0.453_592_37,
+        MASS,
+        Prefixes::None,
+    ),
+    unit(&["oz"], &["ounce"], 0.028_349_523_125, MASS, Prefixes::None),
+    unit(&["st"], &["stone"], 6.350_293_18, MASS, Prefixes::None),
+    // time
+    unit(&["s", "sec"], &["second"], 1.0, TIME, Prefixes::Metric),
+    unit(&["min"], &["minute"], 60.0, TIME, Prefixes::None),
+    unit(&["h", "hr"], &["hour"], 3600.0, TIME, Prefixes::None),
+    unit(&["d"], &["day"], 86_400.0, TIME, Prefixes::None),
+    unit(&["wk"], &["week"], 604_800.0, TIME, Prefixes::None),
+    // a twelfth of a Julian year
+    unit(&["mo"], &["month"], 2_629_800.0, TIME, Prefixes::None),
+    unit(&["yr", "a"], &["year"], 31_557_600.0, TIME, Prefixes::None),
+    // frequency and speed
+    unit(&["Hz"], &["hertz"], 1.0, FREQUENCY, Prefixes::Metric),
+    unit(&["rpm"], &[], 1.0 / 60.0, FREQUENCY, Prefixes::None),
+    unit(&["mph"], &[], 0.447_04, SPEED, Prefixes::None),
+    unit(&["kph"], &[], 1.0 / 3.6, SPEED, Prefixes::None),
+    unit(
+        &["kn", "kt"],
+        &["knot"],
+        1852.0 / 3600.0,
+        SPEED,
+        Prefixes::None,
+    ),
+    // force, pressure, energy and power
+    unit(&["N"], &["newton"], 1.0, FORCE, Prefixes::Metric),
+    unit(
+        &["lbf"],
+        &["pound_force"],
+        4.448_221_615_260_5,
+        FORCE,
+        Prefixes::None,
+    ),
+    unit(&["Pa"], &["pascal"], 1.0, PRESSURE, Prefixes::Metric),
+    unit(&["bar"], &["bar"], 100_000.0, PRESSURE, Prefixes::Metric),
+    unit(
+        &["atm"],
+        &["atmosphere"],
+        101_325.0,
+        PRESSURE,
+        Prefixes::None,
+    ),
+    unit(&["psi"], &[], 6_894.757_293_168, PRESSURE, Prefixes::None),
+    unit(&["mmHg"], &[], 133.322_387_415, PRESSURE, Prefixes::None),
+    unit(&["J"], &["joule"], 1.0, ENERGY, Prefixes::Metric),
+    unit(&["cal"], &["calorie"], 4.184, ENERGY, Prefixes::Metric),
+    unit(
+        &["eV"],
+        &["electronvolt"],
+        1.602_176_634e-19,
+        ENERGY,
+        Prefixes::Metric,
+    ),
+    unit(&["Wh"], &["watt_hour"], 3600.0, ENERGY, Prefixes::Metric),
+    unit(&["W"], &["watt"], 1.0, POWER, Prefixes::Metric),
+    unit(
+        &["hp"],
+        &["horsepower"],
+        745.699_871_582_270_2,
+        POWER,
+        Prefixes::None,
+    ),
+    // temperature, see `Temperature` for how offsets apply
+    unit(&["K"], &["kelvin"], 1.0, TEMPERATURE, Prefixes::Metric),
+    Unit {
+        symbols: &["degC", "°C", "C"],
+        names: &["celsius", "degree_celsius"],
+        factor: 1.0,
+        offset: 273.15,
+        dimension: TEMPERATURE,
+        prefixes: Prefixes::None,
+    },
+    Unit {
+        symbols: &["degF", "°F", "F"],
+        names: &["fahrenheit", "degree_fahrenheit"],
+        factor: 5.0 / 9.0,
+        offset: 273.15 - 32.0 * 5.0 / 9.0,
+        dimension: TEMPERATURE,
+        prefixes: Prefixes::None,
+    },
+    unit(
+        &["degR", "°R"],
+        &["rankine"],
+        5.0 / 9.0,
+        TEMPERATURE,
+        Prefixes::None,
+    ),
+    // data, in bytes
+    unit(&["B"], &["byte", "octet"], 1.0, DATA, Prefixes::Data),
+    unit(&["b", "bit"], &["bit"], 0.125, DATA, Prefixes::Data),
+    unit(&["bps"], &[], 0.125, DATA_RATE, Prefixes::Data),
+];
+
+const METRIC_PREFIXES: &[(&str, &str, f64)] = &[
+    ("Q", "quetta", 1e30),
+    ("R", "ronna", 1e27),
+    ("Y", "yotta", 1e24),
+    ("Z", "zetta", 1e21),
+    ("E", "exa", 1e18),
+    ("P", "peta", 1e15),
+    ("T", "tera", 1e12),
+    ("G", "giga", 1e9),
+    ("M", "mega", 1e6),
+    ("k", "kilo", 1e3),
+    // the usual spelling of kilo for bytes
+    ("K", "kilo", 1e3),
+    ("h", "hecto", 1e2),
+    ("da", "deca", 1e1),
+    ("da", "deka", 1e1),
+    ("d", "deci", 1e-1),
+    ("c", "centi", 1e-2),
+    ("m", "milli", 1e-3),
+    ("u", "micro", 1e-6),
+    ("µ", "micro", 1e-6),
+    ("μ", "micro", 1e-6),
+    ("n", "nano", 1e-9),
+    ("p", "pico", 1e-12),
+    ("f", "femto", 1e-15),
+    ("a", "atto", 1e-18),
+];
+
+const BINARY_PREFIXES: &[(&str, &str, f64)] = &[
+    ("Ki", "kibi", 1024.0),
+    ("Mi", "mebi", 1_048_576.0),
+    ("Gi", "gibi", 1_073_741_824.0),
+    ("Ti", "tebi", 1_099_511_627_776.0),
+    ("Pi", "pebi", 1_125_899_906_842_624.0),
+    ("Ei", "exbi", 1_152_921_504_606_846_976.0),
+];
+
+impl Unit {
+    /// Prefix symbols and names that may go before this unit, with their multiplier
+    fn prefixes(&self) -> Vec<(&'static str, &'static str, f64)> {
+        match self.prefixes {
+            Prefixes::None => Vec::new(),
+            Prefixes::Metric => METRIC_PREFIXES
+                .iter()
+                .filter(|(symbol, _, _)| *symbol != "K")
+                .copied()
+                .collect(),
+            Prefixes::Data => METRIC_PREFIXES
+                .iter()
+                .filter(|(_, _, factor)| *factor >= 1e3)
+                .chain(BINARY_PREFIXES)
+                .copied()
+                .collect(),
+        }
+    }
+    fn is_named(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        let singular = [
+            Some(name.as_str()),
+            name.strip_suffix('s'),
+            name.strip_suffix("es"),
+        ];
+        self.names
+            .iter()
+            .any(|unit_name| singular.contains(&Some(unit_name)))
+    }
+}
+
+/// Finds a unit by symbol, prefixed symbol, name or prefixed name, returning its multiplier
+fn lookup(name: &str) -> Option<(&'static Unit, f64)> {
+    if let Some(unit) = UNITS.iter().find(|unit| unit.symbols.contains(&name)) {
+        return Some((unit, 1.0));
+    }
+    for unit in UNITS {
+        for (symbol, _, factor) in unit.prefixes() {
+            if name
+                .strip_prefix(symbol)
+                .is_some_and(|rest| unit.symbols.contains(&rest))
+            {
+                return Some((unit, factor));
+            }
+        }
+    }
+    if let Some(unit) = UNITS.iter().find(|unit| unit.is_named(name)) {
+        return Some((unit, 1.0));
+    }
+    let lowercase = name.to_lowercase();
+    UNITS.iter().find_map(|unit| {
+        unit.prefixes().into_iter().find_map(|(_, prefix, factor)| {
+            lowercase
+                .strip_prefix(prefix)
+                .filter(|rest| unit.is_named(rest))
+                .map(|_| (unit, factor))
+        })
+    })
+}
+
+/// A unit expression such as `km/h` or `kg*m/s^2`, reduced to SI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub factor: f64,
+    pub dimension: Dimension,
+    /// The SI offset of a lone temperature unit, absent when temperatures are differences
+    temperature_offset: Option<f64>,
+}
+
+fn invalid(unit: &str, message: impl Into<String>) -> UnitError {
+    UnitError::Invalid {
+        unit: unit.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Splits `m^2`, `m²` or `s^-1` into the unit and its exponent
+fn split_exponent<'a>(text: &str, factor: &'a str) -> Result<(&'a str, i8), UnitError> {
+    if let Some((name, exponent)) = factor.split_once('^') {
+        let exponent = exponent
+            .trim_matches(|c| c == '(' || c == ')')
+            .parse()
+            .map_err(|_| invalid(text, format!("bad exponent in '{}'", factor)))?;
+        return Ok((name, exponent));
+    }
+    for (superscript, exponent) in [('²', 2), ('³', 3)] {
+        if let Some(name) = factor.strip_suffix(superscript) {
+            return Ok((name, exponent));
+        }
+    }
+    Ok((factor, 1))
+}
+
+impl Quantity {
+    pub fn parse(text: &str) -> Result<Self, UnitError> {
+        let normalized = text
+            .trim()
+            .replace(" per ", "/")
+            .replace(['(', ')'], " ")
+            .replace(['·', '⋅', '*'], " ");
+        // everything after the first '/' divides, so `J/kg/K` means J/(kg*K)
+        let (numerator, denominator) = normalized
+            .split_once('/')
+            .unwrap_or((normalized.as_str(), ""));
+        let mut quantity = Self {
+            factor: 1.0,
+            dimension: NONE,
+            temperature_offset: None,
+        };
+        let mut units = Vec::new();
+        for (part, sign) in [(numerator, 1), (denominator, -1)] {
+            for factor in part.split(['/', ' ']).filter(|factor| !factor.is_empty()) {
+                if factor == "1" {
+                    continue;
+                }
+                let (name, exponent) = split_exponent(text, factor)?;
+                let (unit, prefix) =
+                    lookup(name).ok_or_else(|| UnitError::UnknownUnit(name.to_string()))?;
+                let too_large =
+                    || invalid(text, format!("the exponent of '{}' is too large", factor));
+                let exponent = exponent.checked_mul(sign).ok_or_else(too_large)?;
+                quantity.factor *= (prefix * unit.factor).powi(exponent as i32);
+                for (dimension, unit_dimension) in quantity.dimension.iter_mut().zip(unit.dimension)
+                {
+                    *dimension = unit_dimension
+                        .checked_mul(exponent)
+                        .and_then(|exponent| dimension.checked_add(exponent))
+                        .ok_or_else(too_large)?;
+                }
+                units.push((unit, exponent));
+            }
+        }
+        if units.is_empty() {
+            return Err(invalid(text, "no units given"));
+        }
+        quantity.temperature_offset = match units.as_slice() {
+            [(unit, 1)] if unit.dimension == TEMPERATURE => Some(unit.offset),
+            _ => None,
+        };
+        Ok(quantity)
+    }
+
+    /// The name of the dimension, or its base dimensions such as `length^2/time`
+    pub fn dimension_name(&self) -> String {
+        if let Some((_, name)) = NAMED_DIMENSIONS
+            .iter()
+            .find(|(dimension, _)| *dimension == self.dimension)
+        {
+            return name.to_string();
+        }
+        let part = |positive: bool| {
+            BASE_DIMENSIONS
+                .iter()
+                .zip(self.dimension)
+                .filter(|(_, exponent)| *exponent != 0 && (*exponent > 0) == positive)
+                .map(|(name, exponent)| match exponent.abs() {
+                    1 => name.to_string(),
+                    n => format!("{}^{}", name, n),
+                })
+                .collect::<Vec<_>>()
+                .join("*")
+        };
+        match (part(true), part(false)) {
+            (numerator, denominator) if denominator.is_empty() => numerator,
+            (numerator, denominator) if numerator.is_empty() => format!("1/{}", denominator),
+            (numerator, denominator) => format!("{}/{}", numerator, denominator),
+        }
+    }
+}
+
+/// Converts `value` from one unit expression to another. Lone temperature units are absolute
+/// (0 °C is 32 °F), inside compound units they are differences (1 J/°C is 1 J/K).
+pub fn convert(value: f64, from: &str, to: &str) -> Result<(f64, Quantity), UnitError> {
+    if !value.is_finite() {
+        return Err(UnitError::NotFinite);
+    }
+    let source = Quantity::parse(from)?;
+    let target = Quantity::parse(to)?;
+    if source.dimension != target.dimension {
+        return Err(UnitError::IncompatibleDimensions {
+            from: from.to_string(),
+            from_dimension: source.dimension_name(),
+            to: to.to_string(),
+            to_dimension: target.dimension_name(),
+        });
+    }
+    let converted = match (source.temperature_offset, target.temperature_offset) {
+        (Some(from_offset), Some(to_offset)) => {
+            (value * source.factor + from_offset - to_offset) / target.factor
+        }
+        _ => value * source.factor / target.factor,
+    };
+    // drop the float noise the factors leave in the last digits
+    let converted = format!("{:.12e}", converted).parse().unwrap_or(converted);
+    Ok((converted, source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to(value: f64, from: &str, to: &str) -> f64 {
+        convert(value, from, to).unwrap().0
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(to(1.0, "mi", "ft"), 5280.0);
+        assert_eq!(to(100.0, "km/h", "m/s"), 27.77777777778);
+        assert_eq!(to(1.0, "KiB", "B"), 1024.0);
+        assert_eq!(to(1.0, "KB", "B"), 1000.0);
+        assert_eq!(to(1.0, "gibibytes", "megabytes"), 1073.741824);
+        assert_eq!(to(100.0, "Mbps", "MB/s"), 12.5);
+        assert_eq!(to(100.0, "degC", "°F"), 212.0);
+        assert_eq!(to(0.0, "K", "celsius"), -273.15);
+        assert_eq!(to(1.0, "kWh", "J"), 3_600_000.0);
+        assert_eq!(to(1.0, "N", "kg*m/s^2"), 1.0);
+        assert_eq!(to(2.0, "J/degC", "J/K"), 2.0);
+        assert_eq!(to(90.0, "minutes", "h"), 1.5);
+        assert_eq!(
+            convert(1.0, "km/h", "kg"),
+            Err(UnitError::IncompatibleDimensions {
+                from: "km/h".to_string(),
+                from_dimension: "speed".to_string(),
+                to: "kg".to_string(),
+                to_dimension: "mass".to_string(),
+            })
+        );
+        assert_eq!(
+            convert(1.0, "furlong", "m"),
+            Err(UnitError::UnknownUnit("furlong".to_string()))
+        );
+        for overflowing in ["1/m^-128", "m^100*m^100", "m^127/m^-1"] {
+            assert!(matches!(
+                Quantity::parse(overflowing),
+                Err(UnitError::Invalid { .. })
+            ));
+        }
+    }
+}
@@ -1,7 +1,17 @@
+pub mod approval;
+pub mod files;
 pub mod math;
+pub mod permissions;
+pub mod process;
+pub mod rust;
+pub mod shell;
+pub mod time;
 pub mod web;
+pub use files::FileTools;
 pub use math::math_tools;
-use thiserror::Error;
+pub use rust::rust_tools;
+pub use shell::shell_tools;
+pub use time::time_tools;
 pub use web::WebTools;
 
 use rig::tool::ToolError;
@@ -23,7 +33,3 @@ impl<T, E: ToToolError> ToToolResult<T, E> for Result<T, E> {
         self.map_err(|e| e.to_tool_err())
     }
 }
-
-#[derive(Debug, Error)]
-#[error("{0}")]
-pub(super) struct SomeError(String);
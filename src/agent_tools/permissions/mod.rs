@@ -0,0 +1,328 @@
+use crate::{
+    agent_tools::{
+        approval::{ask_approval, Approval},
+        files::{apply_patch::ApplyPatch, workspace::Workspace, write_file::WriteFile},
+        rust::run_rust::RunRust,
+        shell::run_command::RunCommand,
+    },
+    chat::CHATS_DIR_NAME,
+    ui::horizontal_line,
+};
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolDyn, ToolError},
+    wasm_compat::WasmBoxedFuture,
+};
+use rules::{Action, Rule};
+use serde_json::Value;
+use std::{
+    collections::BTreeSet,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+use thiserror::Error;
+
+pub mod rules;
+
+const PERMISSIONS_FILE_NAME: &str = "permissions.txt";
+/// Used until the user edits the policy
+pub const DEFAULT_RULES: &[&str] = &[
+    "ask crawl_website max_depth>2",
+    "ask crawl_website limit>50",
+    "ask map_website max_depth>3",
+];
+/// Characters of the arguments shown when asking about a call
+const ARGS_PREVIEW_LEN: usize = 1000;
+/// Arguments holding a path in the workspace, which `path=` conditions are checked against
+const PATH_FIELDS: &[&str] = &["path", "cwd"];
+/// Tools that show the whole command, program or diff and ask on their own. An `ask` rule
+/// leaves the asking to them instead of showing a cut-off preview of the arguments.
+const OWN_PROMPT_TOOLS: &[&str] = &[
+    RunCommand::NAME,
+    WriteFile::NAME,
+    ApplyPatch::NAME,
+    RunRust::NAME,
+];
+
+tokio::task_local! {
+    /// Set while a call runs that an `ask` rule matched
+    static RULE_ASKS: bool;
+}
+
+/// Whether an `ask` rule matched the running call, so a tool that asks on its own has to ask
+/// even where its own settings, such as `MARVIN_COMMAND_ALLOW`, would let the call through
+pub fn rule_asks() -> bool {
+    RULE_ASKS.try_with(|asks| *asks).unwrap_or(false)
+}
+
+#[derive(Debug, Error)]
+pub enum PermissionError {
+    #[error("the permission rule '{rule}' denies this {tool} call, do not retry it")]
+    Denied { tool: String, rule: String },
+    #[error("the user rejected this {0} call, ask them how to proceed instead of retrying")]
+    Rejected(String),
+}
+
+/// What the policy decided for a call, and the rule that decided it
+pub struct Decision {
+    pub action: Action,
+    pub rule: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    rules: Vec<Rule>,
+    /// Tool name and `ask` rule the user allowed for the rest of the session
+    remembered: BTreeSet<(String, String)>,
+}
+
+/// Which tool calls run, need confirmation or are refused. Rules are checked in order and the
+/// last one matching a call decides it, calls no rule matches are allowed.
+pub struct PermissionPolicy {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+fn permissions_path() -> PathBuf {
+    Path::new(CHATS_DIR_NAME).join(PERMISSIONS_FILE_NAME)
+}
+
+fn default_rules() -> Vec<Rule> {
+    DEFAULT_RULES
+        .iter()
+        .map(|rule| rule.parse().expect("default rules are valid"))
+        .collect()
+}
+
+impl PermissionPolicy {
+    /// Reads the rules saved by `/permissions`, one per line with `#` comments, or the defaults
+    pub fn load() -> anyhow::Result<Self> {
+        let path = permissions_path();
+        let rules = match std::fs::read_to_string(&path) {
+            Ok(text) => text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| {
+                    line.parse::<Rule>().map_err(|e| {
+                        anyhow::anyhow!("Invalid rule '{}' in {}: {}", line, path.display(), e)
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+            Err(e) if e.kind() == ErrorKind::NotFound => default_rules(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            state: Mutex::new(State {
+                rules,
+                remembered: BTreeSet::new(),
+            }),
+        })
+    }
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    fn save(&self, rules: &[Rule]) -> anyhow::Result<()> {
+        std::fs::create_dir_all(CHATS_DIR_NAME)?;
+        let mut text = String::from(
+            "# Tool permission rules, edited with /permissions. The last matching rule decides.\n",
+        );
+        for rule in rules {
+            text.push_str(&rule.to_string());
+            text.push('\n');
+        }
+        std::fs::write(&self.path, text)?;
+        Ok(())
+    }
+    pub fn rules(&self) -> Vec<String> {
+        self.lock().rules.iter().map(Rule::to_string).collect()
+    }
+    /// Calls the user allowed for the session, as tool name and rule
+    pub fn remembered(&self) -> Vec<(String, String)> {
+        self.lock().remembered.iter().cloned().collect()
+    }
+    /// Adds a rule after the others, so it wins over them
+    pub fn add(&self, rule: Rule) -> anyhow::Result<()> {
+        let mut state = self.lock();
+        state.rules.push(rule);
+        self.save(&state.rules)
+    }
+    /// Removes rule `index`, returning it
+    pub fn remove(&self, index: usize) -> anyhow::Result<Option<String>> {
+        let mut state = self.lock();
+        if index >= state.rules.len() {
+            return Ok(None);
+        }
+        let rule = state.rules.remove(index);
+        self.save(&state.rules)?;
+        Ok(Some(rule.to_string()))
+    }
+    pub fn reset(&self) -> anyhow::Result<()> {
+        let mut state = self.lock();
+        state.rules = default_rules();
+        self.save(&state.rules)
+    }
+    /// Forgets what the user allowed for the session
+    pub fn start_session(&self) {
+        self.lock().remembered.clear();
+    }
+    pub fn decide(&self, tool: &str, args: &Value) -> Decision {
+        let state = self.lock();
+        let Some(rule) = state
+            .rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(tool, args))
+        else {
+            return Decision {
+                action: Action::Allow,
+                rule: None,
+            };
+        };
+        let remembered = state
+            .remembered
+            .contains(&(tool.to_string(), rule.to_string()));
+        Decision {
+            action: match rule.action {
+                Action::Ask if remembered => Action::Allow,
+                action => action,
+            },
+            rule: Some(rule.to_string()),
+        }
+    }
+    fn remember(&self, tool: &str, rule: &str) {
+        self.lock()
+            .remembered
+            .insert((tool.to_string(), rule.to_string()));
+    }
+    /// Asks the user about a call an `ask` rule matched
+    fn confirm(&self, tool: &str, rule: &str, args: &Value) -> bool {
+        let args = serde_json::to_string_pretty(args).unwrap_or_default();
+        let preview: String = args.chars().take(ARGS_PREVIEW_LEN).collect();
+        println!();
+        horizontal_line();
+        println!(
+            "{} wants to run, which needs your approval ({}):",
+            tool, rule
+        );
+        println!("{}", preview);
+        if args.chars().count() > ARGS_PREVIEW_LEN {
+            println!("...");
+        }
+        horizontal_line();
+        match ask_approval(&format!("Allow this {} call?", tool)) {
+            Approval::Once => true,
+            Approval::Session => {
+                self.remember(tool, rule);
+                true
+            }
+            Approval::Denied => false,
+        }
+    }
+    /// Puts every tool behind the policy, with paths checked as they resolve in `workspace`
+    pub fn guard(
+        self: &Arc<Self>,
+        tools: Vec<Box<dyn ToolDyn>>,
+        workspace: &Arc<Workspace>,
+    ) -> Vec<Box<dyn ToolDyn>> {
+        tools
+            .into_iter()
+            .map(|inner| {
+                Box::new(Guarded {
+                    inner,
+                    policy: self.clone(),
+                    workspace: workspace.clone(),
+                }) as Box<dyn ToolDyn>
+            })
+            .collect()
+    }
+}
+
+/// Replaces each path argument with the workspace relative path the tool will use, so that
+/// `" .env"`, `src/../.env` or the absolute path all match `path=**/.env`. Paths that do not
+/// resolve are left as they are, the tool refuses them.
+fn normalize_paths(workspace: &Workspace, mut args: Value) -> Value {
+    for field in PATH_FIELDS {
+        if let Some(Value::String(path)) = args.get_mut(*field)
+            && let Ok(real) = workspace
+                .resolve(path)
+                .or_else(|_| workspace.resolve_new(path))
+        {
+            *path = workspace.relative(&real);
+        }
+    }
+    args
+}
+
+/// A tool that checks the policy before each call
+struct Guarded {
+    inner: Box<dyn ToolDyn>,
+    policy: Arc<PermissionPolicy>,
+    workspace: Arc<Workspace>,
+}
+
+impl ToolDyn for Guarded {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn definition<'a>(&'a self, prompt: String) -> WasmBoxedFuture<'a, ToolDefinition> {
+        self.inner.definition(prompt)
+    }
+
+    fn call<'a>(&'a self, args: String) -> WasmBoxedFuture<'a, Result<String, ToolError>> {
+        Box::pin(async move {
+            let tool = self.inner.name();
+            // unparseable arguments fail in the tool itself, with its own error
+            let parsed = serde_json::from_str(&args).unwrap_or(Value::Null);
+            let parsed = normalize_paths(&self.workspace, parsed);
+            let decision = self.policy.decide(&tool, &parsed);
+            let rule = decision.rule.unwrap_or_default();
+            let mut rule_asks = false;
+            match decision.action {
+                Action::Allow => {}
+                Action::Deny => {
+                    return Err(ToolError::ToolCallError(Box::new(
+                        PermissionError::Denied { tool, rule },
+                    )));
+                }
+                Action::Ask if OWN_PROMPT_TOOLS.contains(&tool.as_str()) => rule_asks = true,
+                Action::Ask => {
+                    if !self.policy.confirm(&tool, &rule, &parsed) {
+                        return Err(ToolError::ToolCallError(Box::new(
+                            PermissionError::Rejected(tool),
+                        )));
+                    }
+                }
+            }
+            RULE_ASKS.scope(rule_asks, self.inner.call(args)).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_tools::files::workspace::DEFAULT_FILE_SIZE_LIMIT;
+    use serde_json::json;
+
+    #[test]
+    fn test_paths_match_as_resolved() {
+        let root = std::env::temp_dir().join(format!("marvin-permissions-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join(".env"), "KEY=1").unwrap();
+        let workspace = Workspace::new(&root, DEFAULT_FILE_SIZE_LIMIT).unwrap();
+        let rule: Rule = "deny *_file path=**/.env".parse().unwrap();
+        let absolute = workspace.root().join(".env").display().to_string();
+        for path in [" .env", "src/../.env", "./src/../.env ", absolute.as_str()] {
+            let args = normalize_paths(&workspace, json!({ "path": path }));
+            assert_eq!(args["path"], ".env");
+            assert!(rule.matches("read_file", &args));
+        }
+        let args = normalize_paths(&workspace, json!({"path": "src/new.rs", "cwd": " src"}));
+        assert_eq!(args, json!({"path": "src/new.rs", "cwd": "src"}));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+use globset::{GlobBuilder, GlobMatcher};
+use serde_json::Value;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+use url::Url;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RuleError {
+    #[error("a rule needs an action and a tool, such as 'ask crawl_website'")]
+    Incomplete,
+    #[error("unknown action '{0}', use allow, ask or deny")]
+    UnknownAction(String),
+    #[error("'{0}' is not a condition, use field=pattern, field>number or field<number")]
+    InvalidCondition(String),
+    #[error("invalid pattern '{pattern}': {message}")]
+    InvalidPattern { pattern: String, message: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Allow,
+    Ask,
+    Deny,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Allow => "allow",
+            Self::Ask => "ask",
+            Self::Deny => "deny",
+        })
+    }
+}
+
+/// A test on the arguments of a tool call
+#[derive(Debug, Clone)]
+enum Condition {
+    /// Some URL in the arguments is on this domain or a subdomain of it
+    Domain(String),
+    /// `path` or `cwd`, made workspace relative by the guard, matches a glob where `*` stays
+    /// within a path segment
+    Path(GlobMatcher),
+    /// A string argument, or an element of a list argument, matches a glob
+    Matches(String, GlobMatcher),
+    /// A numeric argument is above the limit
+    Above(String, f64),
+    /// A numeric argument is below the limit
+    Below(String, f64),
+}
+
+/// One line of the policy, such as `ask crawl_website max_depth>2`
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub action: Action,
+    tool: GlobMatcher,
+    conditions: Vec<Condition>,
+    /// The rule as written, shown by `/permissions`
+    text: String,
+}
+
+fn glob(pattern: &str, literal_separator: bool) -> Result<GlobMatcher, RuleError> {
+    GlobBuilder::new(pattern)
+        .literal_separator(literal_separator)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| RuleError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: e.kind().to_string(),
+        })
+}
+
+impl FromStr for Condition {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RuleError::InvalidCondition(s.to_string());
+        if let Some((field, limit)) = s.split_once('>') {
+            return Ok(Self::Above(
+                field.to_string(),
+                limit.parse().map_err(|_| invalid())?,
+            ));
+        }
+        if let Some((field, limit)) = s.split_once('<') {
+            return Ok(Self::Below(
+                field.to_string(),
+                limit.parse().map_err(|_| invalid())?,
+            ));
+        }
+        match s.split_once('=').ok_or_else(invalid)? {
+            ("", _) | (_, "") => Err(invalid()),
+            ("domain", domain) => Ok(Self::Domain(domain.trim_start_matches("*.").to_lowercase())),
+            ("path", pattern) => Ok(Self::Path(glob(pattern, true)?)),
+            (field, pattern) => Ok(Self::Matches(field.to_string(), glob(pattern, false)?)),
+        }
+    }
+}
+
+/// Every string anywhere in the arguments
+fn strings(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(s) => vec![s.as_str()],
+        Value::Array(items) => items.iter().flat_map(strings).collect(),
+        Value::Object(fields) => fields.values().flat_map(strings).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The strings of one argument, itself or the elements of a list
+fn field_strings<'a>(args: &'a Value, field: &str) -> Vec<&'a str> {
+    match args.get(field) {
+        Some(Value::String(s)) => vec![s.as_str()],
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Condition {
+    fn matches(&self, args: &Value) -> bool {
+        match self {
+            Self::Domain(domain) => strings(args).into_iter().any(|s| {
+                Url::parse(s).is_ok_and(|url| {
+                    url.host_str().is_some_and(|host| {
+                        let host = host.to_lowercase();
+                        host == *domain || host.ends_with(&format!(".{}", domain))
+                    })
+                })
+            }),
+            Self::Path(glob) => ["path", "cwd"].iter().any(|field| {
+                field_strings(args, field)
+                    .into_iter()
+                    .any(|path| glob.is_match(path.trim_start_matches("./")))
+            }),
+            Self::Matches(field, glob) => field_strings(args, field)
+                .into_iter()
+                .any(|s| glob.is_match(s)),
+            Self::Above(field, limit) => args
+                .get(field)
+                .and_then(Value::as_f64)
+                .is_some_and(|n| n > *limit),
+            Self::Below(field, limit) => args
+                .get(field)
+                .and_then(Value::as_f64)
+                .is_some_and(|n| n < *limit),
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let (Some(action), Some(tool)) = (words.next(), words.next()) else {
+            return Err(RuleError::Incomplete);
+        };
+        let action = match action.to_lowercase().as_str() {
+            "allow" => Action::Allow,
+            "ask" => Action::Ask,
+            "deny" => Action::Deny,
+            _ => return Err(RuleError::UnknownAction(action.to_string())),
+        };
+        Ok(Self {
+            action,
+            tool: glob(tool, false)?,
+            conditions: words.map(str::parse).collect::<Result<_, _>>()?,
+            text: s.split_whitespace().collect::<Vec<_>>().join(" "),
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, tool: &str, args: &Value) -> bool {
+        self.tool.is_match(tool)
+            && self
+                .conditions
+                .iter()
+                .all(|condition| condition.matches(args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rules() {
+        let crawl: Rule = "ask crawl_website max_depth>2".parse().unwrap();
+        assert_eq!(crawl.action, Action::Ask);
+        assert!(crawl.matches(
+            "crawl_website",
+            &json!({"url": "https://a.io", "max_depth": 3})
+        ));
+        assert!(!crawl.matches(
+            "crawl_website",
+            &json!({"url": "https://a.io", "max_depth": 2})
+        ));
+        assert!(!crawl.matches("crawl_website", &json!({"url": "https://a.io"})));
+        assert!(!crawl.matches("map_website", &json!({"max_depth": 3})));
+
+        let docs: Rule = "allow * domain=*.rust-lang.org".parse().unwrap();
+        assert!(docs.matches(
+            "extract_url",
+            &json!({"urls": ["https://doc.rust-lang.org/std"]})
+        ));
+        assert!(docs.matches("crawl_website", &json!({"url": "https://rust-lang.org"})));
+        assert!(!docs.matches(
+            "crawl_website",
+            &json!({"url": "https://evilrust-lang.org"})
+        ));
+
+        let env: Rule = "deny *_file path=**/.env".parse().unwrap();
+        assert!(env.matches("read_file", &json!({"path": "./config/.env"})));
+        assert!(env.matches("write_file", &json!({"path": ".env"})));
+        assert!(!env.matches("list_dir", &json!({"path": ".env"})));
+
+        let git: Rule = "allow run_command command=git*".parse().unwrap();
+        assert!(git.matches("run_command", &json!({"command": "git log -- src/main.rs"})));
+        assert_eq!(git.to_string(), "allow run_command command=git*");
+
+        assert_eq!("ask".parse::<Rule>().unwrap_err(), RuleError::Incomplete);
+        assert!(matches!(
+            "maybe x".parse::<Rule>(),
+            Err(RuleError::UnknownAction(_))
+        ));
+        assert!(matches!(
+            "deny x depth>deep".parse::<Rule>(),
+            Err(RuleError::InvalidCondition(_))
+        ));
+    }
+}
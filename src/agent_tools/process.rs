@@ -0,0 +1,159 @@
+use std::{
+    collections::VecDeque,
+    process::Stdio,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    process::Command,
+    task::JoinHandle,
+};
+
+/// How long to wait for output after a process exits, in case something it started in the
+/// background keeps the pipes open
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What a process printed and how it ended
+pub struct ProcessOutput {
+    /// `None` when the process was killed, by the timeout or a signal
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration: Duration,
+    pub stdout: String,
+    pub stderr: String,
+    /// Set when the middle of stdout or stderr was left out
+    pub truncated: bool,
+}
+
+/// The start and end of a stream, with the size of what was left out between them
+struct Captured {
+    limit: usize,
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    omitted: usize,
+}
+
+impl Captured {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            head: Vec::new(),
+            tail: VecDeque::new(),
+            omitted: 0,
+        }
+    }
+    fn push(&mut self, bytes: &[u8]) {
+        let head_room = (self.limit / 2).saturating_sub(self.head.len());
+        let (head, rest) = bytes.split_at(head_room.min(bytes.len()));
+        self.head.extend_from_slice(head);
+        self.tail.extend(rest);
+        let excess = self.tail.len().saturating_sub(self.limit / 2);
+        self.tail.drain(..excess);
+        self.omitted += excess;
+    }
+    fn into_text(mut self) -> (String, bool) {
+        let mut text = String::from_utf8_lossy(&self.head).into_owned();
+        if self.omitted > 0 {
+            text.push_str(&format!("\n[... {} bytes left out ...]\n", self.omitted));
+        }
+        text.push_str(&String::from_utf8_lossy(self.tail.make_contiguous()));
+        (text, self.omitted > 0)
+    }
+}
+
+/// Reads a stream to its end in the background, keeping what `Captured` keeps
+fn capture(
+    mut stream: impl AsyncRead + Unpin + Send + 'static,
+    limit: usize,
+) -> JoinHandle<Captured> {
+    tokio::spawn(async move {
+        let mut captured = Captured::new(limit);
+        let mut buffer = [0; 8192];
+        while let Ok(n) = stream.read(&mut buffer).await {
+            if n == 0 {
+                break;
+            }
+            captured.push(&buffer[..n]);
+        }
+        captured
+    })
+}
+
+/// Waits briefly for a capture to finish, giving up on output that never ends
+async fn drain(handle: Option<JoinHandle<Captured>>, limit: usize) -> Captured {
+    let Some(mut handle) = handle else {
+        return Captured::new(limit);
+    };
+    match tokio::time::timeout(DRAIN_TIMEOUT, &mut handle).await {
+        Ok(captured) => captured.unwrap_or_else(|_| Captured::new(limit)),
+        Err(_) => {
+            handle.abort();
+            Captured::new(limit)
+        }
+    }
+}
+
+/// Runs `command` without stdin, killing it and everything it started once `timeout` passes.
+/// Up to `output_limit` bytes of each stream are kept, half from the start and half from the
+/// end.
+pub async fn run_process(
+    mut command: Command,
+    timeout: Duration,
+    output_limit: usize,
+) -> std::io::Result<ProcessOutput> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // its own process group, so a timeout kills what the process started as well
+    #[cfg(unix)]
+    command.process_group(0);
+    let started = Instant::now();
+    let mut child = command.spawn()?;
+    let stdout = child.stdout.take().map(|s| capture(s, output_limit));
+    let stderr = child.stderr.take().map(|s| capture(s, output_limit));
+    let (status, timed_out) = match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(status) => (status.ok(), false),
+        Err(_) => {
+            #[cfg(unix)]
+            if let Some(pid) = child.id() {
+                // SAFETY: only sends a signal, to the group the child leads
+                unsafe {
+                    libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+                }
+            }
+            let _ = child.kill().await;
+            (None, true)
+        }
+    };
+    let duration = started.elapsed();
+    let (stdout, stdout_truncated) = drain(stdout, output_limit).await.into_text();
+    let (stderr, stderr_truncated) = drain(stderr, output_limit).await.into_text();
+    Ok(ProcessOutput {
+        exit_code: status.and_then(|status| status.code()),
+        timed_out,
+        duration,
+        stdout,
+        stderr,
+        truncated: stdout_truncated || stderr_truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captured_keeps_head_and_tail() {
+        let mut captured = Captured::new(1024);
+        for _ in 0..1024 {
+            captured.push(b"ab");
+        }
+        let (text, truncated) = captured.into_text();
+        assert!(truncated);
+        assert!(text.starts_with("abab"));
+        assert!(text.ends_with("abab"));
+        assert!(text.contains("[... 1024 bytes left out ...]"));
+    }
+}
@@ -0,0 +1,176 @@
+use super::{
+    diagnostics::{parse_cargo_messages, Diagnostic},
+    libtest::{parse_test_output, TestSummary},
+    run_rust::RustError,
+};
+use crate::agent_tools::{process::run_process, ToToolResult};
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
+use tokio::process::Command;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const MAX_TIMEOUT_SECS: u64 = 1800;
+/// Bytes kept of cargo's stdout, which holds the JSON messages and the test output
+const OUTPUT_LIMIT: usize = 8 * 1024 * 1024;
+/// Diagnostics returned, errors first
+const MAX_DIAGNOSTICS: usize = 100;
+/// Characters of stderr returned when cargo failed without diagnostics or failing tests
+const MAX_STDERR: usize = 4000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CargoCommand {
+    Check,
+    Clippy,
+    Test,
+}
+
+impl CargoCommand {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Check => "check",
+            Self::Clippy => "clippy",
+            Self::Test => "test",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct CargoArgs {
+    pub command: CargoCommand,
+    #[schemars(description = "Package to run for, in a workspace. Default: all packages")]
+    pub package: Option<String>,
+    #[schemars(description = "For test: only run tests whose names contain this")]
+    pub test_filter: Option<String>,
+    #[schemars(description = "Include tests, examples and benches. Default: true")]
+    pub all_targets: Option<bool>,
+    #[schemars(description = "Seconds before cargo is killed (1-1800). Default: 300")]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct CargoOutput {
+    /// The command line that ran
+    pub command: String,
+    pub success: bool,
+    pub timed_out: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Set when more than `MAX_DIAGNOSTICS` were reported
+    pub diagnostics_truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tests: Option<TestSummary>,
+    /// Only when nothing else explains a failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+/// Runs cargo in the project from `MARVIN_CARGO_PROJECT`
+pub struct Cargo {
+    project: PathBuf,
+}
+
+impl Cargo {
+    pub fn new(project: PathBuf) -> Self {
+        Self { project }
+    }
+    async fn run(&self, args: CargoArgs) -> Result<CargoOutput, RustError> {
+        if !self.project.join("Cargo.toml").is_file() {
+            return Err(RustError::NoProject(self.project.clone()));
+        }
+        let mut arguments = vec![
+            args.command.as_str().to_string(),
+            "--message-format=json".to_string(),
+        ];
+        if let Some(package) = &args.package {
+            arguments.extend(["--package".to_string(), package.clone()]);
+        } else {
+            arguments.push("--workspace".to_string());
+        }
+        if args.all_targets.unwrap_or(true) {
+            arguments.push("--all-targets".to_string());
+        }
+        if let (CargoCommand::Test, Some(filter)) = (args.command, &args.test_filter) {
+            // a filter after -- reaches every test binary
+            arguments.extend(["--".to_string(), filter.clone()]);
+        }
+        let timeout = Duration::from_secs(
+            args.timeout_secs
+                .unwrap_or(DEFAULT_TIMEOUT_SECS)
+                .clamp(1, MAX_TIMEOUT_SECS),
+        );
+        let mut cargo = Command::new("cargo");
+        cargo
+            .args(&arguments)
+            .current_dir(&self.project)
+            .env("CARGO_TERM_COLOR", "never");
+        let ran = run_process(cargo, timeout, OUTPUT_LIMIT).await?;
+
+        let mut messages = parse_cargo_messages(&ran.stdout);
+        // errors first, in the order cargo reported them
+        messages
+            .diagnostics
+            .sort_by_key(|diagnostic| !diagnostic.level.starts_with("error"));
+        let errors = messages.errors();
+        let warnings = messages
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == "warning")
+            .count();
+        let diagnostics_truncated = messages.diagnostics.len() > MAX_DIAGNOSTICS;
+        messages.diagnostics.truncate(MAX_DIAGNOSTICS);
+        let tests = matches!(args.command, CargoCommand::Test)
+            .then(|| parse_test_output(&ran.stdout))
+            .filter(|tests| tests.passed + tests.failed + tests.ignored > 0);
+        let success = ran.exit_code == Some(0);
+        let explained = errors > 0 || tests.as_ref().is_some_and(|tests| tests.failed > 0);
+        let stderr = (!success && !explained).then(|| {
+            let stderr = ran.stderr.trim();
+            let start = stderr
+                .char_indices()
+                .rev()
+                .nth(MAX_STDERR)
+                .map_or(0, |(i, _)| i);
+            stderr[start..].to_string()
+        });
+        Ok(CargoOutput {
+            command: format!("cargo {}", arguments.join(" ")),
+            success,
+            timed_out: ran.timed_out,
+            errors,
+            warnings,
+            diagnostics: messages.diagnostics,
+            diagnostics_truncated,
+            tests,
+            stderr,
+        })
+    }
+}
+
+impl Tool for Cargo {
+    const NAME: &'static str = "cargo";
+    type Args = CargoArgs;
+    type Output = CargoOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: format!(
+                "Run cargo check, clippy or test in the Rust project at {}. Returns each compiler error and warning with its file, span, level, code and rendered message, and for tests the counts and the failing tests with their output.",
+                self.project.display()
+            ),
+            parameters: serde_json::to_value(schema_for!(CargoArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.run(args).await.to_tool_result()
+    }
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::PathBuf};
+
+/// Where a diagnostic points, 1-based and inclusive like rustc reports it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Span {
+    pub line_start: usize,
+    pub column_start: usize,
+    pub line_end: usize,
+    pub column_end: usize,
+}
+
+/// A compiler error, warning or note, reduced to what the agent needs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// The label rustc puts on the primary span
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The diagnostic as rustc prints it, with the source excerpt and help notes
+    pub rendered: String,
+}
+
+/// What a `cargo --message-format=json` run reported
+#[derive(Debug, Default)]
+pub struct CargoMessages {
+    pub diagnostics: Vec<Diagnostic>,
+    /// Binaries that were built, the last one first
+    pub executables: Vec<PathBuf>,
+    pub success: Option<bool>,
+}
+
+impl CargoMessages {
+    pub fn errors(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level.starts_with("error"))
+            .count()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum CargoMessage {
+    CompilerMessage {
+        message: RustcDiagnostic,
+    },
+    CompilerArtifact {
+        executable: Option<PathBuf>,
+    },
+    BuildFinished {
+        success: bool,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    code: Option<RustcCode>,
+    level: String,
+    spans: Vec<RustcSpan>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+    label: Option<String>,
+}
+
+/// Whether a diagnostic is only rustc's closing summary, such as "aborting due to 2 previous
+/// errors", "3 warnings emitted" or the pointer to `rustc --explain`
+fn is_summary(diagnostic: &RustcDiagnostic) -> bool {
+    diagnostic.level == "failure-note"
+        || diagnostic.spans.is_empty()
+            && (diagnostic.message.starts_with("aborting due to")
+                || diagnostic.message.ends_with("emitted"))
+}
+
+impl From<RustcDiagnostic> for Diagnostic {
+    fn from(value: RustcDiagnostic) -> Self {
+        let primary = value
+            .spans
+            .iter()
+            .find(|span| span.is_primary)
+            .or(value.spans.first());
+        Self {
+            level: value.level,
+            code: value.code.map(|code| code.code),
+            file: primary.map(|span| span.file_name.clone()),
+            span: primary.map(|span| Span {
+                line_start: span.line_start,
+                column_start: span.column_start,
+                line_end: span.line_end,
+                column_end: span.column_end,
+            }),
+            label: primary.and_then(|span| span.label.clone()),
+            rendered: value
+                .rendered
+                .unwrap_or_else(|| value.message.clone())
+                .trim_end()
+                .to_string(),
+            message: value.message,
+        }
+    }
+}
+
+/// Reads the JSON lines cargo prints, skipping anything else and diagnostics seen before, which
+/// cargo repeats when a file is built for several targets
+pub fn parse_cargo_messages(stdout: &str) -> CargoMessages {
+    let mut messages = CargoMessages::default();
+    let mut seen = HashSet::new();
+    for line in stdout.lines().filter(|line| line.starts_with('{')) {
+        match serde_json::from_str(line) {
+            Ok(CargoMessage::CompilerMessage { message }) if !is_summary(&message) => {
+                let diagnostic = Diagnostic::from(message);
+                if seen.insert(diagnostic.rendered.clone()) {
+                    messages.diagnostics.push(diagnostic);
+                }
+            }
+            Ok(CargoMessage::CompilerArtifact {
+                executable: Some(executable),
+            }) => messages.executables.insert(0, executable),
+            Ok(CargoMessage::BuildFinished { success }) => messages.success = Some(success),
+            _ => {}
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_messages() {
+        let stdout = r#"{"reason":"compiler-message","package_id":"snippet","message":{"rendered":"error[E0382]: borrow of moved value: `s`\n --> src/main.rs:4:20\n","$message_type":"diagnostic","children":[],"code":{"code":"E0382","explanation":"..."},"level":"error","message":"borrow of moved value: `s`","spans":[{"byte_end":50,"byte_start":49,"column_end":21,"column_start":20,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":"value borrowed here after move","line_end":4,"line_start":4,"suggested_replacement":null,"suggestion_applicability":null,"text":[]}]}}
+{"reason":"compiler-message","package_id":"snippet","message":{"rendered":"error: aborting due to 1 previous error\n","children":[],"code":null,"level":"error","message":"aborting due to 1 previous error","spans":[]}}
+   Compiling snippet v0.1.0
+{"reason":"compiler-artifact","package_id":"snippet","executable":null,"fresh":false}
+{"reason":"build-finished","success":false}"#;
+        let messages = parse_cargo_messages(stdout);
+        assert_eq!(messages.success, Some(false));
+        assert_eq!(messages.errors(), 1);
+        assert!(messages.executables.is_empty());
+        let diagnostic = &messages.diagnostics[0];
+        assert_eq!(diagnostic.code.as_deref(), Some("E0382"));
+        assert_eq!(diagnostic.file.as_deref(), Some("src/main.rs"));
+        assert_eq!(
+            diagnostic.span,
+            Some(Span {
+                line_start: 4,
+                column_start: 20,
+                line_end: 4,
+                column_end: 21
+            })
+        );
+        assert_eq!(
+            diagnostic.label.as_deref(),
+            Some("value borrowed here after move")
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use serde::Serialize;
+
+/// Characters kept of each failing test's output
+const MAX_FAILURE_OUTPUT: usize = 4000;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FailedTest {
+    pub name: String,
+    /// What the test printed, including the panic message
+    pub output: String,
+}
+
+/// Totals over every test binary and doc test run
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub failures: Vec<FailedTest>,
+}
+
+/// The number before `label` in a libtest result line such as
+/// `test result: FAILED. 3 passed; 1 failed; 0 ignored; ...`
+fn count(line: &str, label: &str) -> usize {
+    line.split(';')
+        .find_map(|part| {
+            let (number, name) = part.trim().rsplit_once(' ')?;
+            let number = number.rsplit(' ').next()?;
+            (name == label).then(|| number.parse().ok())?
+        })
+        .unwrap_or(0)
+}
+
+/// Reads libtest's human readable output: the `test result:` lines and the `---- name stdout
+/// ----` sections listing what failing tests printed. Failing tests without a section, such as
+/// ones that timed out, are listed with empty output.
+pub fn parse_test_output(stdout: &str) -> TestSummary {
+    let mut summary = TestSummary::default();
+    let mut failed_names = Vec::new();
+    let mut current: Option<FailedTest> = None;
+    for line in stdout.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            summary.failures.extend(current.take());
+            current = Some(FailedTest {
+                name: name.to_string(),
+                output: String::new(),
+            });
+            continue;
+        }
+        if line == "failures:" || line.starts_with("test result:") {
+            summary.failures.extend(current.take());
+        }
+        if line.starts_with("test result:") {
+            summary.passed += count(line, "passed");
+            summary.failed += count(line, "failed");
+            summary.ignored += count(line, "ignored");
+        } else if let Some(name) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.strip_suffix(" ... FAILED"))
+        {
+            failed_names.push(name.to_string());
+        } else if let Some(test) = current.as_mut() {
+            test.output.push_str(line);
+            test.output.push('\n');
+        }
+    }
+    summary.failures.extend(current);
+    for name in failed_names {
+        if !summary.failures.iter().any(|test| test.name == name) {
+            summary.failures.push(FailedTest {
+                name,
+                output: String::new(),
+            });
+        }
+    }
+    for test in &mut summary.failures {
+        let trimmed = test.output.trim();
+        test.output = match trimmed.char_indices().nth(MAX_FAILURE_OUTPUT) {
+            Some((end, _)) => format!("{}\n[... cut ...]", &trimmed[..end]),
+            None => trimmed.to_string(),
+        };
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_test_output() {
+        let stdout = "
+running 3 tests
+test math::tests::test_add ... ok
+test math::tests::test_div ... FAILED
+test slow ... ignored
+
+failures:
+
+---- math::tests::test_div stdout ----
+
+thread 'math::tests::test_div' panicked at src/math.rs:10:9:
+assertion `left == right` failed
+  left: 2
+ right: 3
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    math::tests::test_div
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+running 1 test
+test src/lib.rs - add (line 3) ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.20s
+";
+        let summary = parse_test_output(stdout);
+        assert_eq!(summary.passed, 2);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.ignored, 1);
+        assert_eq!(summary.failures.len(), 1);
+        assert_eq!(summary.failures[0].name, "math::tests::test_div");
+        assert!(summary.failures[0]
+            .output
+            .starts_with("thread 'math::tests::test_div' panicked at src/math.rs:10:9:"));
+        assert!(summary.failures[0].output.ends_with("display a backtrace"));
+    }
+}
@@ -0,0 +1,13 @@
+use cargo::Cargo;
+use rig::tool::ToolDyn;
+use run_rust::RunRust;
+use std::path::PathBuf;
+
+pub mod cargo;
+pub mod diagnostics;
+pub mod libtest;
+pub mod run_rust;
+
+pub fn rust_tools(project: PathBuf) -> Vec<Box<dyn ToolDyn>> {
+    vec![Box::new(RunRust), Box::new(Cargo::new(project))]
+}
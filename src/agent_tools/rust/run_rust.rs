@@ -0,0 +1,330 @@
+use super::diagnostics::{parse_cargo_messages, Diagnostic};
+use crate::{
+    agent_tools::{
+        approval::{ask_approval, Approval},
+        process::run_process,
+        ToToolResult,
+    },
+    ui::horizontal_line,
+};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::DirBuilder,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::process::Command;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const MAX_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_MEMORY_LIMIT_MB: u64 = 512;
+const MAX_MEMORY_LIMIT_MB: u64 = 4096;
+const COMPILE_TIMEOUT: Duration = Duration::from_secs(120);
+/// Bytes kept of cargo's JSON messages, enough that diagnostics are not cut
+const BUILD_OUTPUT_LIMIT: usize = 4 * 1024 * 1024;
+/// Bytes kept of each stream of the program, half from the start and half from the end
+const RUN_OUTPUT_LIMIT: usize = 16 * 1024;
+
+/// Set when the user lets programs run without asking for the rest of the session
+static APPROVED_FOR_SESSION: AtomicBool = AtomicBool::new(false);
+
+/// Asks again before the next program
+pub fn start_session() {
+    APPROVED_FOR_SESSION.store(false, Ordering::Relaxed);
+}
+
+#[derive(Debug, Error)]
+pub enum RustError {
+    #[error("compiling took longer than {} seconds", COMPILE_TIMEOUT.as_secs())]
+    CompileTimeout,
+    #[error("cargo failed without compiler errors: {0}")]
+    Cargo(String),
+    #[error("no Cargo.toml in {0}, set MARVIN_CARGO_PROJECT to a Rust project")]
+    NoProject(PathBuf),
+    #[error("cargo produced no executable")]
+    NoExecutable,
+    #[error("the user rejected running this program, ask them how to proceed instead of retrying")]
+    Rejected,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema)]
+pub enum Edition {
+    #[serde(rename = "2015")]
+    E2015,
+    #[serde(rename = "2018")]
+    E2018,
+    #[serde(rename = "2021")]
+    E2021,
+    #[default]
+    #[serde(rename = "2024")]
+    E2024,
+}
+
+impl Edition {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::E2015 => "2015",
+            Self::E2018 => "2018",
+            Self::E2021 => "2021",
+            Self::E2024 => "2024",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct RunRustArgs {
+    #[schemars(
+        description = "Source of src/main.rs. Only std is available. Code without `fn main` is wrapped in one"
+    )]
+    pub code: String,
+    #[schemars(description = "Rust edition. Default: 2024")]
+    pub edition: Option<Edition>,
+    #[schemars(description = "Build with optimizations. Default: false")]
+    pub release: Option<bool>,
+    #[schemars(description = "Seconds the program may run (1-60). Default: 10")]
+    pub timeout_secs: Option<u64>,
+    #[schemars(description = "Megabytes of memory the program may use (16-4096). Default: 512")]
+    pub memory_limit_mb: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct RunOutput {
+    /// `None` when the program was killed, by the time limit or a signal
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u128,
+    pub stdout: String,
+    pub stderr: String,
+    /// Set when the middle of stdout or stderr was left out
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+pub struct RunRustOutput {
+    pub edition: Edition,
+    pub release: bool,
+    pub compiled: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Missing when the snippet did not compile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunOutput>,
+}
+
+/// A throwaway crate in the temporary directory, deleted on drop
+struct SnippetCrate {
+    dir: PathBuf,
+}
+
+impl SnippetCrate {
+    /// Makes a directory with a random name that must not exist yet, readable only by the user,
+    /// so another user of a shared temporary directory cannot plant or swap the code
+    fn create(code: &str, edition: Edition) -> std::io::Result<Self> {
+        let mut suffix = [0u8; 16];
+        OsRng.fill_bytes(&mut suffix);
+        let suffix: String = suffix.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let dir = std::env::temp_dir().join(format!("marvin-snippet-{}", suffix));
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&dir)?;
+        let snippet = Self { dir };
+        std::fs::create_dir(snippet.dir.join("src"))?;
+        // the empty [workspace] keeps cargo from looking for a workspace above the temp dir
+        std::fs::write(
+            snippet.dir.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"snippet\"\nversion = \"0.1.0\"\nedition = \"{}\"\n\n[dependencies]\n\n[workspace]\n",
+                edition.as_str()
+            ),
+        )?;
+        let code = if code.contains("fn main") {
+            code.to_string()
+        } else {
+            format!("fn main() {{\n{}\n}}\n", code)
+        };
+        std::fs::write(snippet.dir.join("src/main.rs"), code)?;
+        Ok(snippet)
+    }
+    fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for SnippetCrate {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Caps the address space of the program, which is how much memory it can allocate
+#[cfg(unix)]
+fn limit_memory(command: &mut Command, megabytes: u64) {
+    let bytes = (megabytes * 1024 * 1024) as libc::rlim_t;
+    // SAFETY: setrlimit is async-signal-safe, so it may run between fork and exec
+    unsafe {
+        command.pre_exec(move || {
+            let limit = libc::rlimit {
+                rlim_cur: bytes,
+                rlim_max: bytes,
+            };
+            if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn limit_memory(_command: &mut Command, _megabytes: u64) {}
+
+pub struct RunRust;
+
+impl RunRust {
+    /// Shows the code and asks before compiling it, since the program runs with the user's
+    /// privileges and `include_str!` can read any file while it compiles. Skipped when programs
+    /// were approved for the session.
+    fn approve(&self, code: &str) -> Result<(), RustError> {
+        if APPROVED_FOR_SESSION.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        println!();
+        horizontal_line();
+        println!(
+            "{} wants to compile and run this program with your privileges:",
+            Self::NAME
+        );
+        println!("{}", code.trim_end());
+        horizontal_line();
+        match ask_approval("Run this program?") {
+            Approval::Once => Ok(()),
+            Approval::Session => {
+                APPROVED_FOR_SESSION.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            Approval::Denied => Err(RustError::Rejected),
+        }
+    }
+    async fn run(&self, args: RunRustArgs) -> Result<RunRustOutput, RustError> {
+        self.approve(&args.code)?;
+        let edition = args.edition.unwrap_or_default();
+        let release = args.release.unwrap_or(false);
+        let snippet = SnippetCrate::create(&args.code, edition)?;
+
+        let mut build = Command::new("cargo");
+        build
+            .args(["build", "--offline", "--message-format=json"])
+            .current_dir(snippet.path())
+            .env("CARGO_TARGET_DIR", snippet.path().join("target"))
+            .env("CARGO_TERM_COLOR", "never");
+        if release {
+            build.arg("--release");
+        }
+        let built = run_process(build, COMPILE_TIMEOUT, BUILD_OUTPUT_LIMIT).await?;
+        if built.timed_out {
+            return Err(RustError::CompileTimeout);
+        }
+        let messages = parse_cargo_messages(&built.stdout);
+        let compiled = built.exit_code == Some(0);
+        if !compiled && messages.errors() == 0 {
+            return Err(RustError::Cargo(built.stderr.trim().to_string()));
+        }
+        let mut output = RunRustOutput {
+            edition,
+            release,
+            compiled,
+            diagnostics: messages.diagnostics,
+            run: None,
+        };
+        if !compiled {
+            return Ok(output);
+        }
+
+        let executable = messages
+            .executables
+            .into_iter()
+            .next()
+            .ok_or(RustError::NoExecutable)?;
+        let timeout = Duration::from_secs(
+            args.timeout_secs
+                .unwrap_or(DEFAULT_TIMEOUT_SECS)
+                .clamp(1, MAX_TIMEOUT_SECS),
+        );
+        let mut program = Command::new(executable);
+        program
+            .current_dir(snippet.path())
+            .env_remove("RUST_BACKTRACE");
+        limit_memory(
+            &mut program,
+            args.memory_limit_mb
+                .unwrap_or(DEFAULT_MEMORY_LIMIT_MB)
+                .clamp(16, MAX_MEMORY_LIMIT_MB),
+        );
+        let ran = run_process(program, timeout, RUN_OUTPUT_LIMIT).await?;
+        output.run = Some(RunOutput {
+            exit_code: ran.exit_code,
+            timed_out: ran.timed_out,
+            duration_ms: ran.duration.as_millis(),
+            stdout: ran.stdout,
+            stderr: ran.stderr,
+            truncated: ran.truncated,
+        });
+        Ok(output)
+    }
+}
+
+impl Tool for RunRust {
+    const NAME: &'static str = "run_rust";
+    type Args = RunRustArgs;
+    type Output = RunRustOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Compile and run a Rust program with the local toolchain, offline and with only std. Returns the compiler errors and warnings and, when it compiles, what the program printed. Use it to check claims about borrowing, lifetimes, traits and behaviour before stating them."
+                .to_string(),
+            parameters: serde_json::to_value(schema_for!(RunRustArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.run(args).await.to_tool_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_crate_is_private() {
+        let snippet = SnippetCrate::create("println!(\"hi\");", Edition::E2021).unwrap();
+        let other = SnippetCrate::create("", Edition::E2021).unwrap();
+        assert_ne!(snippet.path(), other.path());
+        let main = std::fs::read_to_string(snippet.path().join("src/main.rs")).unwrap();
+        assert_eq!(main, "fn main() {\nprintln!(\"hi\");\n}\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = snippet.path().metadata().unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        let dir = snippet.path().to_path_buf();
+        drop(snippet);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,12 @@
+use super::files::workspace::Workspace;
+use policy::CommandPolicy;
+use rig::tool::ToolDyn;
+use run_command::RunCommand;
+use std::sync::Arc;
+
+pub mod policy;
+pub mod run_command;
+
+pub fn shell_tools(workspace: Arc<Workspace>, policy: Arc<CommandPolicy>) -> Vec<Box<dyn ToolDyn>> {
+    vec![Box::new(RunCommand::new(workspace, policy))]
+}
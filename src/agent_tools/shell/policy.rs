@@ -0,0 +1,189 @@
+use crate::chat::config::Config;
+use std::{collections::HashSet, sync::Mutex, time::Duration};
+
+pub const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 60;
+/// Refused unless `MARVIN_COMMAND_DENY` replaces the list
+pub const DEFAULT_COMMAND_DENY: &[&str] = &[
+    "sudo", "su", "doas", "mkfs", "dd", "shutdown", "reboot", "halt", "poweroff",
+];
+/// Characters that chain, substitute or redirect in a shell, so a command with any of them is
+/// never pre-approved
+const SHELL_METACHARACTERS: &[char] = &[';', '&', '|', '<', '>', '$', '`', '(', ')', '\n'];
+
+/// What happens to a command before it runs
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Pre-approved by the allow list or earlier in the session
+    Allowed,
+    /// Matches this deny list entry
+    Denied(String),
+    /// Needs the user's confirmation
+    Ask,
+}
+
+/// The allow and deny lists for `run_command`, and the commands approved this session
+pub struct CommandPolicy {
+    allow: Vec<Vec<String>>,
+    deny: Vec<Vec<String>>,
+    timeout: Duration,
+    approved: Mutex<HashSet<String>>,
+}
+
+/// Splits a comma separated list of command prefixes such as `cargo test, git status` into words
+fn prefixes<'a>(entries: impl IntoIterator<Item = &'a str>) -> Vec<Vec<String>> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            entry
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+/// Whether `words` starts with `prefix`, comparing the program by its file name so that
+/// `/usr/bin/sudo` matches `sudo`
+fn starts_with(words: &[String], prefix: &[String]) -> bool {
+    words.len() >= prefix.len()
+        && words
+            .iter()
+            .zip(prefix)
+            .enumerate()
+            .all(|(i, (word, part))| {
+                let word = match i {
+                    0 => word.rsplit('/').next().unwrap_or(word),
+                    _ => word,
+                };
+                word == part
+            })
+}
+
+/// The simple commands in a pipeline or list, split at operators, substitutions and subshells,
+/// with leading `VAR=value` assignments dropped. Quotes are ignored so a quoted `;` splits too,
+/// which errs towards checking more.
+fn simple_commands(command: &str) -> Vec<Vec<String>> {
+    command
+        .split(SHELL_METACHARACTERS)
+        .map(|segment| {
+            shlex::split(segment)
+                .unwrap_or_else(|| segment.split_whitespace().map(str::to_string).collect())
+                .into_iter()
+                .skip_while(|word| {
+                    word.split_once('=').is_some_and(|(name, _)| {
+                        !name.is_empty()
+                            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+impl CommandPolicy {
+    pub fn new<'a>(
+        allow: impl IntoIterator<Item = &'a str>,
+        deny: impl IntoIterator<Item = &'a str>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            allow: prefixes(allow),
+            deny: prefixes(deny),
+            timeout,
+            approved: Mutex::new(HashSet::new()),
+        }
+    }
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    /// Forgets the commands approved for the session
+    pub fn start_session(&self) {
+        self.approved
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+    /// Lets `command` run without asking for the rest of the session
+    pub fn approve_for_session(&self, command: &str) {
+        self.approved
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(command.trim().to_string());
+    }
+    /// Whether the user let `command` run for the rest of the session
+    pub fn approved_for_session(&self, command: &str) -> bool {
+        self.approved
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(command.trim())
+    }
+    /// Deny entries win, then the allow list for plain commands without shell syntax, then
+    /// earlier approvals of the exact same command
+    pub fn check(&self, command: &str) -> Verdict {
+        let commands = simple_commands(command);
+        for words in &commands {
+            if let Some(rule) = self.deny.iter().find(|rule| starts_with(words, rule)) {
+                return Verdict::Denied(rule.join(" "));
+            }
+        }
+        // split as is, so `PATH=... cargo test` is not taken for `cargo test`
+        if !command.contains(SHELL_METACHARACTERS)
+            && let Some(words) = shlex::split(command)
+            && self.allow.iter().any(|rule| starts_with(&words, rule))
+        {
+            return Verdict::Allowed;
+        }
+        if self.approved_for_session(command) {
+            return Verdict::Allowed;
+        }
+        Verdict::Ask
+    }
+}
+
+impl From<&Config> for CommandPolicy {
+    fn from(value: &Config) -> Self {
+        Self::new(
+            value.command_allow().iter().map(String::as_str),
+            value.command_deny().iter().map(String::as_str),
+            value.command_timeout(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_command() {
+        let policy = CommandPolicy::new(
+            ["ls", "cargo test", "git status"],
+            DEFAULT_COMMAND_DENY.iter().copied(),
+            Duration::from_secs(DEFAULT_COMMAND_TIMEOUT_SECS),
+        );
+        assert_eq!(policy.check("ls -la src"), Verdict::Allowed);
+        assert_eq!(policy.check("cargo test --lib"), Verdict::Allowed);
+        assert_eq!(policy.check("cargo build"), Verdict::Ask);
+        assert_eq!(policy.check("ls; rm -rf ~"), Verdict::Ask);
+        assert_eq!(policy.check("ls $(whoami)"), Verdict::Ask);
+        assert_eq!(policy.check("PATH=. cargo test"), Verdict::Ask);
+        assert_eq!(
+            policy.check("cargo test && sudo reboot"),
+            Verdict::Denied("sudo".to_string())
+        );
+        assert_eq!(
+            policy.check("FOO=1 /usr/bin/sudo ls"),
+            Verdict::Denied("sudo".to_string())
+        );
+        assert_eq!(
+            policy.check("echo $(dd if=/dev/zero)"),
+            Verdict::Denied("dd".to_string())
+        );
+        policy.approve_for_session("cargo build");
+        assert_eq!(policy.check(" cargo build "), Verdict::Allowed);
+        policy.start_session();
+        assert_eq!(policy.check("cargo build"), Verdict::Ask);
+    }
+}
@@ -0,0 +1,195 @@
+use super::policy::{CommandPolicy, Verdict};
+use crate::{
+    agent_tools::{
+        approval::{ask_approval, Approval},
+        files::workspace::{Workspace, WorkspaceError},
+        permissions::rule_asks,
+        process::run_process,
+        ToToolResult,
+    },
+    ui::horizontal_line,
+};
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use thiserror::Error;
+use tokio::process::Command;
+
+const MAX_TIMEOUT_SECS: u64 = 600;
+/// Bytes kept of each stream, half from the start and half from the end
+const MAX_OUTPUT: usize = 16 * 1024;
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("the command is empty")]
+    Empty,
+    #[error(
+        "'{command}' is refused by the deny list entry '{rule}', do not try to work around it"
+    )]
+    Denied { command: String, rule: String },
+    #[error("the user rejected '{0}', ask them how to proceed instead of retrying")]
+    Rejected(String),
+    #[error("failed to start the shell: {0}")]
+    Spawn(std::io::Error),
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceError),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct RunCommandArgs {
+    #[schemars(
+        description = "Shell command line, run with sh -c. Commands run without a terminal and cannot read input"
+    )]
+    pub command: String,
+    #[schemars(
+        description = "Directory to run in, relative to the workspace root. Default: the root"
+    )]
+    pub cwd: Option<String>,
+    #[schemars(description = "Seconds before the command is killed (1-600). Default: configured")]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct RunCommandOutput {
+    pub command: String,
+    pub cwd: String,
+    /// `None` when the command was killed, by the timeout or a signal
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u128,
+    pub stdout: String,
+    pub stderr: String,
+    /// Set when the middle of stdout or stderr was left out
+    pub truncated: bool,
+}
+
+pub struct RunCommand {
+    workspace: Arc<Workspace>,
+    policy: Arc<CommandPolicy>,
+}
+
+impl RunCommand {
+    pub fn new(workspace: Arc<Workspace>, policy: Arc<CommandPolicy>) -> Self {
+        Self { workspace, policy }
+    }
+    /// Applies the policy, asking the user about commands it does not settle. When an `ask`
+    /// permission rule matched, only an approval for the session skips the question.
+    fn authorize(&self, command: &str, cwd: &str) -> Result<(), CommandError> {
+        match self.policy.check(command) {
+            Verdict::Allowed if !rule_asks() || self.policy.approved_for_session(command) => {
+                return Ok(());
+            }
+            Verdict::Denied(rule) => {
+                return Err(CommandError::Denied {
+                    command: command.to_string(),
+                    rule,
+                });
+            }
+            Verdict::Allowed | Verdict::Ask => {}
+        }
+        println!();
+        horizontal_line();
+        println!("{} wants to run in {}:", Self::NAME, cwd);
+        println!("  $ {}", command);
+        horizontal_line();
+        match ask_approval("Run this command?") {
+            Approval::Once => Ok(()),
+            Approval::Session => {
+                self.policy.approve_for_session(command);
+                Ok(())
+            }
+            Approval::Denied => Err(CommandError::Rejected(command.to_string())),
+        }
+    }
+    async fn run(&self, args: RunCommandArgs) -> Result<RunCommandOutput, CommandError> {
+        let command = args.command.trim().to_string();
+        if command.is_empty() {
+            return Err(CommandError::Empty);
+        }
+        let dir = self.workspace.resolve(args.cwd.as_deref().unwrap_or("."))?;
+        if !dir.is_dir() {
+            return Err(WorkspaceError::NotDirectory(self.workspace.relative(&dir)).into());
+        }
+        let cwd = self.workspace.relative(&dir);
+        self.authorize(&command, &cwd)?;
+        let timeout = args
+            .timeout_secs
+            .map(|secs| Duration::from_secs(secs.clamp(1, MAX_TIMEOUT_SECS)))
+            .unwrap_or(self.policy.timeout());
+
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let mut process = Command::new(shell);
+        process.arg(flag).arg(&command).current_dir(&dir);
+        let output = run_process(process, timeout, MAX_OUTPUT)
+            .await
+            .map_err(CommandError::Spawn)?;
+        Ok(RunCommandOutput {
+            command,
+            cwd,
+            exit_code: output.exit_code,
+            timed_out: output.timed_out,
+            duration_ms: output.duration.as_millis(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+            truncated: output.truncated,
+        })
+    }
+}
+
+impl Tool for RunCommand {
+    const NAME: &'static str = "run_command";
+    type Args = RunCommandArgs;
+    type Output = RunCommandOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: format!(
+                "Run a shell command in the local workspace at {}, for example to build, run tests or inspect the environment. The user confirms commands that are not pre-approved. Default timeout: {} seconds.",
+                self.workspace.root().display(),
+                self.policy.timeout().as_secs()
+            ),
+            parameters: serde_json::to_value(schema_for!(RunCommandArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.run(args).await.to_tool_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_tools::{
+        files::workspace::DEFAULT_FILE_SIZE_LIMIT, shell::policy::DEFAULT_COMMAND_DENY,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_authorize_without_asking() {
+        let workspace = Workspace::new(std::env::temp_dir(), DEFAULT_FILE_SIZE_LIMIT).unwrap();
+        let policy = CommandPolicy::new(
+            ["cargo test"],
+            DEFAULT_COMMAND_DENY.iter().copied(),
+            Duration::from_secs(1),
+        );
+        let run = RunCommand::new(Arc::new(workspace), Arc::new(policy));
+        assert!(run.authorize("cargo test", ".").is_ok());
+        assert!(matches!(
+            run.authorize("cargo test; sudo rm -rf /", "."),
+            Err(CommandError::Denied { rule, .. }) if rule == "sudo"
+        ));
+        run.policy.approve_for_session("cargo build");
+        assert!(run.authorize("cargo build", ".").is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+use super::parse::{
+    breakdown, parse_datetime, parse_timezone, weekdays_between, CalendarDuration, TimeError,
+};
+use crate::agent_tools::ToToolResult;
+use chrono::{DateTime, Datelike, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use rig::{
+    completion::ToolDefinition,
+    tool::{Tool, ToolError},
+};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[schemars(
+    description = "'now' gives the current date and time. 'parse' reads `datetime`. 'convert' shows `datetime` in `to_timezone`. 'shift' adds `duration` to `datetime`. 'between' measures from `datetime` to `end`."
+)]
+pub enum Operation {
+    #[default]
+    Now,
+    Parse,
+    Convert,
+    Shift,
+    Between,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct DateTimeArgs {
+    #[serde(default)]
+    pub operation: Operation,
+    #[schemars(
+        description = "A date or time: RFC 3339 (\"2025-03-05T14:00:00Z\"), \"2025-03-05 14:00\", \"March 5, 2025\", \"@<unix seconds>\" or relative phrases such as \"now\", \"tomorrow\", \"next friday 14:00\", \"in 3 days\" or \"2 weeks ago\". Defaults to now."
+    )]
+    pub datetime: Option<String>,
+    #[schemars(
+        description = "The end of the span for 'between', in the same formats as `datetime`"
+    )]
+    pub end: Option<String>,
+    #[schemars(
+        description = "What to add for 'shift', e.g. \"3 days 4 hours\", \"-90 minutes\", \"1 month\" or \"2 weeks ago\""
+    )]
+    pub duration: Option<String>,
+    #[schemars(
+        description = "IANA timezone that times without an offset are read in and results are shown in, e.g. \"Europe/Berlin\". Defaults to the user's timezone."
+    )]
+    pub timezone: Option<String>,
+    #[schemars(description = "IANA timezone to show `datetime` in for 'convert'")]
+    pub to_timezone: Option<String>,
+}
+
+/// A point in time with the parts the agent tends to need spelled out
+#[derive(Serialize)]
+pub struct Moment {
+    pub datetime: String,
+    pub timezone: String,
+    pub utc_offset: String,
+    pub weekday: String,
+    pub iso_week: u32,
+    pub day_of_year: u32,
+    pub unix_timestamp: i64,
+}
+
+impl From<DateTime<Tz>> for Moment {
+    fn from(datetime: DateTime<Tz>) -> Self {
+        Self {
+            datetime: datetime.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+            timezone: datetime.timezone().name().to_string(),
+            utc_offset: datetime.format("%:z").to_string(),
+            weekday: datetime.format("%A").to_string(),
+            iso_week: datetime.iso_week().week(),
+            day_of_year: datetime.ordinal(),
+            unix_timestamp: datetime.timestamp(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum DateTimeOutput {
+    Moment(Moment),
+    Conversion {
+        from: Moment,
+        to: Moment,
+    },
+    Span {
+        start: Moment,
+        end: Moment,
+        /// e.g. "1 month 2 days 3 hours", negative when `end` is earlier
+        duration: String,
+        total_seconds: i64,
+        total_days: f64,
+        /// Monday to Friday days from the start date up to the end date
+        weekdays: i64,
+    },
+}
+
+pub struct DateTimeTool {
+    /// The user's timezone, used when a call names none
+    timezone: Tz,
+}
+
+impl DateTimeTool {
+    pub fn new(timezone: Tz) -> Self {
+        Self { timezone }
+    }
+}
+
+impl Tool for DateTimeTool {
+    const NAME: &'static str = "date_time";
+    type Args = DateTimeArgs;
+    type Output = DateTimeOutput;
+    type Error = ToolError;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: format!(
+                "Gives the current date and time, reads dates written in many forms including relative ones, \
+                converts between timezones, adds durations to dates and measures the time between two dates. \
+                Use it to work out dates instead of guessing, e.g. before filling in date filters of a web search. \
+                The user's timezone is {}.",
+                self.timezone.name()
+            ),
+            parameters: serde_json::to_value(schema_for!(DateTimeArgs)).unwrap(),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let timezone = match &args.timezone {
+            Some(name) => parse_timezone(name).to_tool_result()?,
+            None => self.timezone,
+        };
+        let now = Utc::now().with_timezone(&timezone);
+        let datetime =
+            parse_datetime(args.datetime.as_deref().unwrap_or_default(), now).to_tool_result()?;
+        let output = match args.operation {
+            Operation::Now => DateTimeOutput::Moment(now.into()),
+            Operation::Parse => DateTimeOutput::Moment(datetime.into()),
+            Operation::Convert => {
+                let to_timezone = args
+                    .to_timezone
+                    .as_deref()
+                    .ok_or(TimeError::Missing("to_timezone"))
+                    .and_then(parse_timezone)
+                    .to_tool_result()?;
+                DateTimeOutput::Conversion {
+                    from: datetime.into(),
+                    to: datetime.with_timezone(&to_timezone).into(),
+                }
+            }
+            Operation::Shift => {
+                let shifted = args
+                    .duration
+                    .as_deref()
+                    .ok_or(TimeError::Missing("duration"))
+                    .and_then(CalendarDuration::parse)
+                    .and_then(|duration| duration.add_to(datetime))
+                    .to_tool_result()?;
+                DateTimeOutput::Moment(shifted.into())
+            }
+            Operation::Between => {
+                let end = args
+                    .end
+                    .as_deref()
+                    .ok_or(TimeError::Missing("end"))
+                    .and_then(|end| parse_datetime(end, now))
+                    .to_tool_result()?;
+                let total_seconds = (end - datetime).num_seconds();
+                DateTimeOutput::Span {
+                    duration: breakdown(datetime, end),
+                    total_seconds,
+                    total_days: total_seconds as f64 / 86_400.0,
+                    weekdays: weekdays_between(datetime.date_naive(), end.date_naive()),
+                    start: datetime.into(),
+                    end: end.into(),
+                }
+            }
+        };
+        Ok(output)
+    }
+}
@@ -0,0 +1,22 @@
+use crate::agent_tools::time::date_time::DateTimeTool;
+use chrono::Utc;
+use chrono_tz::Tz;
+use rig::tool::ToolDyn;
+
+pub mod date_time;
+pub mod parse;
+
+pub fn time_tools(timezone: Tz) -> Vec<Box<dyn ToolDyn>> {
+    vec![Box::new(DateTimeTool::new(timezone))]
+}
+
+/// Today's date for the system prompt, so the agent knows it without calling a tool
+pub fn current_date_note(timezone: Tz) -> String {
+    let now = Utc::now().with_timezone(&timezone);
+    format!(
+        "Today is {} and the user's timezone is {} (UTC{}).",
+        now.format("%A, %-d %B %Y"),
+        timezone.name(),
+        now.format("%:z")
+    )
+}
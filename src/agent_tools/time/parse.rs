@@ -0,0 +1,350 @@
+use chrono::{
+    DateTime, Datelike, Days, Duration, LocalResult, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc,
+};
+use chrono_tz::{Tz, TZ_VARIANTS};
+use human_date_parser::{from_human_time, ParseResult};
+use thiserror::Error;
+
+const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%d.%m.%Y",
+    "%B %d, %Y",
+    "%B %d %Y",
+    "%d %B %Y",
+    "%b %d, %Y",
+    "%b %d %Y",
+    "%d %b %Y",
+];
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TimeError {
+    #[error(
+        "unknown timezone '{0}', use an IANA name such as 'Europe/Berlin' or 'America/New_York'"
+    )]
+    UnknownTimezone(String),
+    #[error("could not read '{text}' as a date or time: {reason}")]
+    UnknownDate { text: String, reason: String },
+    #[error("could not read '{text}' as a duration: {reason}")]
+    UnknownDuration { text: String, reason: String },
+    #[error("{0} does not exist in this timezone, the clocks skip it")]
+    SkippedLocalTime(NaiveDateTime),
+    #[error("{0} is needed for this operation")]
+    Missing(&'static str),
+    #[error("the result is outside the supported range of dates")]
+    OutOfRange,
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, TimeError> {
+    let name = name.trim();
+    name.parse()
+        .ok()
+        .or_else(|| {
+            TZ_VARIANTS
+                .into_iter()
+                .find(|timezone| timezone.name().eq_ignore_ascii_case(name))
+        })
+        .ok_or_else(|| TimeError::UnknownTimezone(name.to_string()))
+}
+
+/// Places a wall clock time in `timezone`, taking the earlier of two readings when the clocks
+/// go back
+pub fn localize(timezone: Tz, local: NaiveDateTime) -> Result<DateTime<Tz>, TimeError> {
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(datetime) | LocalResult::Ambiguous(datetime, _) => Ok(datetime),
+        LocalResult::None => Err(TimeError::SkippedLocalTime(local)),
+    }
+}
+
+/// Reads RFC 3339, `@<unix seconds>`, ISO dates and times, written out dates such as
+/// "March 5, 2025" and relative phrases such as "next friday 14:00" or "in 3 days". Times
+/// without an offset are in `timezone`.
+pub fn parse_datetime(text: &str, now: DateTime<Tz>) -> Result<DateTime<Tz>, TimeError> {
+    let text = text.trim();
+    let timezone = now.timezone();
+    if text.is_empty() || text.eq_ignore_ascii_case("now") {
+        return Ok(now);
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Ok(datetime.with_timezone(&timezone));
+    }
+    if let Some(seconds) = text.strip_prefix('@').and_then(|s| s.parse().ok()) {
+        return Utc
+            .timestamp_opt(seconds, 0)
+            .single()
+            .map(|datetime| datetime.with_timezone(&timezone))
+            .ok_or(TimeError::OutOfRange);
+    }
+    if let Some(local) = DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    {
+        return localize(timezone, local);
+    }
+    if let Some(date) = DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+    {
+        return localize(timezone, date.and_time(NaiveTime::MIN));
+    }
+    let local = match from_human_time(&text.to_lowercase(), now.naive_local()) {
+        Ok(ParseResult::DateTime(local)) => local,
+        Ok(ParseResult::Date(date)) => date.and_time(NaiveTime::MIN),
+        Ok(ParseResult::Time(time)) => now.date_naive().and_time(time),
+        Err(e) => {
+            return Err(TimeError::UnknownDate {
+                text: text.to_string(),
+                reason: e.to_string(),
+            })
+        }
+    };
+    localize(timezone, local)
+}
+
+/// A duration with calendar parts, which depend on the date they are added to, kept apart
+/// from the exact ones
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CalendarDuration {
+    pub months: i64,
+    pub days: i64,
+    pub exact: Duration,
+}
+
+fn duration_error(text: &str, reason: impl Into<String>) -> TimeError {
+    TimeError::UnknownDuration {
+        text: text.to_string(),
+        reason: reason.into(),
+    }
+}
+
+impl CalendarDuration {
+    /// Reads amounts and units such as "2 weeks 3 days", "-90 minutes", "1y 6mo" or
+    /// "4 hours and 30 minutes". A leading minus or a trailing "ago" negates it.
+    pub fn parse(text: &str) -> Result<Self, TimeError> {
+        let lowercase = text.trim().to_lowercase();
+        let (negative, rest) = match lowercase.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, lowercase.strip_prefix('+').unwrap_or(&lowercase)),
+        };
+        let (negative, rest) = match rest.strip_suffix("ago") {
+            Some(rest) => (!negative, rest),
+            None => (negative, rest),
+        };
+        // split "3days" and "1y6mo" into amounts and units
+        let mut words = Vec::new();
+        let mut word = String::new();
+        for c in rest.chars() {
+            let boundary = !word.is_empty()
+                && (c.is_ascii_digit() || c == '.')
+                    != word.ends_with(|w: char| w.is_ascii_digit() || w == '.');
+            if (c.is_whitespace() || c == ',' || boundary) && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            if !c.is_whitespace() && c != ',' {
+                word.push(c);
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+        let words: Vec<String> = words.into_iter().filter(|word| word != "and").collect();
+        if words.is_empty() {
+            return Err(duration_error(text, "no amounts given"));
+        }
+        let mut duration = Self::default();
+        for pair in words.chunks(2) {
+            let [amount, unit] = pair else {
+                return Err(duration_error(text, format!("'{}' has no unit", pair[0])));
+            };
+            let amount: f64 = match amount.as_str() {
+                "a" | "an" => 1.0,
+                amount => amount
+                    .parse()
+                    .map_err(|_| duration_error(text, format!("'{}' is not a number", amount)))?,
+            };
+            let unit = unit.trim_end_matches('s');
+            let too_large = || duration_error(text, format!("{} {} is too large", amount, unit));
+            let whole = |amount: f64| {
+                if amount.fract() != 0.0 {
+                    Err(duration_error(
+                        text,
+                        format!("{} {} is not a whole number", amount, unit),
+                    ))
+                } else if amount >= i64::MAX as f64 {
+                    Err(too_large())
+                } else {
+                    Ok(amount as i64)
+                }
+            };
+            let add = |total: i64, amount: i64, per: i64| {
+                amount
+                    .checked_mul(per)
+                    .and_then(|amount| total.checked_add(amount))
+                    .ok_or_else(too_large)
+            };
+            let add_exact = |total: Duration, millis_per: f64| {
+                let millis = amount * millis_per;
+                (millis < i64::MAX as f64)
+                    .then(|| Duration::try_milliseconds(millis as i64))
+                    .flatten()
+                    .and_then(|exact| total.checked_add(&exact))
+                    .ok_or_else(too_large)
+            };
+            match unit {
+                "y" | "yr" | "year" => duration.months = add(duration.months, whole(amount)?, 12)?,
+                "mo" | "month" => duration.months = add(duration.months, whole(amount)?, 1)?,
+                "w" | "wk" | "week" => duration.days = add(duration.days, whole(amount)?, 7)?,
+                "d" | "day" => duration.days = add(duration.days, whole(amount)?, 1)?,
+                "h" | "hr" | "hour" => duration.exact = add_exact(duration.exact, 3_600_000.0)?,
+                "m" | "min" | "minute" => duration.exact = add_exact(duration.exact, 60_000.0)?,
+                "" | "sec" | "second" => duration.exact = add_exact(duration.exact, 1000.0)?,
+                unit => return Err(duration_error(text, format!("unknown unit '{}'", unit))),
+            }
+        }
+        if negative {
+            duration = Self {
+                months: -duration.months,
+                days: -duration.days,
+                exact: -duration.exact,
+            };
+        }
+        Ok(duration)
+    }
+
+    /// Adds months and days to the wall clock time, so "1 day" across a daylight saving
+    /// change keeps the time of day, then the exact part
+    pub fn add_to(&self, start: DateTime<Tz>) -> Result<DateTime<Tz>, TimeError> {
+        let local = start.naive_local();
+        let local = match u32::try_from(self.months.unsigned_abs()).map(Months::new) {
+            Ok(months) if self.months < 0 => local.checked_sub_months(months),
+            Ok(months) => local.checked_add_months(months),
+            Err(_) => None,
+        }
+        .ok_or(TimeError::OutOfRange)?;
+        let local = match Days::new(self.days.unsigned_abs()) {
+            days if self.days < 0 => local.checked_sub_days(days),
+            days => local.checked_add_days(days),
+        }
+        .ok_or(TimeError::OutOfRange)?;
+        let moved = if local == start.naive_local() {
+            start
+        } else {
+            localize(start.timezone(), local)?
+        };
+        moved
+            .checked_add_signed(self.exact)
+            .ok_or(TimeError::OutOfRange)
+    }
+}
+
+/// Years, months, days, hours, minutes and seconds from `start` to `end`, largest first
+pub fn breakdown(start: DateTime<Tz>, end: DateTime<Tz>) -> String {
+    let (start, end, sign) = if end < start {
+        (end, start, "-")
+    } else {
+        (start, end, "")
+    };
+    let mut months = 0;
+    while start
+        .naive_local()
+        .checked_add_months(Months::new(months + 1))
+        .and_then(|local| localize(start.timezone(), local).ok())
+        .is_some_and(|datetime| datetime <= end)
+    {
+        months += 1;
+    }
+    let after_months = start
+        .naive_local()
+        .checked_add_months(Months::new(months))
+        .and_then(|local| localize(start.timezone(), local).ok())
+        .unwrap_or(start);
+    let seconds = (end - after_months).num_seconds();
+    let parts = [
+        (i64::from(months / 12), "year"),
+        (i64::from(months % 12), "month"),
+        (seconds / 86_400, "day"),
+        (seconds % 86_400 / 3600, "hour"),
+        (seconds % 3600 / 60, "minute"),
+        (seconds % 60, "second"),
+    ];
+    let parts: Vec<String> = parts
+        .iter()
+        .filter(|(amount, _)| *amount != 0)
+        .map(|(amount, unit)| format!("{} {}{}", amount, unit, if *amount == 1 { "" } else { "s" }))
+        .collect();
+    if parts.is_empty() {
+        "0 seconds".to_string()
+    } else {
+        format!("{}{}", sign, parts.join(" "))
+    }
+}
+
+/// Monday to Friday days from the date of `start` up to, but not including, the date of `end`
+pub fn weekdays_between(start: NaiveDate, end: NaiveDate) -> i64 {
+    let (start, end, sign) = if end < start {
+        (end, start, -1)
+    } else {
+        (start, end, 1)
+    };
+    let days = (end - start).num_days();
+    let remainder = (0..days % 7)
+        .filter(|offset| {
+            let day = start + Duration::days(days / 7 * 7 + offset);
+            day.weekday().number_from_monday() <= 5
+        })
+        .count() as i64;
+    sign * (days / 7 * 5 + remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Berlin;
+
+    #[test]
+    fn test_parse_and_shift() {
+        let now = Berlin.with_ymd_and_hms(2026, 3, 27, 9, 30, 0).unwrap();
+        let parse = |text| parse_datetime(text, now).unwrap().to_rfc3339();
+        assert_eq!(parse("2026-01-05 14:00"), "2026-01-05T14:00:00+01:00");
+        assert_eq!(parse("2026-01-05T14:00:00Z"), "2026-01-05T15:00:00+01:00");
+        assert_eq!(parse("March 5, 2025"), "2025-03-05T00:00:00+01:00");
+        assert_eq!(parse("tomorrow"), "2026-03-28T00:00:00+01:00");
+        assert!(parse_datetime("the day after never", now).is_err());
+        // the clocks go forward on the 29th, a day later keeps the time of day
+        let duration = CalendarDuration::parse("2 days and 90 minutes").unwrap();
+        assert_eq!(
+            duration.add_to(now).unwrap().to_rfc3339(),
+            "2026-03-29T11:00:00+02:00"
+        );
+        assert_eq!(
+            CalendarDuration::parse("1y6mo").unwrap(),
+            CalendarDuration {
+                months: 18,
+                ..Default::default()
+            }
+        );
+        for text in [
+            "9223372036854775807 years",
+            "100000000000000000000 days",
+            "9223372036854775807 hours 9223372036854775807 hours",
+            "2562047788015 hours 2562047788015 hours",
+        ] {
+            assert!(matches!(
+                CalendarDuration::parse(text),
+                Err(TimeError::UnknownDuration { .. })
+            ));
+        }
+        let end = Berlin.with_ymd_and_hms(2027, 5, 28, 10, 31, 5).unwrap();
+        assert_eq!(
+            breakdown(now, end),
+            "1 year 2 months 1 day 1 hour 1 minute 5 seconds"
+        );
+        assert_eq!(weekdays_between(now.date_naive(), end.date_naive()), 305);
+    }
+}
@@ -2,11 +2,9 @@ pub mod request;
 #[allow(dead_code)]
 pub mod response;
 
-use crate::agent_tools::{SomeError, ToToolError, ToToolResult};
-
-use super::tavily::{TavilyClient, BASE_URL};
+use super::tavily::TavilyClient;
+use crate::agent_tools::ToToolResult;
 use request::CrawlArgs;
-use reqwest::StatusCode;
 use rig::{
     completion::ToolDefinition,
     tool::{Tool, ToolError},
@@ -14,9 +12,6 @@ use rig::{
 use schemars::schema_for;
 use serde_json::Value;
 use std::sync::Arc;
-use url::Url;
-
-const CRAWL_PATH: &str = "/crawl";
 
 pub struct Crawl {
     client: Arc<TavilyClient>,
@@ -43,19 +38,9 @@ impl Tool for Crawl {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        let url = Url::parse(BASE_URL)
-            .to_tool_result()?
-            .join(CRAWL_PATH)
-            .to_tool_result()?;
-        let json = serde_json::to_value(args).to_tool_result()?;
-        let response = self.client.post(url, json).await.to_tool_result()?;
-        let status = response.status();
-        let body = response.json::<Value>().await.to_tool_result()?;
-        match status {
-            StatusCode::OK => Ok(body),
-            status => {
-                Err(SomeError(format!("Crawl failed with {}: {:?}", status, body)).to_tool_err())
-            }
-        }
+        self.client
+            .request(Self::NAME, &args)
+            .await
+            .to_tool_result()
     }
 }
@@ -1,3 +1,7 @@
+use crate::agent_tools::web::{
+    map::request::DEFAULT_LIMIT,
+    tavily::{credits_per, TavilyRequest},
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -35,7 +39,9 @@ pub struct CrawlArgs {
     pub chunks_per_source: Option<u8>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "How far from the base URL the crawler can explore (1-5). Default: 1")]
+    #[schemars(
+        description = "How far from the base URL the crawler can explore (1-5). Default: 1"
+    )]
     pub max_depth: Option<u8>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -85,8 +91,20 @@ pub struct CrawlArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(description = "Operation timeout in seconds (10-150). Default: 150")]
     pub timeout: Option<f32>,
+}
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "Include credit usage information in response")]
-    pub include_usage: Option<bool>,
+impl TavilyRequest for CrawlArgs {
+    const PATH: &'static str = "/crawl";
+
+    /// Mapping as for `map_website`, plus extraction at 1 credit per 5 pages, 2 with the
+    /// advanced depth, for as many pages as the limit allows
+    fn max_credits(&self) -> u32 {
+        let pages = self.limit.unwrap_or(DEFAULT_LIMIT) as usize;
+        let mapping = if self.instructions.is_some() { 2 } else { 1 };
+        let extraction = match self.extract_depth {
+            Some(ExtractDepth::Advanced) => 2,
+            _ => 1,
+        };
+        credits_per(pages, 10, mapping).saturating_add(credits_per(pages, 5, extraction))
+    }
 }
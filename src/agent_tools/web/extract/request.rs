@@ -1,3 +1,4 @@
+use crate::agent_tools::web::tavily::{credits_per, TavilyRequest};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -29,7 +30,9 @@ pub struct ExtractArgs {
     pub query: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "Number of content chunks per source (max 500 chars each). Default: 3")]
+    #[schemars(
+        description = "Number of content chunks per source (max 500 chars each). Default: 3"
+    )]
     pub chunks_per_source: Option<u8>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -51,8 +54,17 @@ pub struct ExtractArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(description = "Maximum seconds to wait for extraction (1.0-60.0)")]
     pub timeout: Option<f32>,
+}
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "Include credit usage information in response")]
-    pub include_usage: Option<bool>,
+impl TavilyRequest for ExtractArgs {
+    const PATH: &'static str = "/extract";
+
+    /// 1 credit per 5 URLs, 2 with the advanced depth
+    fn max_credits(&self) -> u32 {
+        let rate = match self.extract_depth {
+            Some(ExtractDepth::Advanced) => 2,
+            _ => 1,
+        };
+        credits_per(self.urls.len(), 5, rate)
+    }
 }
@@ -1,3 +1,4 @@
+use crate::agent_tools::web::tavily::{credits_per, TavilyRequest};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -49,8 +50,17 @@ pub struct MapArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(description = "Timeout in seconds for the map operation (10-150). Default: 150")]
     pub timeout: Option<f32>,
+}
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "Include credit usage information in response")]
-    pub include_usage: Option<bool>,
+/// Pages mapped when no limit is given
+pub const DEFAULT_LIMIT: u32 = 50;
+
+impl TavilyRequest for MapArgs {
+    const PATH: &'static str = "/map";
+
+    /// 1 credit per 10 pages, 2 with instructions, for as many pages as the limit allows
+    fn max_credits(&self) -> u32 {
+        let rate = if self.instructions.is_some() { 2 } else { 1 };
+        credits_per(self.limit.unwrap_or(DEFAULT_LIMIT) as usize, 10, rate)
+    }
 }
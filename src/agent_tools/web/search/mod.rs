@@ -2,10 +2,9 @@ pub mod request;
 #[allow(dead_code)]
 pub mod response;
 
-use super::tavily::{TavilyClient, BASE_URL};
-use crate::agent_tools::{SomeError, ToToolError, ToToolResult};
+use super::tavily::TavilyClient;
+use crate::agent_tools::ToToolResult;
 use request::SearchArgs;
-use reqwest::StatusCode;
 use rig::{
     completion::ToolDefinition,
     tool::{Tool, ToolError},
@@ -13,9 +12,6 @@ use rig::{
 use schemars::schema_for;
 use serde_json::Value;
 use std::sync::Arc;
-use url::Url;
-
-const SEARCH_PATH: &str = "/search";
 
 pub struct Search {
     client: Arc<TavilyClient>,
@@ -42,19 +38,9 @@ impl Tool for Search {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        let url = Url::parse(BASE_URL)
-            .to_tool_result()?
-            .join(SEARCH_PATH)
-            .to_tool_result()?;
-        let json = serde_json::to_value(args).to_tool_result()?;
-        let response = self.client.post(url, json).await.to_tool_result()?;
-        let status = response.status();
-        let body = response.json::<Value>().await.to_tool_result()?;
-        match status {
-            StatusCode::OK => Ok(body),
-            status => {
-                Err(SomeError(format!("Search failed with {}: {:?}", status, body)).to_tool_err())
-            }
-        }
+        self.client
+            .request(Self::NAME, &args)
+            .await
+            .to_tool_result()
     }
 }
@@ -1,3 +1,4 @@
+use crate::agent_tools::web::tavily::TavilyRequest;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -109,8 +110,19 @@ pub struct SearchArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(description = "Boost results from a specific country (ISO country code)")]
     pub country: Option<String>,
+}
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "Include credit usage information in response")]
-    pub include_usage: Option<bool>,
+impl TavilyRequest for SearchArgs {
+    const PATH: &'static str = "/search";
+
+    fn max_credits(&self) -> u32 {
+        // auto_parameters may switch to the advanced depth
+        if matches!(self.search_depth, Some(SearchDepth::Advanced))
+            || self.auto_parameters == Some(true)
+        {
+            2
+        } else {
+            1
+        }
+    }
 }
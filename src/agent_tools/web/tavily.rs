@@ -1,34 +1,194 @@
-use crate::chat::config::Config;
-use reqwest::{Client, Response};
+use crate::{
+    chat::config::Config,
+    costs::{
+        ledger::{self, LedgerEntry},
+        pricing::DEFAULT_TAVILY_CREDIT_PRICE,
+    },
+};
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
 use serde_json::Value;
+use std::{collections::BTreeMap, sync::Mutex};
+use thiserror::Error;
 use url::Url;
 
 pub struct TavilyClient {
     client: Client,
     api_key: String,
+    credit_price: f64,
+    /// Credits a single chat may use, from `MARVIN_TAVILY_CREDIT_LIMIT`
+    credit_limit: Option<u32>,
+    usage: Mutex<CreditUsage>,
 }
 
-pub(super) const BASE_URL: &str = "https://api.tavily.com";
+/// Credits used by the active chat, by tool
+#[derive(Debug, Clone, Default)]
+pub struct CreditUsage {
+    pub session: u16,
+    pub by_tool: BTreeMap<String, u32>,
+}
+
+impl CreditUsage {
+    pub fn total(&self) -> u32 {
+        self.by_tool.values().sum()
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("This {tool} call may use up to {needed} Tavily credits, but only {} of the {limit} credit limit for this chat are left ({used} used), so it was not sent. Retry with a smaller limit or fewer URLs if that fits, otherwise tell the user that web tools are unavailable until MARVIN_TAVILY_CREDIT_LIMIT is raised or a new chat is started.", limit.saturating_sub(*used))]
+pub struct CreditLimitReached {
+    tool: &'static str,
+    needed: u32,
+    used: u32,
+    limit: u32,
+}
+
+#[derive(Debug, Error)]
+pub enum TavilyError {
+    #[error(transparent)]
+    CreditLimit(#[from] CreditLimitReached),
+    #[error(transparent)]
+    Url(#[from] url::ParseError),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("{tool} failed with {status}: {body}")]
+    Status {
+        tool: &'static str,
+        status: StatusCode,
+        body: Value,
+    },
+}
+
+/// Arguments of a Tavily endpoint
+pub trait TavilyRequest: Serialize {
+    const PATH: &'static str;
+    /// Most credits the call can use, checked against the credit limit before sending it
+    fn max_credits(&self) -> u32;
+}
+
+const BASE_URL: &str = "https://api.tavily.com";
 
 impl TavilyClient {
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
             client: Client::new(),
             api_key: api_key.into(),
+            credit_price: DEFAULT_TAVILY_CREDIT_PRICE,
+            credit_limit: None,
+            usage: Mutex::new(CreditUsage::default()),
         }
     }
-    pub async fn post(&self, url: Url, json: Value) -> Result<Response, reqwest::Error> {
-        self.client
+    /// Sends `args` for `tool` if the credit limit allows its most expensive outcome, and
+    /// records the credits the response reports
+    pub async fn request<T: TavilyRequest>(
+        &self,
+        tool: &'static str,
+        args: &T,
+    ) -> Result<Value, TavilyError> {
+        self.check_credits(tool, args.max_credits())?;
+        let url = Url::parse(BASE_URL)?.join(T::PATH)?;
+        let mut json = serde_json::to_value(args)?;
+        // always asked for so credits can be counted, the agent never sets it
+        json["include_usage"] = Value::Bool(true);
+        let response = self
+            .client
             .post(url)
             .bearer_auth(&self.api_key)
             .json(&json)
             .send()
-            .await
+            .await?;
+        let status = response.status();
+        let body = response.json::<Value>().await?;
+        if status != StatusCode::OK {
+            return Err(TavilyError::Status { tool, status, body });
+        }
+        if let Some(credits) = body
+            .pointer("/usage/credits")
+            .and_then(Value::as_u64)
+            .and_then(|credits| u32::try_from(credits).ok())
+        {
+            self.record_credits(tool, credits);
+        }
+        Ok(body)
+    }
+    fn usage(&self) -> std::sync::MutexGuard<'_, CreditUsage> {
+        self.usage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    /// Bills later requests to chat `session`, which already used `by_tool`
+    pub fn start_session(&self, session: u16, by_tool: BTreeMap<String, u32>) {
+        *self.usage() = CreditUsage { session, by_tool };
     }
+    pub fn credit_usage(&self) -> CreditUsage {
+        self.usage().clone()
+    }
+    pub fn credit_limit(&self) -> Option<u32> {
+        self.credit_limit
+    }
+    /// Fails when a request needing up to `needed` credits could take the active chat past
+    /// its limit, before the request is made
+    fn check_credits(&self, tool: &'static str, needed: u32) -> Result<(), CreditLimitReached> {
+        let used = self.usage().total();
+        match self.credit_limit {
+            Some(limit) if used.saturating_add(needed.max(1)) > limit => Err(CreditLimitReached {
+                tool,
+                needed,
+                used,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+    /// Adds the credits a response reports to the active chat and the usage ledger
+    pub fn record_credits(&self, tool: &str, credits: u32) {
+        let session = {
+            let mut usage = self.usage();
+            *usage.by_tool.entry(tool.to_string()).or_default() += credits;
+            usage.session
+        };
+        let entry = LedgerEntry::credits(session, tool, credits, self.credit_price);
+        if let Err(e) = ledger::record(&entry) {
+            eprintln!("Failed to record usage: {:#}", e);
+        }
+    }
+}
+
+/// Credits for `pages` when every started batch of `per` costs `rate`
+pub(super) fn credits_per(pages: usize, per: usize, rate: u32) -> u32 {
+    u32::try_from(pages.div_ceil(per))
+        .unwrap_or(u32::MAX)
+        .saturating_mul(rate)
 }
 
 impl From<&Config> for TavilyClient {
     fn from(value: &Config) -> Self {
-        TavilyClient::new(value.tavily_api_key())
+        Self {
+            credit_price: value.tavily_credit_price(),
+            credit_limit: value.tavily_credit_limit(),
+            ..TavilyClient::new(value.tavily_api_key())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent_tools::web::crawl::request::CrawlArgs;
+
+    #[test]
+    fn test_check_credits_counts_the_worst_case() {
+        let client = TavilyClient {
+            credit_limit: Some(20),
+            ..TavilyClient::new("key")
+        };
+        client.start_session(1, BTreeMap::from([("search_web".to_string(), 5)]));
+        let crawl: CrawlArgs =
+            serde_json::from_value(serde_json::json!({"url": "https://a.io", "limit": 40}))
+                .unwrap();
+        // 4 credits to map 40 pages and 8 to extract them
+        assert_eq!(crawl.max_credits(), 12);
+        assert!(client.check_credits("crawl_website", 12).is_ok());
+        assert!(client.check_credits("crawl_website", 16).is_err());
     }
 }
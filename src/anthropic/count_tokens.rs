@@ -0,0 +1,58 @@
+use crate::chat::config::Config;
+use reqwest::Client;
+use rig::completion::ToolDefinition;
+use serde_json::json;
+
+pub trait CountAnthropicTokens {
+    /// Input tokens of a one-message request, as counted by the provider
+    fn count_tokens(
+        &self,
+        model_id: &str,
+        system: Option<&str>,
+        tools: &[ToolDefinition],
+        text: &str,
+    ) -> impl Future<Output = anyhow::Result<usize>>;
+}
+
+impl CountAnthropicTokens for Config {
+    async fn count_tokens(
+        &self,
+        model_id: &str,
+        system: Option<&str>,
+        tools: &[ToolDefinition],
+        text: &str,
+    ) -> anyhow::Result<usize> {
+        let mut body = json!({
+            "model": model_id,
+            "messages": [{ "role": "user", "content": text }],
+        });
+        if let Some(system) = system {
+            body["system"] = json!(system);
+        }
+        if !tools.is_empty() {
+            body["tools"] = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.parameters,
+                    })
+                })
+                .collect();
+        }
+        let response = Client::new()
+            .post("https://api.anthropic.com/v1/messages/count_tokens")
+            .header("anthropic-version", "2023-06-01")
+            .header("X-Api-Key", self.anthropic_api_key())
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        let body = response.json::<serde_json::Value>().await?;
+        body.get("input_tokens")
+            .and_then(serde_json::Value::as_u64)
+            .map(|tokens| tokens as usize)
+            .ok_or_else(|| anyhow::anyhow!("Unexpected token count response: {}", body))
+    }
+}
@@ -1,3 +1,4 @@
+pub mod count_tokens;
 pub mod get_models;
 
 use serde::Deserialize;
@@ -17,4 +18,7 @@ pub struct ModelInfo {
     pub created_at: String,
     pub display_name: String,
     pub r#type: String,
+    /// Context window, when the models endpoint reports one
+    #[serde(default)]
+    pub max_input_tokens: Option<usize>,
 }
@@ -1,8 +1,66 @@
+use crate::{
+    agent_tools::{
+        files::workspace::DEFAULT_FILE_SIZE_LIMIT,
+        shell::policy::{DEFAULT_COMMAND_DENY, DEFAULT_COMMAND_TIMEOUT_SECS},
+    },
+    costs::pricing::DEFAULT_TAVILY_CREDIT_PRICE,
+};
+use chrono_tz::Tz;
 use dotenvy::dotenv;
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+const DEFAULT_COMPACT_AT: f64 = 0.8;
+const DEFAULT_TOOL_RESULT_LIMIT: usize = 20_000;
+const DEFAULT_BUDGET_WARN_AT: f64 = 0.8;
 
 pub struct Config {
     anthropic_api_key: String,
     tavily_api_key: String,
+    preamble_file: Option<String>,
+    temperature: Option<f64>,
+    max_tokens: Option<u64>,
+    title_model: Option<String>,
+    context_limit: Option<usize>,
+    compact_at: Option<f64>,
+    tool_result_limit: Option<usize>,
+    session_budget: Option<f64>,
+    daily_budget: Option<f64>,
+    monthly_budget: Option<f64>,
+    budget_warn_at: Option<f64>,
+    tavily_credit_price: Option<f64>,
+    tavily_credit_limit: Option<u32>,
+    timezone: Option<Tz>,
+    workspace: Option<PathBuf>,
+    file_size_limit: Option<u64>,
+    command_allow: Option<String>,
+    command_deny: Option<String>,
+    command_timeout: Option<u64>,
+    cargo_project: Option<PathBuf>,
+}
+
+/// Reads an optional variable, failing only when it is set but cannot be parsed
+fn optional_env<T>(key: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(key) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse::<T>()
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("Invalid {}: {}", key, e)),
+        _ => Ok(None),
+    }
+}
+
+/// Splits a comma separated list, dropping empty entries
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 impl Config {
@@ -13,6 +71,26 @@ impl Config {
         Ok(Self {
             anthropic_api_key,
             tavily_api_key,
+            preamble_file: optional_env("MARVIN_PREAMBLE_FILE")?,
+            temperature: optional_env("MARVIN_TEMPERATURE")?,
+            max_tokens: optional_env("MARVIN_MAX_TOKENS")?,
+            title_model: optional_env("MARVIN_TITLE_MODEL")?,
+            context_limit: optional_env("MARVIN_CONTEXT_LIMIT")?,
+            compact_at: optional_env("MARVIN_COMPACT_AT")?,
+            tool_result_limit: optional_env("MARVIN_TOOL_RESULT_LIMIT")?,
+            session_budget: optional_env("MARVIN_SESSION_BUDGET")?,
+            daily_budget: optional_env("MARVIN_DAILY_BUDGET")?,
+            monthly_budget: optional_env("MARVIN_MONTHLY_BUDGET")?,
+            budget_warn_at: optional_env("MARVIN_BUDGET_WARN_AT")?,
+            tavily_credit_price: optional_env("MARVIN_TAVILY_CREDIT_PRICE")?,
+            tavily_credit_limit: optional_env("MARVIN_TAVILY_CREDIT_LIMIT")?,
+            timezone: optional_env("MARVIN_TIMEZONE")?,
+            workspace: optional_env("MARVIN_WORKSPACE")?,
+            file_size_limit: optional_env("MARVIN_FILE_SIZE_LIMIT")?,
+            command_allow: optional_env("MARVIN_COMMAND_ALLOW")?,
+            command_deny: optional_env("MARVIN_COMMAND_DENY")?,
+            command_timeout: optional_env("MARVIN_COMMAND_TIMEOUT")?,
+            cargo_project: optional_env("MARVIN_CARGO_PROJECT")?,
         })
     }
     pub fn anthropic_api_key(&self) -> &str {
@@ -21,4 +99,97 @@ impl Config {
     pub fn tavily_api_key(&self) -> &str {
         &self.tavily_api_key
     }
+    pub fn preamble_file(&self) -> Option<&str> {
+        self.preamble_file.as_deref()
+    }
+    pub fn temperature(&self) -> Option<f64> {
+        self.temperature
+    }
+    pub fn max_tokens(&self) -> Option<u64> {
+        self.max_tokens
+    }
+    pub fn title_model(&self) -> Option<&str> {
+        self.title_model.as_deref()
+    }
+    pub fn context_limit(&self) -> Option<usize> {
+        self.context_limit
+    }
+    /// Share of the context window at which old tool results are dropped and the chat compacted
+    pub fn compact_at(&self) -> f64 {
+        self.compact_at
+            .filter(|fraction| *fraction > 0.0 && *fraction <= 1.0)
+            .unwrap_or(DEFAULT_COMPACT_AT)
+    }
+    /// Characters of a tool result kept in history, `None` when set to 0 to keep everything
+    pub fn tool_result_limit(&self) -> Option<usize> {
+        match self.tool_result_limit.unwrap_or(DEFAULT_TOOL_RESULT_LIMIT) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+    /// Spending cap for a single chat in USD
+    pub fn session_budget(&self) -> Option<f64> {
+        self.session_budget
+    }
+    pub fn daily_budget(&self) -> Option<f64> {
+        self.daily_budget
+    }
+    pub fn monthly_budget(&self) -> Option<f64> {
+        self.monthly_budget
+    }
+    /// Share of a budget past which each turn starts with a warning
+    pub fn budget_warn_at(&self) -> f64 {
+        self.budget_warn_at
+            .filter(|fraction| *fraction > 0.0 && *fraction <= 1.0)
+            .unwrap_or(DEFAULT_BUDGET_WARN_AT)
+    }
+    /// USD per Tavily credit
+    pub fn tavily_credit_price(&self) -> f64 {
+        self.tavily_credit_price
+            .unwrap_or(DEFAULT_TAVILY_CREDIT_PRICE)
+    }
+    /// Tavily credits a single chat may use
+    pub fn tavily_credit_limit(&self) -> Option<u32> {
+        self.tavily_credit_limit
+    }
+    /// The user's timezone, from the system when not configured and UTC when neither works
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+            .or_else(|| iana_time_zone::get_timezone().ok()?.parse().ok())
+            .unwrap_or(Tz::UTC)
+    }
+    /// Root the file tools are confined to, the working directory unless configured
+    pub fn workspace(&self) -> PathBuf {
+        self.workspace.clone().unwrap_or_else(|| PathBuf::from("."))
+    }
+    /// Largest file in bytes the file tools read whole
+    pub fn file_size_limit(&self) -> u64 {
+        self.file_size_limit.unwrap_or(DEFAULT_FILE_SIZE_LIMIT)
+    }
+    /// The Rust project the `cargo` tool works in, the workspace when not configured
+    pub fn cargo_project(&self) -> PathBuf {
+        self.cargo_project
+            .clone()
+            .unwrap_or_else(|| self.workspace())
+    }
+    /// Command prefixes `run_command` may run without asking, such as `cargo test`
+    pub fn command_allow(&self) -> Vec<String> {
+        split_list(self.command_allow.as_deref().unwrap_or_default())
+    }
+    /// Command prefixes `run_command` refuses, `none` for no deny list
+    pub fn command_deny(&self) -> Vec<String> {
+        match self.command_deny.as_deref() {
+            Some(list) if list.eq_ignore_ascii_case("none") => Vec::new(),
+            Some(list) => split_list(list),
+            None => DEFAULT_COMMAND_DENY.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+    /// Default time limit of a command, which a call can change
+    pub fn command_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.command_timeout
+                .filter(|secs| *secs > 0)
+                .unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS),
+        )
+    }
 }
@@ -0,0 +1,126 @@
+use crate::user_commands::show_token_usage::Formatted;
+use rig::message::{AssistantContent, Message, ToolResultContent, UserContent};
+
+/// Context window assumed when neither the models endpoint nor `MARVIN_CONTEXT_LIMIT` give one
+pub const DEFAULT_CONTEXT_LIMIT: usize = 200_000;
+const METER_WIDTH: usize = 30;
+/// Rough average for English text and code, used when the token counting endpoint is unreachable
+const CHARS_PER_TOKEN: usize = 4;
+
+/// How much of the model's context window the next request will use
+#[derive(Debug, Clone, Copy)]
+pub struct ContextUsage {
+    pub used: usize,
+    pub limit: usize,
+    /// Some counts are local estimates
+    pub estimated: bool,
+}
+
+impl ContextUsage {
+    pub fn fraction(&self) -> f64 {
+        self.used as f64 / self.limit.max(1) as f64
+    }
+    /// One line bar such as `Context [#####-----] 45,120 / 200,000 tokens (23%)`
+    pub fn meter(&self) -> String {
+        let filled = ((self.fraction() * METER_WIDTH as f64).round() as usize).min(METER_WIDTH);
+        format!(
+            "Context [{}{}] {}{} / {} tokens ({:.0}%)",
+            "#".repeat(filled),
+            "-".repeat(METER_WIDTH - filled),
+            if self.estimated { "~" } else { "" },
+            self.used.formatted(),
+            self.limit.formatted(),
+            self.fraction() * 100.0
+        )
+    }
+}
+
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Splits the tokens counted for several texts at once between them, in proportion to their
+/// local estimates. The parts add up to `total`.
+pub fn apportion_tokens(total: usize, texts: &[String]) -> Vec<usize> {
+    let estimates: Vec<usize> = texts.iter().map(|text| estimate_tokens(text)).collect();
+    let estimated: usize = estimates.iter().sum();
+    if estimated == 0 {
+        return vec![0; texts.len()];
+    }
+    let mut given = 0;
+    let mut seen = 0;
+    estimates
+        .iter()
+        .map(|estimate| {
+            // cumulative rounding, so the remainder is spread instead of landing on one text
+            seen += estimate;
+            let upto = (total as u128 * seen as u128 / estimated as u128) as usize;
+            let share = upto - given;
+            given = upto;
+            share
+        })
+        .collect()
+}
+
+/// Everything in a message that takes up context, flattened to text for counting
+pub fn countable_text(message: &Message) -> String {
+    let parts: Vec<String> = match message {
+        Message::User { content } => content
+            .iter()
+            .map(|content| match content {
+                UserContent::Text(text) => text.text().to_string(),
+                UserContent::ToolResult(result) => result
+                    .content
+                    .iter()
+                    .map(|content| match content {
+                        ToolResultContent::Text(text) => text.text().to_string(),
+                        ToolResultContent::Image(_) => String::new(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => String::new(),
+            })
+            .collect(),
+        Message::Assistant { content, .. } => content
+            .iter()
+            .map(|content| match content {
+                AssistantContent::Text(text) => text.text().to_string(),
+                AssistantContent::ToolCall(call) => {
+                    format!("{} {}", call.function.name, call.function.arguments)
+                }
+                AssistantContent::Reasoning(reasoning) => reasoning.display_text(),
+                AssistantContent::Image(_) => String::new(),
+            })
+            .collect(),
+    };
+    parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meter_marks_estimates() {
+        let usage = ContextUsage {
+            used: 50_000,
+            limit: 200_000,
+            estimated: true,
+        };
+        assert_eq!(
+            usage.meter(),
+            "Context [########----------------------] ~50,000 / 200,000 tokens (25%)"
+        );
+    }
+
+    #[test]
+    fn test_apportion_tokens_adds_up() {
+        let texts = ["a".repeat(40), String::new(), "b".repeat(120)];
+        assert_eq!(apportion_tokens(101, &texts), vec![25, 0, 76]);
+        assert_eq!(apportion_tokens(7, &[String::new()]), vec![0]);
+    }
+}
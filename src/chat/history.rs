@@ -0,0 +1,363 @@
+use chrono::{DateTime, Utc};
+use rig::{
+    message::{Message, ToolResultContent, UserContent},
+    OneOrMany,
+};
+use serde::{Deserialize, Serialize};
+
+/// Replaces tool results dropped to save context
+pub const ELIDED_TOOL_RESULT: &str = "[tool result dropped to save context]";
+
+/// Cuts the text of tool results in `message` down to `limit` characters, noting how much
+/// was left out. Returns whether anything was cut.
+pub fn elide_large_tool_results(message: &mut Message, limit: usize) -> bool {
+    let Message::User { content } = message else {
+        return false;
+    };
+    let mut elided = false;
+    for content in content.iter_mut() {
+        let UserContent::ToolResult(result) = content else {
+            continue;
+        };
+        for content in result.content.iter_mut() {
+            let ToolResultContent::Text(text) = content else {
+                continue;
+            };
+            let len = text.text().chars().count();
+            if len > limit {
+                let kept: String = text.text().chars().take(limit).collect();
+                *content = ToolResultContent::text(format!(
+                    "{}\n[{} more characters of this tool result were dropped from the history]",
+                    kept,
+                    len - limit
+                ));
+                elided = true;
+            }
+        }
+    }
+    elided
+}
+
+/// Size of a message in tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TokenCount {
+    pub tokens: usize,
+    /// Counted locally because the token counting endpoint was unreachable
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// `None` for messages saved before timestamps were recorded
+    pub timestamp: Option<DateTime<Utc>>,
+    pub message: Message,
+    /// `None` until the message has been counted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<TokenCount>,
+}
+
+impl HistoryEntry {
+    pub fn now(message: Message) -> Self {
+        Self {
+            timestamp: Some(Utc::now()),
+            message,
+            tokens: None,
+        }
+    }
+}
+
+/// Where a pinned text came from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PinSource {
+    User,
+    Assistant,
+    Note,
+}
+
+/// A message or note sent with every request until it is unpinned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pin {
+    pub source: PinSource,
+    pub text: String,
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// A message in the history tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryNode {
+    /// Index of the message this one follows, `None` for the first message of a branch
+    pub parent: Option<usize>,
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+}
+
+/// The conversation as a tree of messages, with when each message was added.
+/// Retrying, undoing or editing a turn moves `head` back and grows a new branch from there,
+/// so earlier branches stay around to switch back to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatHistory {
+    #[serde(rename = "messages")]
+    nodes: Vec<HistoryNode>,
+    /// Last message of the active branch, the one sent to the agent
+    #[serde(default)]
+    head: Option<usize>,
+    /// Summary of the turns dropped by the last compaction, sent with the system prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recap: Option<String>,
+    /// Kept through clearing and compaction, and sent with the system prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pins: Vec<Pin>,
+}
+
+/// The text of a user message typed as a prompt, `None` for tool results and assistant messages
+pub fn prompt_text(message: &Message) -> Option<&str> {
+    match message {
+        Message::User { content } => content.iter().find_map(|content| match content {
+            UserContent::Text(text) => Some(text.text()),
+            _ => None,
+        }),
+        Message::Assistant { .. } => None,
+    }
+}
+
+impl ChatHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Puts back the messages of a history read from disk, `None` if a message does not
+    /// follow an earlier one
+    pub fn with_tree(self, nodes: Vec<HistoryNode>) -> Option<Self> {
+        let valid = nodes
+            .iter()
+            .enumerate()
+            .all(|(i, node)| node.parent.is_none_or(|parent| parent < i))
+            && self.head.is_none_or(|head| head < nodes.len());
+        valid.then_some(Self { nodes, ..self })
+    }
+    /// Node indices from the root to `tip`
+    fn path(&self, tip: Option<usize>) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut next = tip;
+        while let Some(i) = next {
+            path.push(i);
+            next = self.nodes[i].parent;
+        }
+        path.reverse();
+        path
+    }
+    /// Entries of the active branch, oldest first
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &HistoryEntry> + ExactSizeIterator {
+        self.branch(self.head)
+    }
+    /// Entries of the branch ending at `tip`, oldest first
+    pub fn branch(
+        &self,
+        tip: Option<usize>,
+    ) -> impl DoubleEndedIterator<Item = &HistoryEntry> + ExactSizeIterator {
+        self.path(tip).into_iter().map(|i| &self.nodes[i].entry)
+    }
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Message> + ExactSizeIterator {
+        self.entries().map(|entry| &entry.message)
+    }
+    /// Owned copy of the active branch, as the agent expects it
+    pub fn messages(&self) -> Vec<Message> {
+        self.iter().cloned().collect()
+    }
+    /// Number of messages on the active branch
+    pub fn len(&self) -> usize {
+        self.path(self.head).len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+    pub fn push(&mut self, message: impl Into<Message>) {
+        self.push_entry(HistoryEntry::now(message.into()));
+    }
+    fn push_entry(&mut self, entry: HistoryEntry) {
+        self.nodes.push(HistoryNode {
+            parent: self.head,
+            entry,
+        });
+        self.head = Some(self.nodes.len() - 1);
+    }
+    /// Appends the active branch of `other` to the active branch
+    pub fn extend(&mut self, other: ChatHistory) {
+        let entries: Vec<_> = other.entries().cloned().collect();
+        for entry in entries {
+            self.push_entry(entry);
+        }
+    }
+    /// Drops every branch and the recap, keeping pins
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.head = None;
+        self.recap = None;
+    }
+    pub fn recap(&self) -> Option<&str> {
+        self.recap.as_deref()
+    }
+    /// Keeps only the active branch from `position` on, with `recap` standing in for the
+    /// messages before it. Other branches are dropped, pins are kept.
+    pub fn compact(&mut self, position: usize, recap: String) {
+        let kept: Vec<_> = self.entries().skip(position).cloned().collect();
+        *self = Self {
+            recap: Some(recap),
+            pins: std::mem::take(&mut self.pins),
+            ..Self::from(kept)
+        };
+    }
+    pub fn pins(&self) -> &[Pin] {
+        &self.pins
+    }
+    pub fn pin(&mut self, source: PinSource, text: String) {
+        self.pins.push(Pin {
+            source,
+            text,
+            pinned_at: Utc::now(),
+        });
+    }
+    pub fn unpin(&mut self, index: usize) -> Option<Pin> {
+        (index < self.pins.len()).then(|| self.pins.remove(index))
+    }
+    /// Moves the head back so the active branch keeps its first `len` messages,
+    /// the rest stays reachable as another branch
+    pub fn truncate(&mut self, len: usize) {
+        self.head = len
+            .checked_sub(1)
+            .and_then(|i| self.path(self.head).get(i).copied());
+    }
+    /// Positions on the active branch of the messages typed as prompts, with their text
+    pub fn prompts(&self) -> Vec<(usize, &str)> {
+        self.iter()
+            .enumerate()
+            .filter_map(|(position, message)| prompt_text(message).map(|text| (position, text)))
+            .collect()
+    }
+    pub fn head(&self) -> Option<usize> {
+        self.head
+    }
+    /// Last message of every branch in creation order, including the head when it has
+    /// been moved back to the middle of one
+    pub fn branch_tips(&self) -> Vec<Option<usize>> {
+        let mut has_children = vec![false; self.nodes.len()];
+        for parent in self.nodes.iter().filter_map(|node| node.parent) {
+            has_children[parent] = true;
+        }
+        let mut tips: Vec<_> = (0..self.nodes.len())
+            .filter(|i| !has_children[*i])
+            .map(Some)
+            .collect();
+        if !tips.contains(&self.head) {
+            tips.push(self.head);
+            tips.sort();
+        }
+        tips
+    }
+    /// Mutable entries of the active branch, oldest first
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut HistoryEntry> {
+        // a parent always precedes its children, so the path is in ascending order
+        let path = self.path(self.head);
+        self.nodes
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| path.binary_search(i).is_ok())
+            .map(|(_, node)| &mut node.entry)
+    }
+    /// Stores token counts for entries of the active branch, given by position in one pass
+    pub fn set_tokens(&mut self, counts: impl IntoIterator<Item = (usize, TokenCount)>) {
+        let mut counts = counts.into_iter().peekable();
+        for (position, entry) in self.entries_mut().enumerate() {
+            if let Some((_, tokens)) = counts.next_if(|(counted, _)| *counted == position) {
+                entry.tokens = Some(tokens);
+            }
+        }
+    }
+    /// Replaces the content of tool results before the last `keep_prompts` prompts with a
+    /// placeholder, returning how many were dropped. With 0 every tool result is dropped.
+    pub fn elide_tool_results(&mut self, keep_prompts: usize) -> usize {
+        let prompts = self.prompts();
+        let Some(kept) = prompts.len().checked_sub(keep_prompts) else {
+            return 0;
+        };
+        let cutoff = prompts
+            .get(kept)
+            .map_or(self.len(), |(position, _)| *position);
+        let mut elided = 0;
+        for entry in self.entries_mut().take(cutoff) {
+            let Message::User { content } = &mut entry.message else {
+                continue;
+            };
+            for content in content.iter_mut() {
+                if let UserContent::ToolResult(result) = content
+                    && !matches!(result.content.first(), ToolResultContent::Text(text) if text.text() == ELIDED_TOOL_RESULT)
+                {
+                    result.content = OneOrMany::one(ToolResultContent::text(ELIDED_TOOL_RESULT));
+                    entry.tokens = None;
+                    elided += 1;
+                }
+            }
+        }
+        elided
+    }
+    /// Makes the branch ending at `tip` the active one
+    pub fn switch_branch(&mut self, tip: Option<usize>) {
+        if tip.is_none_or(|tip| tip < self.nodes.len()) {
+            self.head = tip;
+        }
+    }
+}
+
+impl From<Vec<HistoryEntry>> for ChatHistory {
+    fn from(entries: Vec<HistoryEntry>) -> Self {
+        let mut chat_history = Self::new();
+        for entry in entries {
+            chat_history.push_entry(entry);
+        }
+        chat_history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_keeps_old_branch() {
+        let mut chat_history = ChatHistory::new();
+        chat_history.push(Message::user("first"));
+        chat_history.push(Message::assistant("answer"));
+        chat_history.truncate(1);
+        chat_history.push(Message::assistant("another answer"));
+        assert_eq!(chat_history.len(), 2);
+        assert_eq!(chat_history.branch_tips(), vec![Some(1), Some(2)]);
+        chat_history.switch_branch(Some(1));
+        let json = serde_json::to_string(&chat_history).unwrap();
+        let chat_history: ChatHistory = serde_json::from_str(&json).unwrap();
+        assert_eq!(chat_history.head(), Some(1));
+        assert_eq!(
+            chat_history.messages().last(),
+            Some(&Message::assistant("answer"))
+        );
+    }
+
+    #[test]
+    fn test_compact_keeps_recent_turns() {
+        let mut chat_history = ChatHistory::new();
+        for prompt in ["first", "second", "third"] {
+            chat_history.push(Message::user(prompt));
+            chat_history.push(Message::assistant("answer"));
+        }
+        chat_history.pin(PinSource::Note, "note".to_string());
+        let (position, _) = chat_history.prompts()[1];
+        chat_history.compact(position, "recap".to_string());
+        assert_eq!(chat_history.len(), 4);
+        assert_eq!(chat_history.branch_tips(), vec![Some(3)]);
+        assert_eq!(chat_history.prompts()[0].1, "second");
+        assert_eq!(chat_history.recap(), Some("recap"));
+        chat_history.clear();
+        assert_eq!(chat_history.recap(), None);
+        assert_eq!(chat_history.pins()[0].text, "note");
+    }
+}
@@ -1,32 +1,74 @@
+use crate::{
+    sessions::{export::ExportFormat, ChatRef},
+    user_commands::{
+        browse_chats::ChatsCommand, manage_permissions::PermissionsCommand, pin_message::PinTarget,
+        show_cost::CostView, show_edits::EditsCommand, show_pins::PinsCommand,
+    },
+};
+use std::path::PathBuf;
+
 #[derive(Debug, Clone, Default)]
 pub enum ChatInput {
     ExitProcess,
     ShowChatHistory,
-    SaveChatHistory,
+    SaveChatHistory(Option<String>),
     ImportChatHistory(u16),
+    ImportConversations(PathBuf),
+    BrowseChats(ChatsCommand),
+    ResumeChat(Option<ChatRef>),
+    /// Format, path and whether to export while storage is encrypted
+    ExportChat(Option<ExportFormat>, Option<PathBuf>, bool),
+    SearchChats(String),
+    LockChat,
     ShowTokenUsage,
+    ShowCost(CostView),
     ClearContext,
     SwitchModel,
     ShowHelpMessage,
     ShowContextSummary,
-    CompactContext,
+    CompactContext(Option<usize>),
+    RetryTurn(Option<String>),
+    UndoTurn,
+    EditPrompt(Option<usize>),
+    ForkChat(Option<String>),
+    SwitchBranch(Option<usize>),
+    PinMessage(PinTarget),
+    ShowPins(PinsCommand),
+    ShowEdits(EditsCommand),
+    ManagePermissions(PermissionsCommand),
     SendMessage(String),
     #[default]
     None,
 }
 
+/// What follows `name` when `raw` is that command, matched without regard to ASCII case.
+/// Only `raw` is sliced, lowercasing can change the byte length of other characters.
+fn command_args<'a>(raw: &'a str, name: &str) -> Option<&'a str> {
+    let rest = raw.get(name.len()..)?;
+    (raw[..name.len()].eq_ignore_ascii_case(name)
+        && (rest.is_empty() || rest.starts_with(char::is_whitespace)))
+    .then_some(rest)
+}
+
 impl<T> From<T> for ChatInput
 where
     T: AsRef<str>,
 {
     fn from(value: T) -> Self {
-        let value = value.as_ref().trim().to_lowercase();
+        let raw = value.as_ref().trim();
+        let value = raw.to_lowercase();
         if value == "/exit" {
             Self::ExitProcess
         } else if value == "/history" {
             Self::ShowChatHistory
-        } else if value == "/save" {
-            Self::SaveChatHistory
+        } else if let Some(name) = command_args(raw, "/save") {
+            let name = name.trim();
+            Self::SaveChatHistory((!name.is_empty()).then(|| name.to_string()))
+        } else if let Some(path) = command_args(raw, "/import")
+            && !path.trim().is_empty()
+            && path.trim().parse::<u16>().is_err()
+        {
+            Self::ImportConversations(PathBuf::from(path.trim()))
         } else if value.contains("/import")
             && let Some(id) = value
                 .split_whitespace()
@@ -34,8 +76,26 @@ where
                 .next()
         {
             Self::ImportChatHistory(id)
+        } else if let Some(args) = command_args(raw, "/chats") {
+            Self::BrowseChats(ChatsCommand::from(args))
+        } else if let Some(chat) = command_args(raw, "/resume") {
+            Self::ResumeChat((!chat.trim().is_empty()).then(|| ChatRef::from(chat)))
+        } else if let Some(args) = command_args(raw, "/export") {
+            let (flags, mut args): (Vec<_>, Vec<_>) = args
+                .split_whitespace()
+                .partition(|arg| arg.starts_with("--"));
+            args.truncate(2);
+            let path = (args.len() == 2).then(|| PathBuf::from(args[1]));
+            let format = args.first().and_then(|format| format.parse().ok());
+            Self::ExportChat(format, path, flags.contains(&"--plaintext"))
+        } else if let Some(query) = command_args(raw, "/search") {
+            Self::SearchChats(query.trim().to_string())
+        } else if value == "/lock" {
+            Self::LockChat
         } else if value == "/tokens" {
             Self::ShowTokenUsage
+        } else if let Some(view) = command_args(raw, "/cost") {
+            Self::ShowCost(CostView::from(view))
         } else if value == "/clear" {
             Self::ClearContext
         } else if value == "/model" {
@@ -44,8 +104,28 @@ where
             Self::ShowHelpMessage
         } else if value == "/summarize" {
             Self::ShowContextSummary
-        } else if value == "/compact" {
-            Self::CompactContext
+        } else if let Some(keep) = command_args(raw, "/compact") {
+            Self::CompactContext(keep.trim().parse().ok())
+        } else if let Some(prompt) = command_args(raw, "/retry") {
+            let prompt = prompt.trim();
+            Self::RetryTurn((!prompt.is_empty()).then(|| prompt.to_string()))
+        } else if value == "/undo" {
+            Self::UndoTurn
+        } else if let Some(turn) = command_args(raw, "/edit") {
+            Self::EditPrompt(turn.trim().parse().ok())
+        } else if let Some(name) = command_args(raw, "/fork") {
+            let name = name.trim();
+            Self::ForkChat((!name.is_empty()).then(|| name.to_string()))
+        } else if let Some(branch) = command_args(raw, "/branches") {
+            Self::SwitchBranch(branch.trim().parse().ok())
+        } else if let Some(target) = command_args(raw, "/pin") {
+            Self::PinMessage(PinTarget::from(target))
+        } else if let Some(args) = command_args(raw, "/pins") {
+            Self::ShowPins(PinsCommand::from(args))
+        } else if let Some(args) = command_args(raw, "/edits") {
+            Self::ShowEdits(EditsCommand::from(args))
+        } else if let Some(args) = command_args(raw, "/permissions") {
+            Self::ManagePermissions(PermissionsCommand::from(args))
         } else if value.is_empty() {
             Self::None
         } else {
@@ -65,3 +145,28 @@ impl ChatInput {
         matches!(self, ChatInput::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_keep_raw_arguments() {
+        assert!(matches!(
+            ChatInput::from("/FORK Ideas"),
+            ChatInput::ForkChat(Some(name)) if name == "Ideas"
+        ));
+        // U+212A lowercases to the one-byte 'k'
+        assert!(matches!(
+            ChatInput::from("/fork \u{212A}elvin"),
+            ChatInput::ForkChat(Some(name)) if name == "\u{212A}elvin"
+        ));
+        // lowercased it reads `/fork x`, but the command is not there in the raw input
+        assert!(matches!(
+            ChatInput::from("/for\u{212A} x"),
+            ChatInput::SendMessage(_)
+        ));
+        assert!(matches!(ChatInput::from("/edits"), ChatInput::ShowEdits(_)));
+        assert!(matches!(ChatInput::from("/pins"), ChatInput::ShowPins(_)));
+    }
+}
@@ -1,18 +1,45 @@
 pub mod config;
+pub mod context;
+pub mod history;
 pub mod input;
+pub mod settings;
+pub mod titles;
 pub use input::ChatInput;
 
 use crate::{
-    agent_tools::{math_tools, web::tavily::TavilyClient, WebTools},
-    anthropic::{get_models::GetAnthropicModels, ModelInfo},
-    chat::config::Config,
-    ui::{horizontal_line, welcome_message},
+    agent_tools::{
+        files::workspace::Workspace, math_tools, permissions::PermissionPolicy, rust::run_rust,
+        rust_tools, shell::policy::CommandPolicy, shell_tools, time::current_date_note, time_tools,
+        web::tavily::TavilyClient, FileTools, WebTools,
+    },
+    anthropic::{count_tokens::CountAnthropicTokens, get_models::GetAnthropicModels, ModelInfo},
+    chat::{
+        config::Config,
+        context::{
+            apportion_tokens, countable_text, estimate_tokens, ContextUsage, DEFAULT_CONTEXT_LIMIT,
+        },
+        history::{elide_large_tool_results, ChatHistory, Pin, PinSource, TokenCount},
+        settings::AgentSettings,
+        titles::{generate_title, title_model, GeneratedTitle},
+    },
+    cli::StartMode,
+    costs::{
+        ledger::{self, LedgerEntry, Spend},
+        pricing::ModelUsage,
+        BudgetStatus, Budgets,
+    },
+    sessions::{
+        list_sessions, load_session, most_recent_session, next_session_id,
+        recovery::{mark_closed, mark_open, unclosed_sessions},
+        session_path, update_session_meta, write_session, SavedSession, SessionMeta, TokenUsage,
+    },
+    ui::{confirm, horizontal_line, welcome_message},
 };
 use futures::StreamExt;
 use rig::{
     agent::{Agent, MultiTurnStreamItem},
     client::CompletionClient,
-    completion::Chat as ChatTrait,
+    completion::Prompt,
     message::Message,
     providers::anthropic::{
         completion::CompletionModel,
@@ -21,96 +48,229 @@ use rig::{
     },
     streaming::{StreamedAssistantContent, StreamingChat},
 };
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    sync::{
+        mpsc::{Receiver, RecvTimeoutError, TryRecvError},
+        Arc,
+    },
+    time::Duration,
+};
 
 pub static PREAMBLE: &str = include_str!("preamble.txt");
+/// Introduces pinned messages and notes in the system prompt
+const PINS_HEADING: &str =
+    "# Pinned\n\nThe user pinned the following, keep it in mind for every answer:";
+/// Introduces the summary of compacted turns in the system prompt
+const RECAP_HEADING: &str = "# Recap of the conversation so far\n\nEarlier turns of this conversation were condensed into the following summary:";
 
 pub struct Chat {
     id: u16,
     config: Config,
     model_options: Vec<ModelInfo>,
+    settings: AgentSettings,
     agent: Agent<CompletionModel>,
-    chat_history: Vec<Message>,
+    chat_history: ChatHistory,
     input: ChatInput,
     total_input_tokens_used: usize,
     total_output_tokens_used: usize,
     tavily_client: Arc<TavilyClient>,
+    workspace: Arc<Workspace>,
+    command_policy: Arc<CommandPolicy>,
+    permissions: Arc<PermissionPolicy>,
+    /// Chat ID and pending result of a background title request
+    title_task: Option<(u16, Receiver<anyhow::Result<GeneratedTitle>>)>,
+    /// Set once the active chat has a summary or one was requested this run
+    title_requested: bool,
+    /// What the token counting endpoint reports for a one-character message, subtracted from
+    /// each message's count. `None` until reached for the current model.
+    count_baseline: Option<usize>,
+    /// Tokens the system prompt and tool definitions add to every request
+    context_overhead: Option<TokenCount>,
+    last_context_usage: Option<ContextUsage>,
+    /// Today's date as of the start of the session, fixed so the system prompt stays cacheable
+    date_note: String,
 }
 
 pub const CHATS_DIR_NAME: &str = "chats";
-fn next_chat_id() -> anyhow::Result<u16> {
-    std::fs::create_dir_all(CHATS_DIR_NAME)?;
-    let existing_chat_ids: HashSet<u16> = std::fs::read_dir(CHATS_DIR_NAME)?
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .filter(|ent| ent.path().extension().and_then(|ostr| ostr.to_str()) == Some("json"))
-        .flat_map(|ent| {
-            ent.path()
-                .file_prefix()
-                .and_then(|prfx| prfx.to_str())
-                .and_then(|str| str.parse::<u16>().ok())
+
+/// Offers to continue chats left open by a crash, returning the one the user picked
+fn recover_session() -> anyhow::Result<Option<u16>> {
+    let unclosed = unclosed_sessions()?;
+    if unclosed.is_empty() {
+        return Ok(None);
+    }
+    let listings = list_sessions()?;
+    for id in unclosed {
+        let Some(listing) = listings.iter().find(|listing| listing.id == id) else {
+            continue;
+        };
+        println!(
+            "Chat (ID = {}) \"{}\" with {} messages was not closed cleanly",
+            id,
+            listing.meta.display_title(),
+            listing.message_count
+        );
+        if confirm("Recover it?") {
+            horizontal_line();
+            return Ok(Some(id));
+        }
+        mark_closed(id)?;
+    }
+    horizontal_line();
+    Ok(None)
+}
+
+fn select_model(model_options: &[ModelInfo]) -> anyhow::Result<String> {
+    let mut input = String::new();
+    println!("Current model: not set");
+    for (i, model) in model_options.iter().enumerate() {
+        println!("{}. {}", i + 1, model.display_name);
+    }
+    horizontal_line();
+    loop {
+        println!("Select a model");
+        horizontal_line();
+        std::io::stdin().read_line(&mut input)?;
+        if let Some((_, ModelInfo { id, .. })) = model_options
+            .iter()
+            .enumerate()
+            .find(|(i, _)| (i + 1).to_string() == input.trim())
+        {
+            horizontal_line();
+            return Ok(id.clone());
+        } else if input.trim() == "/exit" {
+            println!("Farewell!");
+            std::process::exit(0);
+        } else {
+            input.clear();
+        }
+    }
+}
+
+fn build_agent(
+    config: &Config,
+    model_id: &str,
+    settings: &AgentSettings,
+    tavily_client: &Arc<TavilyClient>,
+    workspace: &Arc<Workspace>,
+    command_policy: &Arc<CommandPolicy>,
+    permissions: &Arc<PermissionPolicy>,
+) -> anyhow::Result<Agent<CompletionModel>> {
+    let tools = [
+        math_tools(),
+        time_tools(config.timezone()),
+        tavily_client.web_tools(),
+        workspace.file_tools(),
+        shell_tools(workspace.clone(), command_policy.clone()),
+        rust_tools(config.cargo_project()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let mut builder = Client::new(config.anthropic_api_key())?
+        .agent(model_id)
+        .name("Marvin")
+        .preamble(&settings.preamble)
+        .tools(permissions.guard(tools, workspace))
+        .default_max_turns(100);
+    if let Some(temperature) = settings.temperature {
+        builder = builder.temperature(temperature);
+    }
+    if let Some(max_tokens) = settings.max_tokens {
+        builder = builder.max_tokens(max_tokens);
+    }
+    Ok(builder.build())
+}
+
+/// Saves a generated title and summary, keeping any title the user already chose
+fn store_title(id: u16, result: anyhow::Result<GeneratedTitle>) {
+    let stored = result.and_then(|generated| {
+        update_session_meta(id, |meta| {
+            meta.title.get_or_insert(generated.title);
+            meta.summary = Some(generated.summary);
         })
-        .collect();
-    match (0..u16::MAX).find(|id| !existing_chat_ids.contains(id)) {
-        Some(id) => Ok(id),
-        None => Err(anyhow::anyhow!(
-            "Chat count has hit its limit of {}",
-            u16::MAX
-        )),
+    });
+    if let Err(e) = stored {
+        eprintln!("Title generation failed: {:#}", e);
     }
 }
 
 impl Chat {
-    pub async fn new() -> anyhow::Result<Self> {
-        let id = next_chat_id()?;
-        welcome_message(id);
+    pub async fn new(start_mode: StartMode) -> anyhow::Result<Self> {
+        let resume_id = match start_mode {
+            StartMode::New => recover_session()?,
+            StartMode::Continue => {
+                let id = most_recent_session(None)?;
+                if id.is_none() {
+                    println!("No saved chat to continue");
+                }
+                id
+            }
+            StartMode::Resume(id) => Some(id),
+        };
+        let saved = resume_id.map(load_session).transpose()?;
+        let id = match &saved {
+            Some(saved) => saved.id,
+            None => next_session_id(None)?,
+        };
+        welcome_message(id, saved.as_ref().map(|saved| &saved.meta));
         let config = Config::from_env()?;
         let tavily_client = Arc::new(TavilyClient::from(&config));
+        let workspace = Arc::new(Workspace::try_from(&config)?);
+        let command_policy = Arc::new(CommandPolicy::from(&config));
+        let permissions = Arc::new(PermissionPolicy::load()?);
         let model_options = config.get_models().await?;
-        let agent: Agent<CompletionModel>;
-        let mut input = String::new();
-        println!("Current model: not set");
-        for (i, model) in model_options.iter().enumerate() {
-            println!("{}. {}", i + 1, model.display_name);
-        }
-        horizontal_line();
-        loop {
-            println!("Select a model");
-            horizontal_line();
-            std::io::stdin().read_line(&mut input)?;
-            if let Some((_, ModelInfo { id, .. })) = model_options
-                .iter()
-                .enumerate()
-                .find(|(i, _)| (i + 1).to_string() == input.trim())
-            {
-                agent = Client::new(config.anthropic_api_key())?
-                    .agent(id)
-                    .name("Marvin")
-                    .preamble(PREAMBLE)
-                    .tools(math_tools())
-                    .tools(tavily_client.web_tools())
-                    .default_max_turns(100)
-                    .build();
-                horizontal_line();
-                break;
-            } else if input.trim() == "/exit" {
-                println!("Farewell!");
-                std::process::exit(0);
-            } else {
-                input.clear();
-            }
-        }
-        Ok(Self {
+        let settings = match saved.as_ref().and_then(|saved| saved.meta.settings.clone()) {
+            Some(settings) => settings,
+            None => AgentSettings::from_config(&config)?,
+        };
+        let saved_model = saved
+            .as_ref()
+            .and_then(|saved| saved.meta.model.clone())
+            .filter(|model_id| model_options.iter().any(|model| &model.id == model_id));
+        let model_id = match saved_model {
+            Some(model_id) => model_id,
+            None => select_model(&model_options)?,
+        };
+        let agent = build_agent(
+            &config,
+            &model_id,
+            &settings,
+            &tavily_client,
+            &workspace,
+            &command_policy,
+            &permissions,
+        )?;
+        let title_requested = saved
+            .as_ref()
+            .is_some_and(|saved| saved.meta.summary.is_some());
+        let (chat_history, usage) = match saved {
+            Some(saved) => (saved.chat_history, saved.meta.usage),
+            None => (ChatHistory::new(), TokenUsage::default()),
+        };
+        let mut chat = Self {
             id,
             config,
             model_options,
+            settings,
             agent,
-            chat_history: Vec::new(),
+            chat_history,
             input: ChatInput::new(),
-            total_input_tokens_used: 0,
-            total_output_tokens_used: 0,
+            total_input_tokens_used: usage.input_tokens,
+            total_output_tokens_used: usage.output_tokens,
             tavily_client,
-        })
+            workspace,
+            command_policy,
+            permissions,
+            title_task: None,
+            title_requested,
+            count_baseline: None,
+            context_overhead: None,
+            last_context_usage: None,
+            date_note: String::new(),
+        };
+        chat.start_session();
+        Ok(chat)
     }
     pub fn id(&self) -> u16 {
         self.id
@@ -121,45 +281,88 @@ impl Chat {
     pub fn model_options(&self) -> &[ModelInfo] {
         self.model_options.as_slice()
     }
+    pub fn model_id(&self) -> &str {
+        &self.agent.model.model
+    }
     pub fn model(&self) -> &str {
         self.model_options()
             .iter()
-            .find(|model| model.id == self.agent.model.model)
+            .find(|model| model.id == self.model_id())
             .map(|model| model.display_name.as_str())
             .unwrap_or("")
     }
+    pub fn settings(&self) -> &AgentSettings {
+        &self.settings
+    }
     pub fn set_agent(&mut self, model: ModelInfo) -> anyhow::Result<()> {
-        self.agent = Client::new(self.config().anthropic_api_key())?
-            .agent(model.id)
-            .preamble(PREAMBLE)
-            .tools(math_tools())
-            .tools(self.tavily_client().web_tools())
-            .build();
+        self.agent = build_agent(
+            self.config(),
+            &model.id,
+            self.settings(),
+            &self.tavily_client(),
+            &self.workspace,
+            &self.command_policy,
+            &self.permissions,
+        )?;
+        self.count_baseline = None;
+        self.context_overhead = None;
         Ok(())
     }
-    pub async fn send(&mut self, message: impl Into<Message>) -> anyhow::Result<String> {
-        let message = message.into();
-        self.add_to_chat_history(message.clone());
-        let response = self
-            .agent
-            .chat(message, self.chat_history().to_owned())
-            .await?;
-        self.add_to_chat_history(Message::assistant(response.clone()));
-        Ok(response)
+    /// The preamble and today's date, followed by the recap of compacted turns and the pins if
+    /// there are any
+    pub fn system_prompt(&self) -> String {
+        let mut system_prompt = format!("{}\n\n{}", self.settings.preamble, self.date_note);
+        if let Some(recap) = self.chat_history().recap() {
+            system_prompt.push_str(&format!("\n\n{}\n\n{}", RECAP_HEADING, recap));
+        }
+        if !self.chat_history().pins().is_empty() {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(PINS_HEADING);
+            for pin in self.chat_history().pins() {
+                let from = match pin.source {
+                    PinSource::User => "From the user: ",
+                    PinSource::Assistant => "From your earlier answer: ",
+                    PinSource::Note => "",
+                };
+                system_prompt.push_str(&format!("\n- {}{}", from, pin.text));
+            }
+        }
+        system_prompt
+    }
+    fn sync_system_prompt(&mut self) {
+        self.agent.preamble = Some(self.system_prompt());
     }
     pub async fn stream(&mut self, message: impl Into<Message>) {
         horizontal_line();
+        if !self.within_budget() {
+            return;
+        }
         let message: Message = message.into();
+        self.sync_system_prompt();
+        let history = self.chat_history().messages();
+        // the agent sends the prompt after the history, so it is recorded but not passed in
+        let turn_start = history.len() + 1;
         self.add_to_chat_history(message.clone());
-        let mut stream = self
-            .agent
-            .stream_chat(message, self.chat_history().to_owned())
-            .await;
+        // summed per model call, then replaced by the final response's total so each turn is
+        // recorded once; an interrupted turn keeps the partial sum
+        let mut turn_usage = ModelUsage::default();
+        let mut stream = self.agent.stream_chat(message, history).await;
         while let Some(result) = stream.next().await {
             match result {
                 Ok(MultiTurnStreamItem::FinalResponse(final_response)) => {
-                    self.chat_history
-                        .push(Message::assistant(final_response.response()));
+                    turn_usage = ModelUsage::from(final_response.usage());
+                    // tool calls, their results and the answer, as the agent saw them
+                    let turn = match final_response.history() {
+                        Some(history) => history.get(turn_start..).unwrap_or_default().to_vec(),
+                        None => vec![Message::assistant(final_response.response())],
+                    };
+                    let limit = self.config().tool_result_limit();
+                    for mut message in turn {
+                        if let Some(limit) = limit {
+                            elide_large_tool_results(&mut message, limit);
+                        }
+                        self.add_to_chat_history(message);
+                    }
                 }
                 Ok(MultiTurnStreamItem::StreamAssistantItem(StreamedAssistantContent::Text(
                     text,
@@ -180,6 +383,8 @@ impl Chat {
                     if let Some(input_tokens) = input_tokens {
                         self.add_input_tokens_used(input_tokens);
                     }
+                    turn_usage.input_tokens += input_tokens.unwrap_or_default() as u64;
+                    turn_usage.output_tokens += output_tokens as u64;
                 }
                 Ok(_) => (),
                 Err(e) => {
@@ -187,48 +392,385 @@ impl Chat {
                 }
             }
         }
+        if turn_usage != ModelUsage::default() {
+            let model_id = self.model_id().to_string();
+            self.record_usage(&model_id, turn_usage);
+        }
+        self.autosave();
     }
-    pub fn chat_history(&self) -> &[Message] {
-        self.chat_history.as_slice()
+    /// Sends `prompt` over `messages` without recording either in the chat history. `None`
+    /// when a spending budget is exhausted, which has been shown to the user.
+    pub async fn ask_aside(
+        &mut self,
+        prompt: &str,
+        mut messages: Vec<Message>,
+    ) -> anyhow::Result<Option<String>> {
+        if !self.within_budget() {
+            return Ok(None);
+        }
+        self.sync_system_prompt();
+        let response = self
+            .agent
+            .prompt(prompt)
+            .with_history(&mut messages)
+            .extended_details()
+            .await?;
+        let model_id = self.model_id().to_string();
+        self.add_input_tokens_used(response.total_usage.input_tokens as usize);
+        self.add_output_tokens_used(response.total_usage.output_tokens as usize);
+        self.record_usage(&model_id, ModelUsage::from(response.total_usage));
+        Ok(Some(response.output))
     }
+    /// Adds a billed request to the usage ledger
+    pub fn record_usage(&self, model_id: &str, usage: ModelUsage) {
+        if let Err(e) = ledger::record(&LedgerEntry::tokens(self.id(), model_id, usage)) {
+            eprintln!("Failed to record usage: {:#}", e);
+        }
+    }
+    /// Spend so far against the configured budgets
+    pub fn spend(&self) -> anyhow::Result<Spend> {
+        Ok(Spend::from_entries(
+            &ledger::read_ledger()?,
+            self.id(),
+            chrono::Local::now(),
+        ))
+    }
+    /// Checks the budgets before a turn, printing a warning past `MARVIN_BUDGET_WARN_AT` and
+    /// refusing the turn once one is spent
+    pub fn within_budget(&self) -> bool {
+        match self.budget_status() {
+            BudgetStatus::Within => true,
+            BudgetStatus::Warning(warning) => {
+                println!("Warning: {}", warning);
+                true
+            }
+            BudgetStatus::Exhausted(reason) => {
+                println!("{}", reason);
+                false
+            }
+        }
+    }
+    fn budget_status(&self) -> BudgetStatus {
+        match self.spend() {
+            Ok(spend) => Budgets::from_config(self.config()).check(&spend),
+            Err(e) => {
+                eprintln!("Failed to read the usage ledger: {:#}", e);
+                BudgetStatus::Within
+            }
+        }
+    }
+    pub fn chat_history(&self) -> &ChatHistory {
+        &self.chat_history
+    }
+    /// Drops the conversation, keeping pins
     pub fn clear_chat_history(&mut self) {
         self.chat_history.clear();
+        self.context_overhead = None;
+    }
+    /// Drops the conversation and pins alike
+    pub fn forget_chat_history(&mut self) {
+        self.chat_history = ChatHistory::new();
+        self.context_overhead = None;
+    }
+    pub fn pin(&mut self, source: PinSource, text: String) {
+        self.chat_history.pin(source, text);
+        self.context_overhead = None;
+    }
+    pub fn unpin(&mut self, index: usize) -> Option<Pin> {
+        let pin = self.chat_history.unpin(index);
+        self.context_overhead = None;
+        pin
+    }
+    /// Drops the active branch before `position` in favour of `recap`
+    pub fn compact_chat_history(&mut self, position: usize, recap: String) {
+        self.chat_history.compact(position, recap);
+        self.context_overhead = None;
     }
     pub fn add_to_chat_history(&mut self, message: impl Into<Message>) {
         self.chat_history.push(message.into());
     }
+    pub fn extend_chat_history(&mut self, chat_history: ChatHistory) {
+        self.chat_history.extend(chat_history);
+    }
+    /// Moves the active branch back to its first `len` messages, keeping the rest as a branch
+    pub fn truncate_chat_history(&mut self, len: usize) {
+        self.chat_history.truncate(len);
+    }
+    pub fn switch_branch(&mut self, tip: Option<usize>) {
+        self.chat_history.switch_branch(tip);
+    }
+    /// Metadata as it should be saved now, on top of what is already on disk
+    pub fn session_meta(&self) -> anyhow::Result<SessionMeta> {
+        // title, tags and creation time live on disk, anything unreadable is left untouched
+        let mut meta = if session_path(self.id()).exists() {
+            load_session(self.id())?.meta
+        } else {
+            SessionMeta::new()
+        };
+        meta.model = Some(self.model_id().to_string());
+        meta.settings = Some(self.settings().clone());
+        meta.usage = TokenUsage {
+            input_tokens: self.total_input_tokens_used(),
+            output_tokens: self.total_output_tokens_used(),
+        };
+        meta.updated_at = chrono::Utc::now();
+        Ok(meta)
+    }
     pub fn save_chat_history_to_file(&self) -> anyhow::Result<()> {
-        let file_path = format!("{}/{}.json", CHATS_DIR_NAME, self.id());
-        let file = std::fs::File::create(file_path)?;
-        let chat_history_json = serde_json::to_value(self.chat_history())?;
-        serde_json::to_writer_pretty(file, &chat_history_json)?;
+        write_session(&SavedSession::new(
+            self.id(),
+            self.session_meta()?,
+            self.chat_history().clone(),
+        ))
+    }
+    /// Persists the chat after each completed turn, leaving it marked open until `/exit`
+    pub fn autosave(&mut self) {
+        if self.chat_history().is_empty() && self.chat_history().pins().is_empty() {
+            return;
+        }
+        if let Err(e) = self
+            .save_chat_history_to_file()
+            .and_then(|_| mark_open(self.id()))
+        {
+            eprintln!("Autosave failed: {}", e);
+        }
+        self.update_title();
+    }
+    /// Stores a finished background title, or requests one once the chat has its first answer
+    fn update_title(&mut self) {
+        if let Some((id, receiver)) = &self.title_task {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    let id = *id;
+                    self.title_task = None;
+                    store_title(id, result);
+                }
+                Err(TryRecvError::Disconnected) => self.title_task = None,
+                Err(TryRecvError::Empty) => (),
+            }
+            return;
+        }
+        let answered = self
+            .chat_history()
+            .iter()
+            .any(|message| matches!(message, Message::Assistant { .. }));
+        // titles are not worth a warning, they are skipped quietly once a budget is spent
+        if self.title_requested
+            || !answered
+            || matches!(self.budget_status(), BudgetStatus::Exhausted(_))
+        {
+            return;
+        }
+        self.title_requested = true;
+        let model_id = title_model(self.config(), self.model_options())
+            .unwrap_or_else(|| self.model_id().to_string());
+        match generate_title(
+            self.config(),
+            self.id(),
+            &model_id,
+            self.chat_history().messages(),
+        ) {
+            Ok(receiver) => self.title_task = Some((self.id(), receiver)),
+            Err(e) => eprintln!("Title generation failed: {:#}", e),
+        }
+    }
+    /// Gives a pending title request up to `timeout` to finish, used before exiting
+    pub fn await_title(&mut self, timeout: Duration) {
+        if let Some((id, receiver)) = self.title_task.take() {
+            match receiver.recv_timeout(timeout) {
+                Ok(result) => store_title(id, result),
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => (),
+            }
+        }
+    }
+    /// Marks the chat as cleanly closed so it is not offered for recovery on startup
+    pub fn close_session(&self) -> anyhow::Result<()> {
+        mark_closed(self.id())
+    }
+    /// Continues a saved chat in place of the current one, restoring its model, settings and
+    /// token counters; later saves go back to its file
+    pub fn resume_session(&mut self, saved: SavedSession) -> anyhow::Result<()> {
+        if saved.id != self.id() {
+            self.close_session()?;
+        }
+        if let Some(settings) = saved.meta.settings {
+            self.settings = settings;
+        }
+        let model_id = saved
+            .meta
+            .model
+            .filter(|model_id| {
+                self.model_options()
+                    .iter()
+                    .any(|model| &model.id == model_id)
+            })
+            .unwrap_or_else(|| self.model_id().to_string());
+        self.agent = build_agent(
+            self.config(),
+            &model_id,
+            self.settings(),
+            &self.tavily_client(),
+            &self.workspace,
+            &self.command_policy,
+            &self.permissions,
+        )?;
+        self.count_baseline = None;
+        self.context_overhead = None;
+        self.id = saved.id;
+        self.title_requested = saved.meta.summary.is_some();
+        self.chat_history = saved.chat_history;
+        self.total_input_tokens_used = saved.meta.usage.input_tokens;
+        self.total_output_tokens_used = saved.meta.usage.output_tokens;
+        self.start_session();
         Ok(())
     }
+    fn start_session(&mut self) {
+        self.date_note = current_date_note(self.config.timezone());
+        self.start_credit_session();
+        self.workspace.edits().start_session();
+        self.command_policy.start_session();
+        self.permissions.start_session();
+        run_rust::start_session();
+    }
+    /// Bills Tavily requests to this chat, counting the credits it used in earlier runs
+    /// towards `MARVIN_TAVILY_CREDIT_LIMIT`
+    fn start_credit_session(&self) {
+        let by_tool = match ledger::read_ledger() {
+            Ok(entries) => ledger::session_credits(&entries, self.id()),
+            Err(e) => {
+                eprintln!("Failed to read the usage ledger: {:#}", e);
+                Default::default()
+            }
+        };
+        self.tavily_client.start_session(self.id(), by_tool);
+    }
     pub fn append_chat_history_from_file_infallible(&mut self, id: u16) {
-        let file_path = format!("{}/{}.json", CHATS_DIR_NAME, id);
-        let file_result = std::fs::File::open(file_path);
-        match file_result {
-            Ok(mut file) => {
+        match load_session(id) {
+            Ok(saved) => {
                 println!("chat_history with ID: {} found!", id);
-                let file_str = {
-                    let mut file_str = String::new();
-                    let Ok(_) =
-                        <std::fs::File as std::io::Read>::read_to_string(&mut file, &mut file_str)
-                    else {
-                        println!("Failed to read file");
-                        return;
-                    };
-                    file_str
-                };
-                let Ok(chat_history) = serde_json::from_str::<Vec<Message>>(&file_str) else {
-                    println!("Failed to serialize file to `Vec<Message>`");
-                    return;
-                };
-                self.chat_history.extend(chat_history);
+                self.extend_chat_history(saved.chat_history);
             }
-            Err(e) => println!("Failed to get chat_history: {}", e),
+            Err(e) => println!("Failed to import chat_history: {:#}", e),
         }
     }
+    /// Context window of the active model, `MARVIN_CONTEXT_LIMIT` taking precedence
+    pub fn context_limit(&self) -> usize {
+        self.config()
+            .context_limit()
+            .or_else(|| {
+                self.model_options()
+                    .iter()
+                    .find(|model| model.id == self.model_id())
+                    .and_then(|model| model.max_input_tokens)
+            })
+            .unwrap_or(DEFAULT_CONTEXT_LIMIT)
+    }
+    /// Counts the messages of the active branch not counted yet, using the provider's token
+    /// counting endpoint and falling back to a local estimate when it cannot be reached.
+    /// Estimates are replaced by real counts once the endpoint is back.
+    pub async fn context_usage(&mut self) -> ContextUsage {
+        let model_id = self.model_id().to_string();
+        if self.count_baseline.is_none() {
+            self.count_baseline = self
+                .config
+                .count_tokens(&model_id, None, &[], ".")
+                .await
+                .ok();
+        }
+        let online = self.count_baseline.is_some();
+        if self
+            .context_overhead
+            .is_none_or(|overhead| overhead.estimated && online)
+        {
+            let system_prompt = self.system_prompt();
+            let tools = self
+                .agent
+                .tool_server_handle
+                .get_tool_defs(None)
+                .await
+                .unwrap_or_default();
+            let counted = match self.count_baseline {
+                Some(baseline) => self
+                    .config
+                    .count_tokens(&model_id, Some(&system_prompt), &tools, ".")
+                    .await
+                    .ok()
+                    .map(|tokens| tokens.saturating_sub(baseline)),
+                None => None,
+            };
+            self.context_overhead = Some(match counted {
+                Some(tokens) => TokenCount {
+                    tokens,
+                    estimated: false,
+                },
+                None => TokenCount {
+                    tokens: estimate_tokens(&system_prompt)
+                        + estimate_tokens(&serde_json::to_string(&tools).unwrap_or_default()),
+                    estimated: true,
+                },
+            });
+        }
+        let (positions, texts): (Vec<usize>, Vec<String>) = self
+            .chat_history()
+            .entries()
+            .enumerate()
+            .filter(|(_, entry)| entry.tokens.is_none_or(|tokens| tokens.estimated && online))
+            .map(|(position, entry)| (position, countable_text(&entry.message)))
+            .unzip();
+        // one request for all of them, split between the messages by their estimates
+        let counted = match self.count_baseline {
+            Some(_) if texts.iter().all(String::is_empty) => Some(0),
+            Some(baseline) => self
+                .config
+                .count_tokens(&model_id, None, &[], &texts.join("\n"))
+                .await
+                .ok()
+                .map(|tokens| tokens.saturating_sub(baseline)),
+            None => None,
+        };
+        let counts: Vec<TokenCount> = match counted {
+            Some(total) => apportion_tokens(total, &texts)
+                .into_iter()
+                .map(|tokens| TokenCount {
+                    tokens,
+                    estimated: false,
+                })
+                .collect(),
+            None => texts
+                .iter()
+                .map(|text| TokenCount {
+                    tokens: estimate_tokens(text),
+                    estimated: true,
+                })
+                .collect(),
+        };
+        self.chat_history
+            .set_tokens(positions.into_iter().zip(counts));
+        let counts: Vec<TokenCount> = self
+            .context_overhead
+            .iter()
+            .copied()
+            .chain(
+                self.chat_history()
+                    .entries()
+                    .filter_map(|entry| entry.tokens),
+            )
+            .collect();
+        let usage = ContextUsage {
+            used: counts.iter().map(|count| count.tokens).sum(),
+            limit: self.context_limit(),
+            estimated: counts.iter().any(|count| count.estimated),
+        };
+        self.last_context_usage = Some(usage);
+        usage
+    }
+    /// Usage as of the last count, shown by `/tokens`
+    pub fn last_context_usage(&self) -> Option<ContextUsage> {
+        self.last_context_usage
+    }
+    pub fn elide_tool_results(&mut self, keep_prompts: usize) -> usize {
+        self.chat_history.elide_tool_results(keep_prompts)
+    }
     pub fn input(&self) -> &ChatInput {
         &self.input
     }
@@ -261,4 +803,10 @@ impl Chat {
     pub fn tavily_client(&self) -> Arc<TavilyClient> {
         self.tavily_client.clone()
     }
+    pub fn workspace(&self) -> Arc<Workspace> {
+        self.workspace.clone()
+    }
+    pub fn permissions(&self) -> Arc<PermissionPolicy> {
+        self.permissions.clone()
+    }
 }
@@ -0,0 +1,32 @@
+use crate::chat::{config::Config, PREAMBLE};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Persona and generation settings the agent is built with, saved with each chat so a
+/// resumed chat behaves the way it did before
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentSettings {
+    pub preamble: String,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+}
+
+impl AgentSettings {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let preamble = match config.preamble_file() {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => PREAMBLE.to_string(),
+        };
+        Ok(Self {
+            preamble,
+            temperature: config.temperature(),
+            max_tokens: config.max_tokens(),
+        })
+    }
+    /// Identifies the persona a chat was saved with without comparing full preambles
+    pub fn preamble_hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.preamble.as_bytes()))
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    anthropic::ModelInfo,
+    chat::{config::Config, history::prompt_text},
+    costs::ledger::{self, LedgerEntry},
+    user_commands::show_context_summary::SUMMARIZE_PROMPT,
+};
+use rig::{
+    agent::Agent,
+    client::CompletionClient,
+    completion::Prompt,
+    message::{AssistantContent, Message},
+    providers::anthropic::{completion::CompletionModel, Client},
+};
+use std::sync::mpsc::{channel, Receiver};
+
+const TITLE_PREAMBLE: &str =
+    "You label saved conversations. Reply with exactly what is asked and nothing else.";
+const TITLE_PROMPT: &str = "Give this conversation a title of at most six words on the first line, without quotes. Then, after a blank line, write this:";
+const TITLE_MAX_TOKENS: u64 = 400;
+
+/// Title and summary written by a cheap model, stored as session metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedTitle {
+    pub title: String,
+    pub summary: String,
+}
+
+/// `MARVIN_TITLE_MODEL` if set, otherwise the first Haiku model on offer
+pub fn title_model(config: &Config, model_options: &[ModelInfo]) -> Option<String> {
+    config.title_model().map(str::to_string).or_else(|| {
+        model_options
+            .iter()
+            .find(|model| model.id.contains("haiku"))
+            .map(|model| model.id.clone())
+    })
+}
+
+/// Prompts and answers of `messages`, without the tool calls and results a model without
+/// tools refuses
+fn conversation_only(messages: Vec<Message>) -> Vec<Message> {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            Message::User { .. } => prompt_text(message).map(Message::user),
+            Message::Assistant { content, .. } => {
+                let text: Vec<&str> = content
+                    .iter()
+                    .filter_map(|content| match content {
+                        AssistantContent::Text(text) => Some(text.text()),
+                        _ => None,
+                    })
+                    .collect();
+                (!text.is_empty()).then(|| Message::assistant(text.join("\n")))
+            }
+        })
+        .collect()
+}
+
+/// Asks `model_id` for a title and summary of `messages` on a background task, billed to
+/// chat `session`. Nothing is added to the conversation, the result arrives on the returned
+/// channel.
+pub fn generate_title(
+    config: &Config,
+    session: u16,
+    model_id: &str,
+    messages: Vec<Message>,
+) -> anyhow::Result<Receiver<anyhow::Result<GeneratedTitle>>> {
+    let agent: Agent<CompletionModel> = Client::new(config.anthropic_api_key())?
+        .agent(model_id)
+        .preamble(TITLE_PREAMBLE)
+        .max_tokens(TITLE_MAX_TOKENS)
+        .build();
+    let model_id = model_id.to_string();
+    let mut messages = conversation_only(messages);
+    let (sender, receiver) = channel();
+    tokio::spawn(async move {
+        let prompt = format!("{} {}", TITLE_PROMPT, SUMMARIZE_PROMPT);
+        let result = agent
+            .prompt(prompt)
+            .with_history(&mut messages)
+            .extended_details()
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|response| {
+                let entry = LedgerEntry::tokens(session, &model_id, response.total_usage.into());
+                if let Err(e) = ledger::record(&entry) {
+                    eprintln!("Failed to record usage: {:#}", e);
+                }
+                parse_title(&response.output).ok_or_else(|| {
+                    anyhow::anyhow!("Unexpected title response: {}", response.output.trim())
+                })
+            });
+        let _ = sender.send(result);
+    });
+    Ok(receiver)
+}
+
+fn parse_title(response: &str) -> Option<GeneratedTitle> {
+    let mut lines = response.trim().lines();
+    let title = lines
+        .next()?
+        .trim()
+        .trim_start_matches('#')
+        .trim()
+        .trim_matches(|c| c == '"' || c == '*')
+        .trim()
+        .to_string();
+    let summary = lines.collect::<Vec<_>>().join(" ");
+    let summary = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty() && !summary.is_empty()).then_some(GeneratedTitle { title, summary })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_title_strips_decoration() {
+        let response = "**\"Pinning Futures in Rust\"**\n\nWe discussed why futures\nget pinned.";
+        assert_eq!(
+            parse_title(response),
+            Some(GeneratedTitle {
+                title: "Pinning Futures in Rust".to_string(),
+                summary: "We discussed why futures get pinned.".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use crate::sessions::{export::ExportFormat, vault::NewKey};
+use std::path::PathBuf;
+
+/// How the chat session starts, chosen with command line flags
+#[derive(Debug, Clone, Copy, Default)]
+pub enum StartMode {
+    #[default]
+    New,
+    /// `--continue`: the most recently updated saved chat
+    Continue,
+    /// `--resume <id>`: a specific saved chat
+    Resume(u16),
+}
+
+/// What `marvin` was asked to do from the command line
+#[derive(Debug, Clone)]
+pub enum CliCommand {
+    Chat(StartMode),
+    /// `marvin export <id> <md|html|txt> [path] [--plaintext]`
+    Export {
+        id: u16,
+        format: ExportFormat,
+        path: Option<PathBuf>,
+        /// Export even though storage is encrypted
+        plaintext: bool,
+    },
+    /// `marvin search <query>`
+    Search {
+        query: String,
+    },
+    /// `marvin rekey [--keyfile <path> | --decrypt]`, a new passphrase when no flag is given
+    Rekey(NewKey),
+}
+
+fn parse_id(id: Option<String>, flag: &str) -> anyhow::Result<u16> {
+    let id = id.ok_or_else(|| anyhow::anyhow!("{} requires a chat ID", flag))?;
+    id.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid chat ID: {}", id))
+}
+
+impl CliCommand {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut args = args.into_iter().peekable();
+        if args.peek().map(String::as_str) == Some("export") {
+            args.next();
+            let id = parse_id(args.next(), "export")?;
+            let format = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("export requires a format (md, html or txt)"))?
+                .parse()?;
+            let (flags, rest): (Vec<_>, Vec<_>) = args.partition(|arg| arg.starts_with("--"));
+            if let Some(flag) = flags.iter().find(|flag| *flag != "--plaintext") {
+                return Err(anyhow::anyhow!("Unknown argument: {}", flag));
+            }
+            return Ok(Self::Export {
+                id,
+                format,
+                path: rest.into_iter().next().map(PathBuf::from),
+                plaintext: !flags.is_empty(),
+            });
+        }
+        if args.peek().map(String::as_str) == Some("search") {
+            args.next();
+            let query = args.collect::<Vec<_>>().join(" ");
+            if query.trim().is_empty() {
+                return Err(anyhow::anyhow!("search requires a query"));
+            }
+            return Ok(Self::Search { query });
+        }
+        if args.peek().map(String::as_str) == Some("rekey") {
+            args.next();
+            let new_key = match args.next().as_deref() {
+                None => NewKey::Passphrase,
+                Some("--keyfile") => NewKey::Keyfile(PathBuf::from(
+                    args.next()
+                        .ok_or_else(|| anyhow::anyhow!("--keyfile requires a path"))?,
+                )),
+                Some("--decrypt") => NewKey::None,
+                Some(other) => return Err(anyhow::anyhow!("Unknown argument: {}", other)),
+            };
+            return Ok(Self::Rekey(new_key));
+        }
+        let mut mode = StartMode::New;
+        while let Some(arg) = args.next() {
+            mode = match arg.as_str() {
+                "--continue" | "-c" => StartMode::Continue,
+                "--resume" | "-r" => StartMode::Resume(parse_id(args.next(), "--resume")?),
+                other => return Err(anyhow::anyhow!("Unknown argument: {}", other)),
+            };
+        }
+        Ok(Self::Chat(mode))
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    chat::CHATS_DIR_NAME,
+    costs::pricing::{model_pricing, ModelUsage},
+};
+use chrono::{DateTime, Datelike, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+pub const LEDGER_FILE_NAME: &str = "ledger.jsonl";
+
+/// One billed request, appended to `chats/ledger.jsonl` when it completes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: DateTime<Utc>,
+    pub session: u16,
+    /// Model ID, or the tool name for Tavily requests
+    pub model: String,
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_write_tokens: u64,
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    #[serde(default)]
+    pub tavily_credits: f64,
+    /// USD at the prices known when the request was made, 0 for models without a price
+    pub cost: f64,
+}
+
+impl LedgerEntry {
+    pub fn tokens(session: u16, model: &str, usage: ModelUsage) -> Self {
+        let cost = model_pricing(model)
+            .map(|(pricing, _)| pricing.cost(&usage))
+            .unwrap_or_default();
+        Self {
+            timestamp: Utc::now(),
+            session,
+            model: model.to_string(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_write_tokens: usage.cache_write_tokens,
+            cache_read_tokens: usage.cache_read_tokens,
+            tavily_credits: 0.0,
+            cost,
+        }
+    }
+    pub fn credits(session: u16, tool: &str, credits: u32, credit_price: f64) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session,
+            model: tool.to_string(),
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_write_tokens: 0,
+            cache_read_tokens: 0,
+            tavily_credits: credits as f64,
+            cost: credits as f64 * credit_price,
+        }
+    }
+}
+
+/// Tavily credits chat `session` used, by tool
+pub fn session_credits(entries: &[LedgerEntry], session: u16) -> BTreeMap<String, u32> {
+    let mut by_tool = BTreeMap::new();
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.session == session && entry.tavily_credits > 0.0)
+    {
+        *by_tool.entry(entry.model.clone()).or_default() += entry.tavily_credits as u32;
+    }
+    by_tool
+}
+
+fn ledger_path() -> PathBuf {
+    Path::new(CHATS_DIR_NAME).join(LEDGER_FILE_NAME)
+}
+
+pub fn record(entry: &LedgerEntry) -> anyhow::Result<()> {
+    std::fs::create_dir_all(CHATS_DIR_NAME)?;
+    let mut options = std::fs::OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    options.open(ledger_path())?.write_all(&line)?;
+    Ok(())
+}
+
+/// Every recorded request, skipping lines cut short by a crash
+pub fn read_ledger() -> anyhow::Result<Vec<LedgerEntry>> {
+    let file = match std::fs::File::open(ledger_path()) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Spend in USD over the periods budgets apply to, days and months in local time
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spend {
+    pub session: f64,
+    pub today: f64,
+    pub month: f64,
+}
+
+impl Spend {
+    pub fn from_entries(entries: &[LedgerEntry], session: u16, now: DateTime<Local>) -> Self {
+        let mut spend = Self::default();
+        for entry in entries {
+            let timestamp = entry.timestamp.with_timezone(&Local);
+            if entry.session == session {
+                spend.session += entry.cost;
+            }
+            if (timestamp.year(), timestamp.month()) == (now.year(), now.month()) {
+                spend.month += entry.cost;
+                if timestamp.day() == now.day() {
+                    spend.today += entry.cost;
+                }
+            }
+        }
+        spend
+    }
+}
@@ -0,0 +1,103 @@
+pub mod ledger;
+pub mod pricing;
+
+use crate::{chat::config::Config, costs::ledger::Spend};
+
+/// What a turn is allowed to do given the spend so far
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetStatus {
+    Within,
+    /// Past the warning share of a budget, with a message saying which
+    Warning(String),
+    /// A budget is spent, no more requests until it is raised or the period rolls over
+    Exhausted(String),
+}
+
+/// Spending caps in USD, read from `MARVIN_SESSION_BUDGET`, `MARVIN_DAILY_BUDGET` and
+/// `MARVIN_MONTHLY_BUDGET`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Budgets {
+    pub session: Option<f64>,
+    pub daily: Option<f64>,
+    pub monthly: Option<f64>,
+    /// Share of a budget past which each turn starts with a warning
+    pub warn_at: f64,
+}
+
+impl Budgets {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            session: config.session_budget(),
+            daily: config.daily_budget(),
+            monthly: config.monthly_budget(),
+            warn_at: config.budget_warn_at(),
+        }
+    }
+    fn limits(&self, spend: &Spend) -> [(&'static str, &'static str, Option<f64>, f64); 3] {
+        [
+            (
+                "session",
+                "MARVIN_SESSION_BUDGET",
+                self.session,
+                spend.session,
+            ),
+            ("daily", "MARVIN_DAILY_BUDGET", self.daily, spend.today),
+            (
+                "monthly",
+                "MARVIN_MONTHLY_BUDGET",
+                self.monthly,
+                spend.month,
+            ),
+        ]
+    }
+    pub fn check(&self, spend: &Spend) -> BudgetStatus {
+        let mut status = BudgetStatus::Within;
+        for (name, key, limit, spent) in self.limits(spend) {
+            let Some(limit) = limit else {
+                continue;
+            };
+            if spent >= limit {
+                return BudgetStatus::Exhausted(format!(
+                    "The {} budget of ${:.2} is spent (${:.2}), raise {} to continue",
+                    name, limit, spent, key
+                ));
+            }
+            if status == BudgetStatus::Within && spent >= limit * self.warn_at {
+                status = BudgetStatus::Warning(format!(
+                    "${:.2} of the ${:.2} {} budget is spent",
+                    spent, limit, name
+                ));
+            }
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_check() {
+        let budgets = Budgets {
+            session: Some(1.0),
+            daily: None,
+            monthly: Some(10.0),
+            warn_at: 0.8,
+        };
+        let spend = |session, month| Spend {
+            session,
+            today: month,
+            month,
+        };
+        assert_eq!(budgets.check(&spend(0.5, 5.0)), BudgetStatus::Within);
+        assert!(matches!(
+            budgets.check(&spend(0.9, 5.0)),
+            BudgetStatus::Warning(_)
+        ));
+        assert!(matches!(
+            budgets.check(&spend(0.5, 10.0)),
+            BudgetStatus::Exhausted(_)
+        ));
+    }
+}
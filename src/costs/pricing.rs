@@ -0,0 +1,108 @@
+/// Price of one Tavily API credit on the pay-as-you-go plan, in USD
+pub const DEFAULT_TAVILY_CREDIT_PRICE: f64 = 0.008;
+
+/// Rates in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    /// Writing the prompt cache with the default five minute lifetime
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+impl ModelPricing {
+    const fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_write: input * 1.25,
+            cache_read: input * 0.1,
+        }
+    }
+    pub fn cost(&self, usage: &ModelUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_write_tokens as f64 * self.cache_write
+            + usage.cache_read_tokens as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Tokens billed for one or more requests to a model
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModelUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_write_tokens: u64,
+    pub cache_read_tokens: u64,
+}
+
+/// rig's Anthropic streaming reports only input and output tokens, so the cache counts stay 0
+/// for streamed turns. The agent does not turn on prompt caching, so none are billed either.
+impl From<rig::completion::Usage> for ModelUsage {
+    fn from(usage: rig::completion::Usage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_write_tokens: 0,
+            cache_read_tokens: usage.cached_input_tokens,
+        }
+    }
+}
+
+/// Published prices by model ID prefix, most specific first
+const PRICES: &[(&str, ModelPricing)] = &[
+    ("claude-opus-4-5", ModelPricing::new(5.0, 25.0)),
+    ("claude-opus-4-1", ModelPricing::new(15.0, 75.0)),
+    ("claude-opus-4-2025", ModelPricing::new(15.0, 75.0)),
+    ("claude-3-opus", ModelPricing::new(15.0, 75.0)),
+    ("claude-sonnet-4", ModelPricing::new(3.0, 15.0)),
+    ("claude-3-7-sonnet", ModelPricing::new(3.0, 15.0)),
+    ("claude-3-5-sonnet", ModelPricing::new(3.0, 15.0)),
+    ("claude-haiku-4-5", ModelPricing::new(1.0, 5.0)),
+    ("claude-3-5-haiku", ModelPricing::new(0.8, 4.0)),
+    ("claude-3-haiku", ModelPricing::new(0.25, 1.25)),
+];
+
+/// Latest prices of each model family, assumed for models newer than the table
+const FAMILY_PRICES: &[(&str, ModelPricing)] = &[
+    ("opus", ModelPricing::new(5.0, 25.0)),
+    ("sonnet", ModelPricing::new(3.0, 15.0)),
+    ("haiku", ModelPricing::new(1.0, 5.0)),
+];
+
+/// Prices of `model_id`, with `true` when they are guessed from the model family
+pub fn model_pricing(model_id: &str) -> Option<(ModelPricing, bool)> {
+    PRICES
+        .iter()
+        .find(|(prefix, _)| model_id.starts_with(prefix))
+        .map(|(_, pricing)| (*pricing, false))
+        .or_else(|| {
+            FAMILY_PRICES
+                .iter()
+                .find(|(family, _)| model_id.contains(family))
+                .map(|(_, pricing)| (*pricing, true))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_pricing() {
+        let (opus, guessed) = model_pricing("claude-opus-4-20250514").unwrap();
+        assert_eq!((opus.input, guessed), (15.0, false));
+        let (sonnet, guessed) = model_pricing("claude-sonnet-5-20270101").unwrap();
+        assert_eq!((sonnet.output, guessed), (15.0, true));
+        let usage = ModelUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_write_tokens: 0,
+            cache_read_tokens: 1_000_000,
+        };
+        assert!((sonnet.cost(&usage) - 4.8).abs() < 1e-9);
+        assert_eq!(model_pricing("gpt-4o"), None);
+    }
+}
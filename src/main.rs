@@ -1,15 +1,65 @@
 mod agent_tools;
 mod anthropic;
 mod chat;
+mod cli;
+mod costs;
 mod runner;
+mod sessions;
 mod ui;
 mod user_commands;
 
-use crate::{chat::Chat, runner::Runner};
+use crate::{
+    chat::Chat,
+    cli::{CliCommand, StartMode},
+    runner::Runner,
+    sessions::{search::search_sessions, vault},
+    user_commands::search_chats::{export_hit, pick_hit, print_hits, SearchAction},
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let state = Chat::new().await?;
-    Runner::run(state).await?;
+    dotenvy::dotenv().ok();
+    let command = CliCommand::from_args(std::env::args().skip(1))?;
+    if let CliCommand::Rekey(new_key) = command {
+        let count = vault::rekey(new_key)?;
+        let state = if vault::is_encrypted() {
+            "encrypted"
+        } else {
+            "decrypted"
+        };
+        println!("Rekeyed chat storage, {} files {}", count, state);
+        return Ok(());
+    }
+    if !vault::unlock_interactive()? {
+        println!("Farewell!");
+        return Ok(());
+    }
+    match command {
+        CliCommand::Chat(start_mode) => {
+            let state = Chat::new(start_mode).await?;
+            Runner::run(state).await?;
+        }
+        CliCommand::Export {
+            id,
+            format,
+            path,
+            plaintext,
+        } => export_hit(id, format, path, plaintext)?,
+        CliCommand::Search { query } => {
+            let hits = search_sessions(&query, 10)?;
+            print_hits(&query, &hits);
+            match pick_hit(&hits) {
+                Some(SearchAction::Resume(id)) => {
+                    let state = Chat::new(StartMode::Resume(id)).await?;
+                    Runner::run(state).await?;
+                }
+                Some(SearchAction::Export(id, format, plaintext)) => {
+                    export_hit(id, format, None, plaintext)?
+                }
+                None => (),
+            }
+        }
+        CliCommand::Rekey(_) => unreachable!("handled before unlocking"),
+    }
     Ok(())
 }
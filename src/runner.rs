@@ -30,18 +30,53 @@ impl Runner {
                     chat.show_chat_history();
                     continue;
                 }
-                ChatInput::SaveChatHistory => {
-                    chat.save_chat_history()?;
+                ChatInput::SaveChatHistory(title) => {
+                    let title = title.clone();
+                    chat.save_chat_history(title)?;
                     continue;
                 }
                 ChatInput::ImportChatHistory(id) => {
                     chat.import_chat_history(*id);
                     continue;
                 }
+                ChatInput::ImportConversations(path) => {
+                    let path = path.clone();
+                    chat.import_conversations(path)?;
+                    continue;
+                }
+                ChatInput::BrowseChats(command) => {
+                    let command = command.clone();
+                    chat.browse_chats(command)?;
+                    continue;
+                }
+                ChatInput::ResumeChat(chat_ref) => {
+                    let chat_ref = chat_ref.clone();
+                    chat.resume_chat(chat_ref)?;
+                    continue;
+                }
+                ChatInput::ExportChat(format, path, plaintext) => {
+                    let (format, path, plaintext) = (*format, path.clone(), *plaintext);
+                    chat.export_chat(format, path, plaintext)?;
+                    continue;
+                }
+                ChatInput::SearchChats(query) => {
+                    let query = query.clone();
+                    chat.search_chats(query)?;
+                    continue;
+                }
+                ChatInput::LockChat => {
+                    chat.lock_chat()?;
+                    continue;
+                }
                 ChatInput::ShowTokenUsage => {
                     chat.show_token_usage();
                     continue;
                 }
+                ChatInput::ShowCost(view) => {
+                    let view = *view;
+                    chat.show_cost(view)?;
+                    continue;
+                }
                 ChatInput::SwitchModel => {
                     chat.switch_model()?;
                     continue;
@@ -50,8 +85,55 @@ impl Runner {
                     chat.show_context_summary().await?;
                     continue;
                 }
-                ChatInput::CompactContext => {
-                    chat.compact_context().await?;
+                ChatInput::CompactContext(keep_exchanges) => {
+                    let keep_exchanges = *keep_exchanges;
+                    chat.compact_context(keep_exchanges).await?;
+                    continue;
+                }
+                ChatInput::RetryTurn(model) => {
+                    let model = model.clone();
+                    chat.retry_turn(model).await?;
+                    chat.manage_context().await?;
+                    continue;
+                }
+                ChatInput::UndoTurn => {
+                    chat.undo_turn();
+                    continue;
+                }
+                ChatInput::EditPrompt(n) => {
+                    let n = *n;
+                    chat.edit_prompt(n).await?;
+                    chat.manage_context().await?;
+                    continue;
+                }
+                ChatInput::ForkChat(title) => {
+                    let title = title.clone();
+                    chat.fork_chat(title)?;
+                    continue;
+                }
+                ChatInput::SwitchBranch(n) => {
+                    let n = *n;
+                    chat.switch_branch_to(n);
+                    continue;
+                }
+                ChatInput::PinMessage(target) => {
+                    let target = target.clone();
+                    chat.pin_message(target);
+                    continue;
+                }
+                ChatInput::ShowPins(command) => {
+                    let command = command.clone();
+                    chat.show_pins(command);
+                    continue;
+                }
+                ChatInput::ShowEdits(command) => {
+                    let command = command.clone();
+                    chat.show_edits(command);
+                    continue;
+                }
+                ChatInput::ManagePermissions(command) => {
+                    let command = command.clone();
+                    chat.manage_permissions(command);
                     continue;
                 }
                 ChatInput::None => continue,
@@ -68,6 +150,7 @@ impl Runner {
                     let message = message.to_owned();
                     chat.clear_input();
                     chat.stream(Message::user(message)).await;
+                    chat.manage_context().await?;
                 }
             }
         }
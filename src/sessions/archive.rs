@@ -0,0 +1,41 @@
+use crate::{
+    chat::CHATS_DIR_NAME,
+    sessions::{vault, write_atomic, SavedSession},
+};
+use std::path::{Path, PathBuf};
+
+pub const ARCHIVE_DIR_NAME: &str = "archive";
+
+pub fn archive_dir() -> PathBuf {
+    Path::new(CHATS_DIR_NAME).join(ARCHIVE_DIR_NAME)
+}
+
+/// Saves a chat as it was before compaction to `chats/archive/<id>-<timestamp>.json`,
+/// returning the path it was written to
+pub fn archive_session(session: &SavedSession) -> anyhow::Result<PathBuf> {
+    let path = archive_dir().join(format!(
+        "{}-{}.json",
+        session.id,
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")
+    ));
+    write_atomic(&path, &vault::seal(&serde_json::to_vec_pretty(session)?)?)?;
+    Ok(path)
+}
+
+/// Deletes every archived copy of a chat
+pub fn remove_archives(id: u16) -> anyhow::Result<()> {
+    let Ok(entries) = std::fs::read_dir(archive_dir()) else {
+        return Ok(());
+    };
+    let prefix = format!("{}-", id);
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(&prefix))
+        {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
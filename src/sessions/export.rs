@@ -0,0 +1,435 @@
+use crate::sessions::{vault, write_atomic, SavedSession};
+use chrono::{DateTime, Local, Utc};
+use rig::message::{AssistantContent, Message, ToolResultContent, UserContent};
+use std::{fmt::Write, path::PathBuf, str::FromStr};
+
+pub const EXPORTS_DIR_NAME: &str = "exports";
+const TOOL_RESULT_SUMMARY_LEN: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Text,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "md" | "markdown" => Ok(Self::Markdown),
+            "html" | "htm" => Ok(Self::Html),
+            "txt" | "text" => Ok(Self::Text),
+            other => Err(anyhow::anyhow!(
+                "Unknown export format: {} (expected md, html or txt)",
+                other
+            )),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Text => "txt",
+        }
+    }
+}
+
+/// One piece of a turn, flattened out of Rig's nested content enums
+enum Block {
+    Text(String),
+    ToolCall { name: String, arguments: String },
+    ToolResult { id: String, summary: String },
+    Reasoning(String),
+    Media(&'static str),
+}
+
+struct Turn {
+    role: &'static str,
+    timestamp: Option<DateTime<Utc>>,
+    blocks: Vec<Block>,
+}
+
+fn turns(session: &SavedSession) -> Vec<Turn> {
+    session
+        .chat_history
+        .entries()
+        .map(|entry| match &entry.message {
+            Message::User { content } => Turn {
+                role: if content
+                    .iter()
+                    .all(|c| matches!(c, UserContent::ToolResult(_)))
+                {
+                    "Tool"
+                } else {
+                    "User"
+                },
+                timestamp: entry.timestamp,
+                blocks: content
+                    .iter()
+                    .map(|content| match content {
+                        UserContent::Text(text) => Block::Text(text.text().to_string()),
+                        UserContent::ToolResult(result) => Block::ToolResult {
+                            id: result.id.clone(),
+                            summary: summarize_tool_result(result.content.iter()),
+                        },
+                        UserContent::Image(_) => Block::Media("image"),
+                        UserContent::Audio(_) => Block::Media("audio"),
+                        UserContent::Video(_) => Block::Media("video"),
+                        UserContent::Document(_) => Block::Media("document"),
+                    })
+                    .collect(),
+            },
+            Message::Assistant { content, .. } => Turn {
+                role: "Assistant",
+                timestamp: entry.timestamp,
+                blocks: content
+                    .iter()
+                    .map(|content| match content {
+                        AssistantContent::Text(text) => Block::Text(text.text().to_string()),
+                        AssistantContent::ToolCall(call) => Block::ToolCall {
+                            name: call.function.name.clone(),
+                            arguments: serde_json::to_string_pretty(&call.function.arguments)
+                                .unwrap_or_default(),
+                        },
+                        AssistantContent::Reasoning(reasoning) => {
+                            Block::Reasoning(reasoning.display_text())
+                        }
+                        AssistantContent::Image(_) => Block::Media("image"),
+                    })
+                    .collect(),
+            },
+        })
+        .collect()
+}
+
+fn summarize_tool_result<'a>(content: impl Iterator<Item = &'a ToolResultContent>) -> String {
+    let text = content
+        .map(|content| match content {
+            ToolResultContent::Text(text) => text.text().to_string(),
+            ToolResultContent::Image(_) => "[image]".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let total = text.chars().count();
+    if total <= TOOL_RESULT_SUMMARY_LEN {
+        return text;
+    }
+    let truncated: String = text.chars().take(TOOL_RESULT_SUMMARY_LEN).collect();
+    format!("{}... ({} characters total)", truncated, total)
+}
+
+fn format_time(timestamp: DateTime<Utc>) -> String {
+    timestamp
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+fn header_lines(session: &SavedSession) -> Vec<String> {
+    let meta = &session.meta;
+    let mut lines = vec![
+        format!("Chat ID: {}", session.id),
+        format!("Model: {}", meta.model.as_deref().unwrap_or("unknown")),
+        format!("Created: {}", format_time(meta.created_at)),
+        format!("Updated: {}", format_time(meta.updated_at)),
+    ];
+    if !meta.tags.is_empty() {
+        lines.push(format!("Tags: {}", meta.tags.join(", ")));
+    }
+    lines
+}
+
+fn footer_line(session: &SavedSession) -> String {
+    format!(
+        "{} messages | {} input tokens | {} output tokens",
+        session.chat_history.len(),
+        session.meta.usage.input_tokens,
+        session.meta.usage.output_tokens
+    )
+}
+
+pub fn render(session: &SavedSession, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Markdown => render_markdown(session),
+        ExportFormat::Html => render_html(session),
+        ExportFormat::Text => render_text(session),
+    }
+}
+
+fn render_markdown(session: &SavedSession) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", session.meta.display_title());
+    for line in header_lines(session) {
+        let _ = writeln!(out, "- {}", line);
+    }
+    out.push('\n');
+    for turn in turns(session) {
+        let _ = write!(out, "---\n\n### {}", turn.role);
+        if let Some(timestamp) = turn.timestamp {
+            let _ = write!(out, " <sub>{}</sub>", format_time(timestamp));
+        }
+        out.push_str("\n\n");
+        for block in turn.blocks {
+            match block {
+                Block::Text(text) => {
+                    let _ = writeln!(out, "{}\n", text.trim());
+                }
+                Block::ToolCall { name, arguments } => {
+                    let _ = writeln!(
+                        out,
+                        "**Tool call:** `{}`\n\n```json\n{}\n```\n",
+                        name, arguments
+                    );
+                }
+                Block::ToolResult { id, summary } => {
+                    let _ = writeln!(
+                        out,
+                        "**Tool result** (`{}`):\n\n```\n{}\n```\n",
+                        id, summary
+                    );
+                }
+                Block::Reasoning(text) => {
+                    let _ = writeln!(
+                        out,
+                        "<details><summary>Reasoning</summary>\n\n{}\n\n</details>\n",
+                        text.trim()
+                    );
+                }
+                Block::Media(kind) => {
+                    let _ = writeln!(out, "*[{}]*\n", kind);
+                }
+            }
+        }
+    }
+    let _ = writeln!(out, "---\n\n*{}*", footer_line(session));
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(session: &SavedSession) -> String {
+    let title = escape_html(session.meta.display_title());
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; max-width: 50rem; margin: 2rem auto; line-height: 1.5; }}\n\
+         .turn {{ border-top: 1px solid #ddd; padding: 0.5rem 0; }}\n\
+         .role {{ font-weight: bold; }}\n\
+         .time, footer, .meta {{ color: #777; font-size: 0.85rem; }}\n\
+         pre {{ background: #f5f5f5; padding: 0.5rem; overflow-x: auto; white-space: pre-wrap; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>",
+        title, title
+    );
+    out.push_str("<ul class=\"meta\">\n");
+    for line in header_lines(session) {
+        let _ = writeln!(out, "<li>{}</li>", escape_html(&line));
+    }
+    out.push_str("</ul>\n");
+    for turn in turns(session) {
+        let _ = write!(
+            out,
+            "<div class=\"turn\">\n<span class=\"role\">{}</span>",
+            turn.role
+        );
+        if let Some(timestamp) = turn.timestamp {
+            let _ = write!(
+                out,
+                " <span class=\"time\">{}</span>",
+                format_time(timestamp)
+            );
+        }
+        out.push('\n');
+        for block in turn.blocks {
+            match block {
+                Block::Text(text) => {
+                    let _ = writeln!(
+                        out,
+                        "<p>{}</p>",
+                        escape_html(text.trim()).replace('\n', "<br>\n")
+                    );
+                }
+                Block::ToolCall { name, arguments } => {
+                    let _ = writeln!(
+                        out,
+                        "<p><em>Tool call:</em> <code>{}</code></p>\n<pre>{}</pre>",
+                        escape_html(&name),
+                        escape_html(&arguments)
+                    );
+                }
+                Block::ToolResult { id, summary } => {
+                    let _ = writeln!(
+                        out,
+                        "<p><em>Tool result</em> (<code>{}</code>)</p>\n<pre>{}</pre>",
+                        escape_html(&id),
+                        escape_html(&summary)
+                    );
+                }
+                Block::Reasoning(text) => {
+                    let _ = writeln!(
+                        out,
+                        "<details><summary>Reasoning</summary>\n<pre>{}</pre>\n</details>",
+                        escape_html(text.trim())
+                    );
+                }
+                Block::Media(kind) => {
+                    let _ = writeln!(out, "<p><em>[{}]</em></p>", kind);
+                }
+            }
+        }
+        out.push_str("</div>\n");
+    }
+    let _ = writeln!(
+        out,
+        "<footer>{}</footer>\n</body>\n</html>",
+        escape_html(&footer_line(session))
+    );
+    out
+}
+
+fn render_text(session: &SavedSession) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", session.meta.display_title());
+    for line in header_lines(session) {
+        let _ = writeln!(out, "{}", line);
+    }
+    for turn in turns(session) {
+        let _ = write!(out, "\n{}\n{}", "-".repeat(50), turn.role);
+        if let Some(timestamp) = turn.timestamp {
+            let _ = write!(out, " ({})", format_time(timestamp));
+        }
+        out.push_str(":\n");
+        for block in turn.blocks {
+            match block {
+                Block::Text(text) => {
+                    let _ = writeln!(out, "{}", text.trim());
+                }
+                Block::ToolCall { name, arguments } => {
+                    let _ = writeln!(out, "[tool call: {}]\n{}", name, arguments);
+                }
+                Block::ToolResult { id, summary } => {
+                    let _ = writeln!(out, "[tool result: {}]\n{}", id, summary);
+                }
+                Block::Reasoning(text) => {
+                    let _ = writeln!(out, "[reasoning]\n{}", text.trim());
+                }
+                Block::Media(kind) => {
+                    let _ = writeln!(out, "[{}]", kind);
+                }
+            }
+        }
+    }
+    let _ = writeln!(out, "\n{}\n{}", "-".repeat(50), footer_line(session));
+    out
+}
+
+/// Writes a transcript of `session` to `path`, or `exports/<id>.<ext>` by default. Transcripts
+/// are plain text, so while storage is encrypted this is refused unless `plaintext` says the
+/// user asked for it anyway.
+pub fn export_session(
+    session: &SavedSession,
+    format: ExportFormat,
+    path: Option<PathBuf>,
+    plaintext: bool,
+) -> anyhow::Result<PathBuf> {
+    if vault::is_encrypted() && !plaintext {
+        return Err(vault::VaultError::PlaintextExport.into());
+    }
+    let path = path.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "{}/{}.{}",
+            EXPORTS_DIR_NAME,
+            session.id,
+            format.extension()
+        ))
+    });
+    let transcript = render(session, format);
+    if vault::is_encrypted() {
+        // transcripts are plain text by design, but stay as private as the store they came from
+        write_atomic(&path, transcript.as_bytes())?;
+        return Ok(path);
+    }
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, transcript)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chat::history::ChatHistory, sessions::SessionMeta};
+    use rig::{message::AssistantContent, OneOrMany};
+    use serde_json::json;
+
+    fn session() -> SavedSession {
+        let mut history = ChatHistory::new();
+        history.push(Message::user("Is <T> Send?"));
+        history.push(Message::Assistant {
+            id: None,
+            content: OneOrMany::one(AssistantContent::tool_call(
+                "call-1",
+                "read_file",
+                json!({"path": "a.rs"}),
+            )),
+        });
+        history.push(Message::tool_result("call-1", "fn main() {}"));
+        history.push(Message::assistant("Only if T: Send."));
+        let mut meta = SessionMeta::new();
+        meta.title = Some("Send & <Sync>".to_string());
+        SavedSession::new(7, meta, history)
+    }
+
+    #[test]
+    fn test_render_each_format() {
+        let session = session();
+        let footer = "4 messages | 0 input tokens | 0 output tokens";
+
+        let markdown = render(&session, ExportFormat::Markdown);
+        assert!(markdown.starts_with("# Send & <Sync>\n\n- Chat ID: 7\n"));
+        assert!(markdown.contains("### User <sub>"));
+        assert!(markdown.contains("Is <T> Send?\n"));
+        assert!(markdown
+            .contains("**Tool call:** `read_file`\n\n```json\n{\n  \"path\": \"a.rs\"\n}\n```\n"));
+        assert!(markdown.contains("### Tool"));
+        assert!(markdown.contains("**Tool result** (`call-1`):\n\n```\nfn main() {}\n```\n"));
+        assert!(markdown.ends_with(&format!("---\n\n*{}*\n", footer)));
+
+        let html = render(&session, ExportFormat::Html);
+        assert!(html.contains("<title>Send &amp; &lt;Sync&gt;</title>"));
+        assert!(html.contains("<p>Is &lt;T&gt; Send?</p>"));
+        assert!(html.contains("<pre>{\n  &quot;path&quot;: &quot;a.rs&quot;\n}</pre>"));
+        assert!(html.contains("<p>Only if T: Send.</p>"));
+        assert!(!html.contains("<T>"));
+        assert!(html.ends_with(&format!("<footer>{}</footer>\n</body>\n</html>\n", footer)));
+
+        let text = render(&session, ExportFormat::Text);
+        assert!(text.starts_with("Send & <Sync>\nChat ID: 7\n"));
+        assert!(text.contains("[tool call: read_file]\n{\n  \"path\": \"a.rs\"\n}\n"));
+        assert!(text.contains("[tool result: call-1]\nfn main() {}\n"));
+        assert!(text.ends_with(&format!("{}\n{}\n", "-".repeat(50), footer)));
+    }
+
+    #[test]
+    fn test_long_tool_results_are_summarized() {
+        let long = ToolResultContent::text("word ".repeat(100));
+        let summary = summarize_tool_result(std::iter::once(&long));
+        assert!(summary.ends_with("... (499 characters total)"));
+        assert_eq!(summary.chars().count(), TOOL_RESULT_SUMMARY_LEN + 26);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::sessions::import::{content_text, ImportedConversation, Role};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+fn unix_time(value: Option<&Value>) -> Option<DateTime<Utc>> {
+    let seconds = value?.as_f64()?;
+    DateTime::from_timestamp_millis((seconds * 1000.0) as i64)
+}
+
+/// One entry of a ChatGPT `conversations.json`. Messages form a tree keyed by node ID,
+/// the visible conversation is the path from `current_node` back to the root.
+pub fn parse(conversation: &Value) -> anyhow::Result<ImportedConversation> {
+    let mapping = conversation
+        .get("mapping")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow::anyhow!("ChatGPT conversation has no message mapping"))?;
+    let mut imported = ImportedConversation::new(
+        conversation
+            .get("title")
+            .and_then(Value::as_str)
+            .map(String::from),
+        unix_time(conversation.get("create_time")),
+    );
+    let mut node_id = conversation
+        .get("current_node")
+        .and_then(Value::as_str)
+        .map(String::from);
+    let mut path = Vec::new();
+    while let Some(node) = node_id.and_then(|id| mapping.get(&id)) {
+        path.push(node);
+        node_id = node.get("parent").and_then(Value::as_str).map(String::from);
+        if path.len() > mapping.len() {
+            return Err(anyhow::anyhow!(
+                "ChatGPT conversation has a cycle in its mapping"
+            ));
+        }
+    }
+    for message in path
+        .into_iter()
+        .rev()
+        .filter_map(|node| node.get("message"))
+    {
+        let role = match message.pointer("/author/role").and_then(Value::as_str) {
+            Some("user") => Role::User,
+            Some("assistant") => Role::Assistant,
+            _ => continue,
+        };
+        let text = match message.pointer("/content/parts") {
+            Some(parts) => content_text(parts),
+            None => message
+                .pointer("/content/text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        };
+        imported.push(role, &text, unix_time(message.get("create_time")));
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_follows_current_branch() {
+        let conversation = serde_json::json!({
+            "title": "Lifetimes",
+            "create_time": 1700000000.0,
+            "current_node": "c",
+            "mapping": {
+                "root": { "message": null, "parent": null },
+                "a": { "parent": "root", "message": { "author": { "role": "user" }, "content": { "content_type": "text", "parts": ["what is 'a?"] } } },
+                "b": { "parent": "a", "message": { "author": { "role": "assistant" }, "content": { "content_type": "text", "parts": ["abandoned answer"] } } },
+                "c": { "parent": "a", "message": { "author": { "role": "assistant" }, "content": { "content_type": "text", "parts": ["a lifetime"] } } }
+            }
+        });
+        let imported = parse(&conversation).unwrap();
+        assert_eq!(imported.title.as_deref(), Some("Lifetimes"));
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported.entries[1].1, "a lifetime");
+    }
+}
@@ -0,0 +1,45 @@
+use crate::sessions::import::{content_text, ImportedConversation, Role};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+fn rfc3339(value: Option<&Value>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value?.as_str()?)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// One entry of a Claude.ai `conversations.json`
+pub fn parse(conversation: &Value) -> anyhow::Result<ImportedConversation> {
+    let messages = conversation
+        .get("chat_messages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow::anyhow!("Claude.ai conversation has no chat_messages"))?;
+    let mut imported = ImportedConversation::new(
+        conversation
+            .get("name")
+            .and_then(Value::as_str)
+            .map(String::from),
+        rfc3339(conversation.get("created_at")),
+    );
+    for message in messages {
+        let role = match message.get("sender").and_then(Value::as_str) {
+            Some("human") => Role::User,
+            Some("assistant") => Role::Assistant,
+            _ => continue,
+        };
+        // newer exports keep the text in content blocks, older ones only in `text`
+        let text = message
+            .get("content")
+            .map(content_text)
+            .filter(|text| !text.trim().is_empty())
+            .or_else(|| {
+                message
+                    .get("text")
+                    .and_then(Value::as_str)
+                    .map(String::from)
+            })
+            .unwrap_or_default();
+        imported.push(role, &text, rfc3339(message.get("created_at")));
+    }
+    Ok(imported)
+}
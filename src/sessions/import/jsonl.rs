@@ -0,0 +1,23 @@
+use crate::sessions::import::{content_text, ImportedConversation, Role};
+use serde_json::Value;
+
+/// One `{"role": ..., "content": ...}` object per line, as one conversation
+pub fn parse(contents: &str) -> anyhow::Result<ImportedConversation> {
+    let mut imported = ImportedConversation::new(None, None);
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(line)
+            .map_err(|e| anyhow::anyhow!("line {} is not valid JSON: {}", i + 1, e))?;
+        let role = match value.get("role").and_then(Value::as_str) {
+            Some("user" | "human") => Role::User,
+            Some("assistant" | "model") => Role::Assistant,
+            Some(_) => continue,
+            None => return Err(anyhow::anyhow!("line {} has no role", i + 1)),
+        };
+        let text = value.get("content").map(content_text).unwrap_or_default();
+        imported.push(role, &text, None);
+    }
+    Ok(imported)
+}
@@ -0,0 +1,147 @@
+pub mod chatgpt;
+pub mod claude;
+pub mod jsonl;
+
+use crate::chat::history::{ChatHistory, HistoryEntry};
+use chrono::{DateTime, Utc};
+use rig::message::Message;
+use serde_json::Value;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportSource {
+    ChatGpt,
+    Claude,
+    Jsonl,
+}
+
+impl ImportSource {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::ChatGpt => "chatgpt",
+            Self::Claude => "claude.ai",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// A conversation from another assistant, converted to Rig messages
+#[derive(Debug)]
+pub struct ImportedConversation {
+    pub title: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub entries: Vec<(Role, String, Option<DateTime<Utc>>)>,
+}
+
+impl ImportedConversation {
+    pub fn new(title: Option<String>, created_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            title: title.filter(|title| !title.trim().is_empty()),
+            created_at,
+            entries: Vec::new(),
+        }
+    }
+    /// Adds a turn, merging consecutive turns from the same role so roles keep alternating
+    pub fn push(&mut self, role: Role, text: &str, timestamp: Option<DateTime<Utc>>) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        match self.entries.last_mut() {
+            Some((last_role, last_text, _)) if *last_role == role => {
+                last_text.push_str("\n\n");
+                last_text.push_str(text);
+            }
+            _ => self.entries.push((role, text.to_string(), timestamp)),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn first_prompt(&self) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(role, _, _)| *role == Role::User)
+            .map(|(_, text, _)| text.as_str())
+    }
+    pub fn to_chat_history(&self) -> ChatHistory {
+        self.entries
+            .iter()
+            .map(|(role, text, timestamp)| HistoryEntry {
+                timestamp: *timestamp,
+                tokens: None,
+                message: match role {
+                    Role::User => Message::user(text),
+                    Role::Assistant => Message::assistant(text),
+                },
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+/// Reads an export file, detecting which assistant produced it
+pub fn read_conversations(
+    path: impl AsRef<Path>,
+) -> anyhow::Result<(ImportSource, Vec<ImportedConversation>)> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)?;
+    if path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
+        return Ok((ImportSource::Jsonl, vec![jsonl::parse(&contents)?]));
+    }
+    let value: Value = serde_json::from_str(&contents)?;
+    let conversations = match value {
+        Value::Array(conversations) => conversations,
+        conversation @ Value::Object(_) => vec![conversation],
+        _ => {
+            return Err(anyhow::anyhow!(
+                "{} is not a conversation export",
+                path.display()
+            ))
+        }
+    };
+    let first = conversations.first();
+    let source = if first.is_some_and(|c| c.get("mapping").is_some()) {
+        ImportSource::ChatGpt
+    } else if first.is_some_and(|c| c.get("chat_messages").is_some()) {
+        ImportSource::Claude
+    } else {
+        return Err(anyhow::anyhow!(
+            "{} is not a ChatGPT or Claude.ai export (use .jsonl for role/content lines)",
+            path.display()
+        ));
+    };
+    let conversations = conversations
+        .iter()
+        .map(|conversation| match source {
+            ImportSource::ChatGpt => chatgpt::parse(conversation),
+            _ => claude::parse(conversation),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok((source, conversations))
+}
+
+/// Text from either a plain string or a list of `{ "type": "text", "text": ... }` blocks
+pub(super) fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| match block {
+                Value::String(text) => Some(text.as_str()),
+                block => block.get("text").and_then(Value::as_str),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
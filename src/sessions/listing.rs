@@ -0,0 +1,141 @@
+use crate::sessions::{first_prompt, load_session, session_ids, SessionMeta};
+use chrono::{Local, NaiveDate};
+
+const PREVIEW_LEN: usize = 60;
+
+#[derive(Debug, Clone)]
+pub struct SessionListing {
+    pub id: u16,
+    pub meta: SessionMeta,
+    pub message_count: usize,
+    pub preview: Option<String>,
+}
+
+/// Every saved chat, most recently updated first
+pub fn list_sessions() -> anyhow::Result<Vec<SessionListing>> {
+    let mut listings = Vec::new();
+    for id in session_ids()? {
+        let Ok(session) = load_session(id) else {
+            continue;
+        };
+        let preview = first_prompt(session.chat_history.iter()).map(|prompt| {
+            let prompt = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+            match prompt.char_indices().nth(PREVIEW_LEN) {
+                Some((i, _)) => format!("{}...", &prompt[..i]),
+                None => prompt,
+            }
+        });
+        listings.push(SessionListing {
+            id,
+            meta: session.meta,
+            message_count: session.chat_history.len(),
+            preview,
+        });
+    }
+    listings.sort_by_key(|listing| std::cmp::Reverse(listing.meta.updated_at));
+    Ok(listings)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChatsFilter {
+    pub tag: Option<String>,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+impl ChatsFilter {
+    pub fn matches(&self, listing: &SessionListing) -> bool {
+        let updated = listing.meta.updated_at.with_timezone(&Local).date_naive();
+        self.tag
+            .as_deref()
+            .is_none_or(|tag| listing.meta.has_tag(tag))
+            && self.since.is_none_or(|since| updated >= since)
+            && self.until.is_none_or(|until| updated <= until)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.tag.is_none() && self.since.is_none() && self.until.is_none()
+    }
+}
+
+/// A saved chat referenced either by its ID or by (part of) its title
+#[derive(Debug, Clone)]
+pub enum ChatRef {
+    Id(u16),
+    Title(String),
+}
+
+impl From<&str> for ChatRef {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        match value.parse::<u16>() {
+            Ok(id) => Self::Id(id),
+            Err(_) => Self::Title(value.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ChatRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "ID: {}", id),
+            Self::Title(title) => write!(f, "title: \"{}\"", title),
+        }
+    }
+}
+
+impl ChatRef {
+    /// Resolves to the listing with the matching ID, or the closest fuzzy title match
+    pub fn resolve<'a>(&self, listings: &'a [SessionListing]) -> Option<&'a SessionListing> {
+        match self {
+            Self::Id(id) => listings.iter().find(|listing| listing.id == *id),
+            Self::Title(query) => listings
+                .iter()
+                .filter_map(|listing| {
+                    fuzzy_score(listing.meta.title.as_deref()?, query).map(|score| (score, listing))
+                })
+                .min_by_key(|(score, _)| *score)
+                .map(|(_, listing)| listing),
+        }
+    }
+}
+
+/// Lower is better, `None` when `query` does not match `title` at all
+fn fuzzy_score(title: &str, query: &str) -> Option<usize> {
+    let title = title.to_lowercase();
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    if title == query {
+        return Some(0);
+    }
+    if title.contains(&query) {
+        return Some(1 + title.len() - query.len());
+    }
+    // every query char must appear in order, gaps between them add to the score
+    let mut gaps = 0;
+    let mut title_chars = title.chars();
+    for q in query.chars() {
+        loop {
+            match title_chars.next() {
+                Some(t) if t == q => break,
+                Some(_) => gaps += 1,
+                None => return None,
+            }
+        }
+    }
+    Some(1_000 + gaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("Pin and Unpin", "pin and unpin"), Some(0));
+        assert!(fuzzy_score("Pin and Unpin", "unpin") < fuzzy_score("Pin and Unpin", "pnunp"));
+        assert!(fuzzy_score("Pin and Unpin", "pnunp").is_some());
+        assert_eq!(fuzzy_score("Pin and Unpin", "lifetimes"), None);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::chat::settings::AgentSettings;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::SystemTime};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMeta {
+    #[serde(default)]
+    pub title: Option<String>,
+    /// One paragraph written in the background once the chat is first saved
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub settings: Option<AgentSettings>,
+    #[serde(default)]
+    pub usage: TokenUsage,
+    /// Copies of the chat saved before each compaction, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archives: Vec<PathBuf>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SessionMeta {
+    pub fn new() -> Self {
+        let now = Utc::now();
+        Self {
+            title: None,
+            summary: None,
+            tags: Vec::new(),
+            model: None,
+            settings: None,
+            usage: TokenUsage::default(),
+            archives: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    /// Fallback for chats saved before any metadata was recorded
+    pub fn from_modified(modified: SystemTime) -> Self {
+        let modified = DateTime::<Utc>::from(modified);
+        Self {
+            created_at: modified,
+            updated_at: modified,
+            ..Self::new()
+        }
+    }
+    pub fn display_title(&self) -> &str {
+        self.title.as_deref().unwrap_or("(untitled)")
+    }
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+impl Default for SessionMeta {
+    fn default() -> Self {
+        Self::new()
+    }
+}
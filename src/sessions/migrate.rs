@@ -0,0 +1,205 @@
+use crate::{
+    chat::{history::HistoryNode, CHATS_DIR_NAME},
+    sessions::{vault, SavedSession, SessionMeta},
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Format version written by this build, bump it alongside a new step in `upgrade`
+pub const CURRENT_VERSION: u64 = 3;
+
+/// Sidecar metadata from before sessions carried their own, only read to migrate version 0
+const LEGACY_INDEX_FILE_NAME: &str = "index.json";
+
+#[derive(Debug, Error)]
+pub enum SessionFileError {
+    #[error("chat {id} is not valid JSON (line {line}, column {column}): {source}")]
+    InvalidJson {
+        id: u16,
+        line: usize,
+        column: usize,
+        source: serde_json::Error,
+    },
+    #[error("chat {id} is neither a message list nor a session object")]
+    UnexpectedLayout { id: u16 },
+    #[error("chat {id} has no format version")]
+    MissingVersion { id: u16 },
+    #[error(
+        "chat {id} uses format version {version}, this build reads up to version {CURRENT_VERSION}"
+    )]
+    UnsupportedVersion { id: u16, version: u64 },
+    #[error("chat {id} message {index} could not be read: {source}")]
+    InvalidMessage {
+        id: u16,
+        index: usize,
+        source: serde_json::Error,
+    },
+    #[error("chat {id} has a message that does not follow an earlier one")]
+    InvalidBranch { id: u16 },
+    #[error("chat {id} has invalid session metadata: {source}")]
+    InvalidMetadata { id: u16, source: serde_json::Error },
+}
+
+/// Parses a saved chat of any known format version, upgrading it to the current one.
+/// Also returns the version it was stored as when an upgrade happened.
+pub fn parse_session(
+    id: u16,
+    json: &str,
+    fallback_meta: impl FnOnce() -> SessionMeta,
+) -> Result<(SavedSession, Option<u64>), SessionFileError> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|source| SessionFileError::InvalidJson {
+            id,
+            line: source.line(),
+            column: source.column(),
+            source,
+        })?;
+    let stored_version = match &value {
+        // version 0 was a bare `Vec<Message>`
+        Value::Array(_) => 0,
+        Value::Object(object) => object
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or(SessionFileError::MissingVersion { id })?,
+        _ => return Err(SessionFileError::UnexpectedLayout { id }),
+    };
+    if stored_version > CURRENT_VERSION {
+        return Err(SessionFileError::UnsupportedVersion {
+            id,
+            version: stored_version,
+        });
+    }
+    let mut value = value;
+    let mut fallback_meta = Some(fallback_meta);
+    for version in stored_version..CURRENT_VERSION {
+        value = upgrade(id, version, value, || {
+            fallback_meta.take().map(|f| f()).unwrap_or_default()
+        })?;
+    }
+    let Value::Object(mut object) = value else {
+        return Err(SessionFileError::UnexpectedLayout { id });
+    };
+    // messages are read one by one so errors can point at the offending message
+    let messages = match object.insert("messages".to_string(), Value::Array(Vec::new())) {
+        Some(Value::Array(messages)) => messages,
+        _ => Vec::new(),
+    };
+    let nodes = messages
+        .into_iter()
+        .enumerate()
+        .map(|(index, node)| {
+            serde_json::from_value::<HistoryNode>(node)
+                .map_err(|source| SessionFileError::InvalidMessage { id, index, source })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut session: SavedSession = serde_json::from_value(Value::Object(object))
+        .map_err(|source| SessionFileError::InvalidMetadata { id, source })?;
+    session.chat_history = std::mem::take(&mut session.chat_history)
+        .with_tree(nodes)
+        .ok_or(SessionFileError::InvalidBranch { id })?;
+    let migrated_from = (stored_version < CURRENT_VERSION).then_some(stored_version);
+    Ok((session, migrated_from))
+}
+
+/// Upgrades a session from `version` to `version + 1`
+fn upgrade(
+    id: u16,
+    version: u64,
+    value: Value,
+    fallback_meta: impl FnOnce() -> SessionMeta,
+) -> Result<Value, SessionFileError> {
+    match (version, value) {
+        (0, messages @ Value::Array(_)) => {
+            let meta = legacy_index_meta(id).unwrap_or_else(fallback_meta);
+            let mut object = match serde_json::to_value(meta) {
+                Ok(Value::Object(object)) => object,
+                _ => Map::new(),
+            };
+            object.insert("version".to_string(), Value::from(1));
+            object.insert("id".to_string(), Value::from(id));
+            object.insert("messages".to_string(), messages);
+            Ok(Value::Object(object))
+        }
+        // version 2 records when each message was added, older messages get no timestamp
+        (1, Value::Object(mut object)) => {
+            let messages = match object.remove("messages") {
+                Some(Value::Array(messages)) => messages,
+                _ => Vec::new(),
+            };
+            let entries = messages
+                .into_iter()
+                .map(|message| {
+                    let mut entry = Map::new();
+                    entry.insert("timestamp".to_string(), Value::Null);
+                    entry.insert("message".to_string(), message);
+                    Value::Object(entry)
+                })
+                .collect();
+            object.insert("version".to_string(), Value::from(2));
+            object.insert("messages".to_string(), Value::Array(entries));
+            Ok(Value::Object(object))
+        }
+        // version 3 stores messages as a tree, a flat list becomes a single branch
+        (2, Value::Object(mut object)) => {
+            let mut entries = match object.remove("messages") {
+                Some(Value::Array(entries)) => entries,
+                _ => Vec::new(),
+            };
+            for (i, entry) in entries.iter_mut().enumerate() {
+                if let Value::Object(entry) = entry {
+                    let parent = i.checked_sub(1).map_or(Value::Null, Value::from);
+                    entry.insert("parent".to_string(), parent);
+                }
+            }
+            let head = entries
+                .len()
+                .checked_sub(1)
+                .map_or(Value::Null, Value::from);
+            object.insert("version".to_string(), Value::from(3));
+            object.insert("head".to_string(), head);
+            object.insert("messages".to_string(), Value::Array(entries));
+            Ok(Value::Object(object))
+        }
+        _ => Err(SessionFileError::UnexpectedLayout { id }),
+    }
+}
+
+fn legacy_index_meta(id: u16) -> Option<SessionMeta> {
+    let path = format!("{}/{}", CHATS_DIR_NAME, LEGACY_INDEX_FILE_NAME);
+    let json = vault::read_to_string(path).ok()?;
+    let mut index: BTreeMap<u16, SessionMeta> = serde_json::from_str(&json).ok()?;
+    index.remove(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_session_upgrades_bare_message_list() {
+        let json = r#"[{"role":"user","content":[{"type":"text","text":"hi"}]}]"#;
+        let (session, migrated_from) =
+            parse_session(u16::MAX, json, SessionMeta::new).expect("version 0 should migrate");
+        assert_eq!(migrated_from, Some(0));
+        assert_eq!(session.version, CURRENT_VERSION);
+        assert_eq!(session.chat_history.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_session_reports_bad_message_index() {
+        let json = r#"[{"role":"user","content":[{"type":"text","text":"hi"}]},{"role":"robot"}]"#;
+        let err = parse_session(u16::MAX, json, SessionMeta::new).unwrap_err();
+        assert!(matches!(
+            err,
+            SessionFileError::InvalidMessage { index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_session_rejects_newer_versions() {
+        let json = format!(r#"{{"version":{},"messages":[]}}"#, CURRENT_VERSION + 1);
+        let err = parse_session(u16::MAX, &json, SessionMeta::new).unwrap_err();
+        assert!(matches!(err, SessionFileError::UnsupportedVersion { .. }));
+    }
+}
@@ -0,0 +1,206 @@
+pub mod archive;
+pub mod export;
+pub mod import;
+pub mod listing;
+pub mod meta;
+pub mod migrate;
+pub mod recovery;
+pub mod search;
+pub mod vault;
+
+pub use listing::{list_sessions, ChatRef, ChatsFilter, SessionListing};
+pub use meta::{SessionMeta, TokenUsage};
+
+use crate::chat::{
+    history::{prompt_text, ChatHistory},
+    CHATS_DIR_NAME,
+};
+use migrate::{parse_session, CURRENT_VERSION};
+use rig::message::Message;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, ffi::OsString, io::Write, path::Path, path::PathBuf};
+
+pub fn session_path(id: u16) -> PathBuf {
+    PathBuf::from(format!("{}/{}.json", CHATS_DIR_NAME, id))
+}
+
+/// Writes to a sibling temp file and renames it over `path`, so a crash mid-write never
+/// leaves a truncated file behind. Files and directories created here are private to the user.
+pub fn write_atomic(path: impl AsRef<Path>, bytes: &[u8]) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(parent)?;
+    }
+    let tmp_path = {
+        let mut tmp_path = OsString::from(path);
+        tmp_path.push(".tmp");
+        PathBuf::from(tmp_path)
+    };
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// IDs of every chat saved in the `chats/` directory
+pub fn session_ids() -> anyhow::Result<Vec<u16>> {
+    std::fs::create_dir_all(CHATS_DIR_NAME)?;
+    let mut ids: Vec<u16> = std::fs::read_dir(CHATS_DIR_NAME)?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .filter(|ent| ent.path().extension().and_then(|ostr| ostr.to_str()) == Some("json"))
+        .flat_map(|ent| {
+            ent.path()
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|str| str.parse::<u16>().ok())
+        })
+        .collect();
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Lowest ID not taken by a saved chat or by `active`, the running chat, which has no file
+/// until it is first saved
+pub fn next_session_id(active: Option<u16>) -> anyhow::Result<u16> {
+    free_session_id(&session_ids()?, active)
+        .ok_or_else(|| anyhow::anyhow!("Chat count has hit its limit of {}", u16::MAX))
+}
+
+fn free_session_id(saved: &[u16], active: Option<u16>) -> Option<u16> {
+    let taken: HashSet<u16> = saved.iter().copied().chain(active).collect();
+    (0..u16::MAX).find(|id| !taken.contains(id))
+}
+
+/// A saved chat with everything needed to continue it, as stored in `chats/<id>.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub version: u64,
+    pub id: u16,
+    #[serde(flatten)]
+    pub meta: SessionMeta,
+    #[serde(default)]
+    pub preamble_hash: Option<String>,
+    #[serde(flatten)]
+    pub chat_history: ChatHistory,
+}
+
+impl SavedSession {
+    pub fn new(id: u16, meta: SessionMeta, chat_history: ChatHistory) -> Self {
+        let preamble_hash = meta
+            .settings
+            .as_ref()
+            .map(|settings| settings.preamble_hash());
+        Self {
+            version: CURRENT_VERSION,
+            id,
+            meta,
+            preamble_hash,
+            chat_history,
+        }
+    }
+}
+
+/// Reads a saved chat, upgrading files written in older formats in place
+/// (the original is kept next to it as `<id>.json.v<version>.bak`)
+pub fn load_session(id: u16) -> anyhow::Result<SavedSession> {
+    let path = session_path(id);
+    let json = vault::read_to_string(&path)?;
+    let (session, migrated_from) = parse_session(id, &json, || {
+        std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .map(SessionMeta::from_modified)
+            .unwrap_or_default()
+    })?;
+    if let Some(version) = migrated_from {
+        let mut backup_path = OsString::from(&path);
+        backup_path.push(format!(".v{}.bak", version));
+        std::fs::copy(&path, backup_path)?;
+        write_session(&session)?;
+    }
+    Ok(session)
+}
+
+pub fn write_session(session: &SavedSession) -> anyhow::Result<()> {
+    write_atomic(
+        session_path(session.id),
+        &vault::seal(&serde_json::to_vec_pretty(session)?)?,
+    )?;
+    // a stale index is rebuilt on the next search, so this never fails the save
+    if let Err(e) = search::index_session(session) {
+        eprintln!("Search index update failed: {:#}", e);
+    }
+    Ok(())
+}
+
+/// Applies `update` to a saved chat's metadata and writes it back
+pub fn update_session_meta(
+    id: u16,
+    update: impl FnOnce(&mut SessionMeta),
+) -> anyhow::Result<SessionMeta> {
+    let mut session = load_session(id)?;
+    update(&mut session.meta);
+    write_session(&session)?;
+    Ok(session.meta)
+}
+
+/// The most recently updated saved chat, optionally skipping one (usually the active chat)
+pub fn most_recent_session(except: Option<u16>) -> anyhow::Result<Option<u16>> {
+    Ok(list_sessions()?
+        .into_iter()
+        .map(|listing| listing.id)
+        .find(|id| Some(*id) != except))
+}
+
+pub fn delete_session(id: u16) -> anyhow::Result<()> {
+    std::fs::remove_file(session_path(id))?;
+    recovery::mark_closed(id)?;
+    search::remove_from_index(id)?;
+    archive::remove_archives(id)?;
+    Ok(())
+}
+
+/// Text of the first user message, used as a preview in listings
+pub fn first_prompt<'a>(mut messages: impl Iterator<Item = &'a Message>) -> Option<&'a str> {
+    messages.find_map(prompt_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_whole_file() {
+        let dir = std::env::temp_dir().join(format!("marvin-atomic-{}", std::process::id()));
+        let path = dir.join("chats/1.json");
+        write_atomic(&path, b"first, and longer").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert!(!dir.join("chats/1.json.tmp").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| path.metadata().unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&path), 0o600);
+            assert_eq!(mode(&dir.join("chats")), 0o700);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_into_unsaved_chat_gets_its_own_id() {
+        // a fresh chat holds ID 0 before anything is saved
+        assert_eq!(free_session_id(&[], Some(0)), Some(1));
+        assert_eq!(free_session_id(&[0, 1, 3], Some(2)), Some(4));
+        assert_eq!(free_session_id(&[1], None), Some(0));
+    }
+}
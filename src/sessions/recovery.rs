@@ -0,0 +1,34 @@
+use crate::{
+    chat::CHATS_DIR_NAME,
+    sessions::{session_ids, session_path},
+};
+use std::{io::ErrorKind, path::PathBuf};
+
+/// Present while a chat is in progress, removed on a clean `/exit`
+fn open_marker_path(id: u16) -> PathBuf {
+    PathBuf::from(format!("{}/{}.open", CHATS_DIR_NAME, id))
+}
+
+pub fn mark_open(id: u16) -> anyhow::Result<()> {
+    let path = open_marker_path(id);
+    if !path.exists() {
+        std::fs::create_dir_all(CHATS_DIR_NAME)?;
+        std::fs::write(path, std::process::id().to_string())?;
+    }
+    Ok(())
+}
+
+pub fn mark_closed(id: u16) -> anyhow::Result<()> {
+    match std::fs::remove_file(open_marker_path(id)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Chats that were autosaved but never closed, e.g. after a panic or a closed terminal
+pub fn unclosed_sessions() -> anyhow::Result<Vec<u16>> {
+    Ok(session_ids()?
+        .into_iter()
+        .filter(|id| open_marker_path(*id).exists() && session_path(*id).exists())
+        .collect())
+}
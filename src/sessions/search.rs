@@ -0,0 +1,376 @@
+use crate::{
+    chat::CHATS_DIR_NAME,
+    sessions::{load_session, session_ids, session_path, vault, SavedSession},
+};
+use chrono::{DateTime, Utc};
+use rig::message::{AssistantContent, Message, ToolResultContent, UserContent};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::ErrorKind,
+    ops::Range,
+    path::PathBuf,
+};
+
+/// Per-chat indexes live in `chats/search/<id>.json`, so saving a chat only rewrites its own
+pub const SEARCH_INDEX_DIR_NAME: &str = "search";
+const TITLE_BOOST: f64 = 2.0;
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+const SNIPPET_BEFORE: usize = 60;
+const SNIPPET_AFTER: usize = 120;
+
+/// Term counts for one searchable piece of a chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedDocument {
+    /// Position on the active branch, `None` for the title
+    position: Option<usize>,
+    length: usize,
+    terms: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionIndex {
+    /// Modification time of the chat file when it was indexed, a different one means stale
+    session_modified: Option<DateTime<Utc>>,
+    title: String,
+    updated_at: DateTime<Utc>,
+    documents: Vec<IndexedDocument>,
+}
+
+/// Best matching piece of a chat for a query
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub id: u16,
+    pub title: String,
+    pub updated_at: DateTime<Utc>,
+    pub score: f64,
+    /// Who wrote the matching text, `"Title"` when only the title matched
+    pub role: &'static str,
+    pub snippet: String,
+    /// Byte ranges of `snippet` that matched a query term
+    pub highlights: Vec<Range<usize>>,
+}
+
+fn index_path(id: u16) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/{}/{}.json",
+        CHATS_DIR_NAME, SEARCH_INDEX_DIR_NAME, id
+    ))
+}
+
+fn session_modified(id: u16) -> Option<DateTime<Utc>> {
+    std::fs::metadata(session_path(id))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(DateTime::<Utc>::from)
+}
+
+/// Lowercased words with their byte ranges in `text`
+fn tokens(text: &str) -> impl Iterator<Item = (Range<usize>, String)> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while chars.peek().is_some_and(|(_, c)| !c.is_alphanumeric()) {
+            chars.next();
+        }
+        let (start, _) = *chars.peek()?;
+        let mut end = start;
+        while let Some((i, c)) = chars.peek().copied().filter(|(_, c)| c.is_alphanumeric()) {
+            end = i + c.len_utf8();
+            chars.next();
+        }
+        Some((start..end, text[start..end].to_lowercase()))
+    })
+}
+
+/// Query terms of three or more characters also match longer words they start, so
+/// "pin" finds "pinned" and "pinning"
+fn term_matches(term: &str, query_term: &str) -> bool {
+    term == query_term || (query_term.chars().count() >= 3 && term.starts_with(query_term))
+}
+
+/// Searchable text of a message with who it came from
+fn message_text(message: &Message) -> (&'static str, String) {
+    match message {
+        Message::User { content } => {
+            let role = if content
+                .iter()
+                .all(|c| matches!(c, UserContent::ToolResult(_)))
+            {
+                "Tool"
+            } else {
+                "User"
+            };
+            let text = content
+                .iter()
+                .filter_map(|content| match content {
+                    UserContent::Text(text) => Some(text.text().to_string()),
+                    UserContent::ToolResult(result) => Some(
+                        result
+                            .content
+                            .iter()
+                            .filter_map(|content| match content {
+                                ToolResultContent::Text(text) => Some(text.text()),
+                                ToolResultContent::Image(_) => None,
+                            })
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    ),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            (role, text)
+        }
+        Message::Assistant { content, .. } => {
+            let text = content
+                .iter()
+                .filter_map(|content| match content {
+                    AssistantContent::Text(text) => Some(text.text()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            ("Assistant", text)
+        }
+    }
+}
+
+fn index_document(position: Option<usize>, text: &str) -> IndexedDocument {
+    let mut terms = BTreeMap::new();
+    let mut length = 0;
+    for (_, term) in tokens(text) {
+        *terms.entry(term).or_insert(0) += 1;
+        length += 1;
+    }
+    IndexedDocument {
+        position,
+        length,
+        terms,
+    }
+}
+
+/// Reindexes a chat after it was written, called from `write_session`
+pub fn index_session(session: &SavedSession) -> anyhow::Result<()> {
+    let index = build_index(session);
+    super::write_atomic(
+        index_path(session.id),
+        &vault::seal(&serde_json::to_vec(&index)?)?,
+    )
+}
+
+fn build_index(session: &SavedSession) -> SessionIndex {
+    let title = session.meta.display_title().to_string();
+    let mut documents = vec![index_document(
+        None,
+        session.meta.title.as_deref().unwrap_or(""),
+    )];
+    documents.extend(
+        session
+            .chat_history
+            .iter()
+            .enumerate()
+            .map(|(position, message)| index_document(Some(position), &message_text(message).1)),
+    );
+    SessionIndex {
+        session_modified: session_modified(session.id),
+        title,
+        updated_at: session.meta.updated_at,
+        documents,
+    }
+}
+
+pub fn remove_from_index(id: u16) -> anyhow::Result<()> {
+    match std::fs::remove_file(index_path(id)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Index of every saved chat, reindexing the ones changed outside `write_session`
+fn load_index() -> anyhow::Result<Vec<(u16, SessionIndex)>> {
+    let ids = session_ids()?;
+    let mut indexes = Vec::new();
+    for id in &ids {
+        let cached = vault::read(index_path(*id))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<SessionIndex>(&bytes).ok())
+            .filter(|index| index.session_modified == session_modified(*id));
+        let index = match cached {
+            Some(index) => index,
+            None => {
+                let Ok(session) = load_session(*id) else {
+                    continue;
+                };
+                let index = build_index(&session);
+                super::write_atomic(index_path(*id), &vault::seal(&serde_json::to_vec(&index)?)?)?;
+                index
+            }
+        };
+        indexes.push((*id, index));
+    }
+    // drop indexes of chats deleted by hand
+    if let Ok(entries) = std::fs::read_dir(format!("{}/{}", CHATS_DIR_NAME, SEARCH_INDEX_DIR_NAME))
+    {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let orphaned = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u16>().ok())
+                .is_some_and(|id| !ids.contains(&id));
+            if orphaned {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+    Ok(indexes)
+}
+
+/// BM25 score of every document against `query_terms`, keyed by chat and document index.
+/// Documents matching only some of the terms are scaled down by the share they match.
+fn score_documents(
+    indexes: &[(u16, SessionIndex)],
+    query_terms: &[String],
+) -> HashMap<(usize, usize), f64> {
+    let documents: Vec<_> = indexes
+        .iter()
+        .enumerate()
+        .flat_map(|(s, (_, index))| {
+            index
+                .documents
+                .iter()
+                .enumerate()
+                .map(move |(d, doc)| ((s, d), doc))
+        })
+        .collect();
+    let total = documents.len() as f64;
+    let average_length =
+        documents.iter().map(|(_, doc)| doc.length).sum::<usize>() as f64 / total.max(1.0);
+    let mut scores: HashMap<(usize, usize), (f64, usize)> = HashMap::new();
+    for query_term in query_terms {
+        let frequencies: Vec<_> = documents
+            .iter()
+            .filter_map(|(key, doc)| {
+                let frequency: usize = doc
+                    .terms
+                    .iter()
+                    .filter(|(term, _)| term_matches(term, query_term))
+                    .map(|(_, count)| count)
+                    .sum();
+                (frequency > 0).then_some((*key, doc, frequency as f64))
+            })
+            .collect();
+        let matching = frequencies.len() as f64;
+        let idf = (1.0 + (total - matching + 0.5) / (matching + 0.5)).ln();
+        for (key, doc, frequency) in frequencies {
+            let length_norm = 1.0 - BM25_B + BM25_B * doc.length as f64 / average_length.max(1.0);
+            let mut score = idf * frequency * (BM25_K1 + 1.0) / (frequency + BM25_K1 * length_norm);
+            if doc.position.is_none() {
+                score *= TITLE_BOOST;
+            }
+            let entry = scores.entry(key).or_default();
+            entry.0 += score;
+            entry.1 += 1;
+        }
+    }
+    scores
+        .into_iter()
+        .map(|(key, (score, matched))| (key, score * matched as f64 / query_terms.len() as f64))
+        .collect()
+}
+
+/// Searches the titles, messages and tool results of every saved chat, best chat first
+pub fn search_sessions(query: &str, limit: usize) -> anyhow::Result<Vec<SearchHit>> {
+    let query_terms: Vec<String> = tokens(query).map(|(_, term)| term).collect();
+    if query_terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let indexes = load_index()?;
+    let mut best: HashMap<usize, (usize, f64)> = HashMap::new();
+    for ((s, d), score) in score_documents(&indexes, &query_terms) {
+        let entry = best.entry(s).or_insert((d, score));
+        if score > entry.1 {
+            *entry = (d, score);
+        }
+    }
+    let mut ranked: Vec<_> = best.into_iter().collect();
+    ranked.sort_by(|(a, (_, a_score)), (b, (_, b_score))| {
+        b_score
+            .total_cmp(a_score)
+            .then(indexes[*b].1.updated_at.cmp(&indexes[*a].1.updated_at))
+    });
+    let mut hits = Vec::new();
+    for (s, (d, score)) in ranked.into_iter().take(limit) {
+        let (id, index) = &indexes[s];
+        let (role, text) = match index.documents[d].position {
+            Some(position) => match load_session(*id)
+                .ok()
+                .and_then(|session| session.chat_history.iter().nth(position).map(message_text))
+            {
+                Some(found) => found,
+                None => continue,
+            },
+            None => ("Title", index.title.clone()),
+        };
+        let (snippet, highlights) = snippet(&text, &query_terms);
+        hits.push(SearchHit {
+            id: *id,
+            title: index.title.clone(),
+            updated_at: index.updated_at,
+            score,
+            role,
+            snippet,
+            highlights,
+        });
+    }
+    Ok(hits)
+}
+
+/// A window of `text` around its first match, whitespace collapsed, with the matching words
+fn snippet(text: &str, query_terms: &[String]) -> (String, Vec<Range<usize>>) {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let matches: Vec<_> = tokens(&text)
+        .filter(|(_, term)| {
+            query_terms
+                .iter()
+                .any(|query_term| term_matches(term, query_term))
+        })
+        .map(|(range, _)| range)
+        .collect();
+    let first = matches.first().map_or(0, |range| range.start);
+    let floor = |mut i: usize| {
+        while !text.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    };
+    let start = floor(first.saturating_sub(SNIPPET_BEFORE));
+    let end = floor((first + SNIPPET_AFTER).min(text.len()));
+    let prefix = if start > 0 { "..." } else { "" };
+    let suffix = if end < text.len() { "..." } else { "" };
+    let snippet = format!("{}{}{}", prefix, &text[start..end], suffix);
+    let highlights = matches
+        .into_iter()
+        .filter(|range| range.start >= start && range.end <= end)
+        .map(|range| range.start - start + prefix.len()..range.end - start + prefix.len())
+        .collect();
+    (snippet, highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_highlights_prefix_matches() {
+        let (snippet, highlights) = snippet("Why  does Pinning matter?", &["pin".to_string()]);
+        assert_eq!(snippet, "Why does Pinning matter?");
+        assert_eq!(
+            highlights
+                .iter()
+                .map(|range| &snippet[range.clone()])
+                .collect::<Vec<_>>(),
+            vec!["Pinning"]
+        );
+    }
+}
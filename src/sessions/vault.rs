@@ -0,0 +1,417 @@
+use crate::{chat::CHATS_DIR_NAME, sessions::write_atomic};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Present once storage is encrypted, holds what is needed to derive and check the key
+pub const VAULT_FILE_NAME: &str = "vault.json";
+/// The header `rekey` switches to, present only until the switch is committed
+const STAGED_VAULT_FILE_NAME: &str = "vault.json.new";
+/// Suffix of a file rewritten by `rekey`, renamed over the original once the header is switched
+const STAGED_SUFFIX: &str = ".rekey";
+/// Sealed files start with this so plain files written before encryption still read
+const MAGIC: &[u8] = b"MARVIN-SEALED-1\n";
+const NONCE_LEN: usize = 24;
+const SALT_LEN: usize = 16;
+const CHECK_PLAINTEXT: &[u8] = b"marvin vault check";
+const UNLOCK_ATTEMPTS: usize = 3;
+
+/// The key of the unlocked vault, wiped on `/lock`
+static KEY: RwLock<Option<Zeroizing<[u8; 32]>>> = RwLock::new(None);
+
+#[derive(Debug, Error)]
+pub enum VaultError {
+    #[error("chat storage is locked")]
+    Locked,
+    #[error("wrong passphrase or keyfile")]
+    WrongKey,
+    #[error("{path} is damaged or was sealed with another key")]
+    Damaged { path: String },
+    #[error("chat storage is encrypted but transcripts are plain text, add --plaintext to export anyway")]
+    PlaintextExport,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    Passphrase,
+    Keyfile,
+}
+
+/// What `marvin rekey` should protect storage with from now on
+#[derive(Debug, Clone)]
+pub enum NewKey {
+    Passphrase,
+    Keyfile(PathBuf),
+    /// Decrypt everything and turn encryption off
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultHeader {
+    version: u32,
+    key_source: KeySource,
+    salt: Vec<u8>,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    /// `CHECK_PLAINTEXT` sealed with the key, tells a wrong passphrase from a damaged chat
+    check: Vec<u8>,
+}
+
+fn vault_path() -> PathBuf {
+    PathBuf::from(format!("{}/{}", CHATS_DIR_NAME, VAULT_FILE_NAME))
+}
+
+fn staged_vault_path() -> PathBuf {
+    PathBuf::from(format!("{}/{}", CHATS_DIR_NAME, STAGED_VAULT_FILE_NAME))
+}
+
+/// `None` when there is no header, or it is `null` because `rekey --decrypt` is finishing
+fn read_header() -> anyhow::Result<Option<VaultHeader>> {
+    match std::fs::read(vault_path()) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn is_encrypted() -> bool {
+    // an unreadable header still means encrypted, so nothing is written in the clear
+    !matches!(read_header(), Ok(None))
+}
+
+fn derive_key(secret: &[u8], header: &VaultHeader) -> anyhow::Result<Zeroizing<[u8; 32]>> {
+    let params = Params::new(header.m_cost, header.t_cost, header.p_cost, Some(32))
+        .map_err(|e| anyhow::anyhow!("Invalid key derivation parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(secret, &header.salt, key.as_mut())
+        .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+    let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// `None` when the tag does not verify, i.e. a wrong key or tampered bytes
+fn decrypt(key: &[u8; 32], sealed: &[u8]) -> Option<Vec<u8>> {
+    let body = sealed.strip_prefix(MAGIC)?;
+    if body.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .ok()
+}
+
+/// Derives the key from `secret` and keeps it for later reads and writes
+pub fn unlock(secret: &[u8]) -> anyhow::Result<()> {
+    let Some(header) = read_header()? else {
+        return Ok(());
+    };
+    let key = derive_key(secret, &header)?;
+    if decrypt(&key, &header.check).as_deref() != Some(CHECK_PLAINTEXT) {
+        return Err(VaultError::WrongKey.into());
+    }
+    *KEY.write().unwrap_or_else(|e| e.into_inner()) = Some(key);
+    Ok(())
+}
+
+/// Forgets the key, every read and write of sealed storage fails until `unlock`
+pub fn lock() {
+    *KEY.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Encrypts `plaintext` for storage when encryption is on, otherwise returns it as is
+pub fn seal(plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if !is_encrypted() {
+        return Ok(plaintext.to_vec());
+    }
+    match KEY.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(key) => encrypt(key, plaintext),
+        None => Err(VaultError::Locked.into()),
+    }
+}
+
+/// Reads a file written with `seal`, plain files are returned unchanged
+pub fn read(path: impl AsRef<Path>) -> anyhow::Result<Vec<u8>> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    if !bytes.starts_with(MAGIC) {
+        return Ok(bytes);
+    }
+    let key = KEY.read().unwrap_or_else(|e| e.into_inner());
+    let key = key.as_ref().ok_or(VaultError::Locked)?;
+    decrypt(key, &bytes).ok_or_else(|| {
+        VaultError::Damaged {
+            path: path.display().to_string(),
+        }
+        .into()
+    })
+}
+
+pub fn read_to_string(path: impl AsRef<Path>) -> anyhow::Result<String> {
+    Ok(String::from_utf8(read(path)?)?)
+}
+
+fn read_keyfile(path: &Path) -> anyhow::Result<Zeroizing<Vec<u8>>> {
+    let bytes = Zeroizing::new(
+        std::fs::read(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read keyfile {}: {}", path.display(), e))?,
+    );
+    if bytes.is_empty() {
+        return Err(anyhow::anyhow!("Keyfile {} is empty", path.display()));
+    }
+    Ok(bytes)
+}
+
+/// Unlocks encrypted storage with `MARVIN_KEYFILE`, or by asking for the passphrase or
+/// keyfile path. Returns `false` if the user gave up.
+pub fn unlock_interactive() -> anyhow::Result<bool> {
+    finish_rekey()?;
+    let Some(header) = read_header()? else {
+        return Ok(true);
+    };
+    if header.key_source == KeySource::Keyfile
+        && let Ok(path) = std::env::var("MARVIN_KEYFILE")
+    {
+        unlock(&read_keyfile(Path::new(&path))?)?;
+        return Ok(true);
+    }
+    prompt_unlock()
+}
+
+/// Asks for the passphrase or keyfile path, ignoring `MARVIN_KEYFILE` so `/lock` means it.
+/// Returns `false` if the user gave up.
+pub fn prompt_unlock() -> anyhow::Result<bool> {
+    let Some(header) = read_header()? else {
+        return Ok(true);
+    };
+    for _ in 0..UNLOCK_ATTEMPTS {
+        let secret = match header.key_source {
+            KeySource::Passphrase => Zeroizing::new(
+                rpassword::prompt_password("Chat storage passphrase (empty to quit): ")?
+                    .into_bytes(),
+            ),
+            KeySource::Keyfile => {
+                let path = crate::ui::prompt("Chat storage keyfile path (empty to quit):");
+                if path.is_empty() {
+                    return Ok(false);
+                }
+                match read_keyfile(Path::new(&path)) {
+                    Ok(secret) => secret,
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                }
+            }
+        };
+        if secret.is_empty() {
+            return Ok(false);
+        }
+        match unlock(&secret) {
+            Ok(()) => return Ok(true),
+            Err(e) => println!("{}", e),
+        }
+    }
+    Ok(false)
+}
+
+fn prompt_new_passphrase() -> anyhow::Result<Zeroizing<Vec<u8>>> {
+    loop {
+        let passphrase = Zeroizing::new(rpassword::prompt_password("New passphrase: ")?);
+        if passphrase.is_empty() {
+            return Err(anyhow::anyhow!("Rekey cancelled"));
+        }
+        let repeated = Zeroizing::new(rpassword::prompt_password("Repeat the passphrase: ")?);
+        if passphrase == repeated {
+            return Ok(Zeroizing::new(passphrase.as_bytes().to_vec()));
+        }
+        println!("Passphrases do not match");
+    }
+}
+
+/// The directories `rekey` rewrites files in
+fn stored_dirs() -> [PathBuf; 3] {
+    [
+        PathBuf::from(CHATS_DIR_NAME),
+        Path::new(CHATS_DIR_NAME).join(super::search::SEARCH_INDEX_DIR_NAME),
+        super::archive::archive_dir(),
+    ]
+}
+
+/// Every file in the chats directory that may hold sealed data, making the directories private
+fn stored_files() -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for dir in stored_dirs() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        // directories created before `write_atomic` restricted them may still be world-readable
+        #[cfg(unix)]
+        std::fs::set_permissions(&dir, std::os::unix::fs::PermissionsExt::from_mode(0o700))?;
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            if path.is_file()
+                && name != VAULT_FILE_NAME
+                && (name.ends_with(".json") || name.ends_with(".bak"))
+            {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn staged_path(path: &Path) -> PathBuf {
+    let mut staged = path.as_os_str().to_owned();
+    staged.push(STAGED_SUFFIX);
+    PathBuf::from(staged)
+}
+
+/// Files a `rekey` rewrote, paired with the files they replace
+fn staged_files() -> Vec<(PathBuf, PathBuf)> {
+    stored_dirs()
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .filter_map(|staged| {
+            let original = staged.to_str()?.strip_suffix(STAGED_SUFFIX)?.to_string();
+            Some((staged, PathBuf::from(original)))
+        })
+        .collect()
+}
+
+fn remove_if_present(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Drops what an uncommitted `rekey` staged, leaving the original files and header
+fn discard_rekey() -> anyhow::Result<()> {
+    for (staged, _) in staged_files() {
+        remove_if_present(&staged)?;
+    }
+    remove_if_present(&staged_vault_path())?;
+    Ok(())
+}
+
+/// Completes or undoes a `rekey` that stopped partway. The staged header is written before
+/// any staged file, and renaming it over `vault.json` is the commit point: before it the
+/// original files and header are untouched and the staged copies are dropped, after it the
+/// staged copies are complete for the new header and only need renaming, which needs no key.
+fn finish_rekey() -> anyhow::Result<()> {
+    if staged_vault_path().exists() {
+        return discard_rekey();
+    }
+    for (staged, original) in staged_files() {
+        std::fs::rename(&staged, &original)?;
+    }
+    if vault_path().exists() && read_header()?.is_none() {
+        std::fs::remove_file(vault_path())?;
+    }
+    Ok(())
+}
+
+/// Re-encrypts all stored chats, backups and search indexes under a new key, or decrypts
+/// them for `NewKey::None`. Everything is read before anything is rewritten, and the new
+/// contents are staged beside the originals until the new header is in place, so a rekey that
+/// fails or is interrupted leaves storage readable with either the old key or the new one.
+pub fn rekey(new_key: NewKey) -> anyhow::Result<usize> {
+    if !unlock_interactive()? {
+        return Err(VaultError::Locked.into());
+    }
+    let files = stored_files()?;
+    let contents = files
+        .iter()
+        .map(|path| read(path).map(Zeroizing::new))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let secret = match &new_key {
+        NewKey::Passphrase => Some((KeySource::Passphrase, prompt_new_passphrase()?)),
+        NewKey::Keyfile(path) => Some((KeySource::Keyfile, read_keyfile(path)?)),
+        NewKey::None => None,
+    };
+    let (header, key) = match secret {
+        Some((key_source, secret)) => {
+            let mut salt = vec![0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            let params = Params::default();
+            let mut header = VaultHeader {
+                version: 1,
+                key_source,
+                salt,
+                m_cost: params.m_cost(),
+                t_cost: params.t_cost(),
+                p_cost: params.p_cost(),
+                check: Vec::new(),
+            };
+            let key = derive_key(&secret, &header)?;
+            header.check = encrypt(&key, CHECK_PLAINTEXT)?;
+            (Some(header), Some(key))
+        }
+        None => (None, None),
+    };
+
+    // a `null` header stands for "not encrypted" until the staged files are in place
+    write_atomic(staged_vault_path(), &serde_json::to_vec_pretty(&header)?)?;
+    let staged = files
+        .iter()
+        .zip(&contents)
+        .try_for_each(|(path, plaintext)| {
+            let bytes = match &key {
+                Some(key) => encrypt(key, plaintext)?,
+                None => plaintext.to_vec(),
+            };
+            write_atomic(staged_path(path), &bytes)
+        });
+    if let Err(e) = staged {
+        discard_rekey()?;
+        return Err(e);
+    }
+    std::fs::rename(staged_vault_path(), vault_path())?;
+    *KEY.write().unwrap_or_else(|e| e.into_inner()) = key;
+    finish_rekey()?;
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decrypt_rejects_other_key() {
+        let sealed = encrypt(&[7; 32], b"fn main() {}").unwrap();
+        assert_eq!(
+            decrypt(&[7; 32], &sealed).as_deref(),
+            Some(&b"fn main() {}"[..])
+        );
+        assert_eq!(decrypt(&[8; 32], &sealed), None);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::sessions::SessionMeta;
 use std::io::{stdout, Write};
 
 const WIDTH: usize = 50;
@@ -6,9 +7,18 @@ pub fn horizontal_line() {
     println!("{}", HORIZONTAL_LINE_STR.repeat(WIDTH));
 }
 
-pub fn welcome_message(chat_id: u16) {
+/// `resumed` is the saved chat's metadata when continuing one, shown with its title and summary
+pub fn welcome_message(chat_id: u16, resumed: Option<&SessionMeta>) {
     println!("Hello, I am Marvin, your personal AI assistant!");
-    print!("Initiating new chat state (ID = {})", chat_id);
+    if let Some(meta) = resumed {
+        println!("Chat: {}", meta.display_title());
+        if let Some(summary) = &meta.summary {
+            println!("Summary: {}", summary);
+        }
+        print!("Resuming chat state (ID = {})", chat_id);
+    } else {
+        print!("Initiating new chat state (ID = {})", chat_id);
+    }
     for _ in 0..3 {
         std::thread::sleep(std::time::Duration::from_millis(500));
         print!(".");
@@ -17,3 +27,24 @@ pub fn welcome_message(chat_id: u16) {
     println!();
     horizontal_line();
 }
+
+/// Prints `question` and reads one trimmed line from stdin, empty on read errors
+pub fn prompt(question: &str) -> String {
+    print!("{} ", question);
+    let _ = stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return String::new();
+    }
+    answer.trim().to_string()
+}
+
+/// Asks a yes/no question on stdin, anything but `y`/`yes` counts as no
+pub fn confirm(question: &str) -> bool {
+    matches!(
+        prompt(&format!("{} [y/N]", question))
+            .to_lowercase()
+            .as_str(),
+        "y" | "yes"
+    )
+}
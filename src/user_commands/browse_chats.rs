@@ -0,0 +1,240 @@
+use crate::{
+    chat::Chat,
+    sessions::{
+        delete_session, list_sessions, load_session, update_session_meta, ChatRef, ChatsFilter,
+        SessionListing, SessionMeta,
+    },
+    ui::{confirm, horizontal_line},
+    user_commands::{
+        resume_chat::ResumeChat,
+        show_chat_history::{print_messages, HISTORY_LEN},
+    },
+};
+use chrono::{Local, NaiveDate};
+
+const SUMMARY_PREVIEW_LEN: usize = 160;
+
+#[derive(Debug, Clone)]
+pub enum ChatsCommand {
+    List(ChatsFilter),
+    Open(ChatRef),
+    Resume(ChatRef),
+    Rename(u16, String),
+    Tag(u16, String),
+    Untag(u16, String),
+    Delete(u16),
+    Usage,
+}
+
+impl From<&str> for ChatsCommand {
+    /// Parses everything after `/chats`, keeping the original casing for titles and tags
+    fn from(args: &str) -> Self {
+        let args = args.trim();
+        let (subcommand, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        let rest = rest.trim();
+        let id_and_value = || {
+            let (id, value) = rest.split_once(char::is_whitespace)?;
+            let value = value.trim();
+            (!value.is_empty()).then(|| (id.parse::<u16>().ok(), value.to_string()))
+        };
+        match subcommand.to_lowercase().as_str() {
+            "open" | "show" if !rest.is_empty() => Self::Open(ChatRef::from(rest)),
+            "resume" if !rest.is_empty() => Self::Resume(ChatRef::from(rest)),
+            "rename" => match id_and_value() {
+                Some((Some(id), title)) => Self::Rename(id, title),
+                _ => Self::Usage,
+            },
+            "tag" => match id_and_value() {
+                Some((Some(id), tag)) => Self::Tag(id, tag),
+                _ => Self::Usage,
+            },
+            "untag" => match id_and_value() {
+                Some((Some(id), tag)) => Self::Untag(id, tag),
+                _ => Self::Usage,
+            },
+            "delete" => match rest.parse::<u16>() {
+                Ok(id) => Self::Delete(id),
+                Err(_) => Self::Usage,
+            },
+            _ => {
+                let mut filter = ChatsFilter::default();
+                for arg in args.split_whitespace() {
+                    match arg.split_once(':') {
+                        Some(("tag", tag)) if !tag.is_empty() => filter.tag = Some(tag.to_string()),
+                        Some(("since", date)) => match date.parse::<NaiveDate>() {
+                            Ok(date) => filter.since = Some(date),
+                            Err(_) => return Self::Usage,
+                        },
+                        Some(("until", date)) => match date.parse::<NaiveDate>() {
+                            Ok(date) => filter.until = Some(date),
+                            Err(_) => return Self::Usage,
+                        },
+                        _ => return Self::Usage,
+                    }
+                }
+                Self::List(filter)
+            }
+        }
+    }
+}
+
+pub trait BrowseChats {
+    fn browse_chats(&mut self, command: ChatsCommand) -> anyhow::Result<()>;
+}
+
+impl BrowseChats for Chat {
+    fn browse_chats(&mut self, command: ChatsCommand) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        let listings = list_sessions()?;
+        match command {
+            ChatsCommand::List(filter) => {
+                let listings: Vec<_> = listings.iter().filter(|l| filter.matches(l)).collect();
+                if listings.is_empty() {
+                    println!(
+                        "No saved chats{}",
+                        if filter.is_empty() {
+                            ""
+                        } else {
+                            " match that filter"
+                        }
+                    );
+                    return Ok(());
+                }
+                for (i, listing) in listings.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    self.print_listing(listing);
+                }
+            }
+            ChatsCommand::Open(chat_ref) => {
+                let Some(listing) = chat_ref.resolve(&listings) else {
+                    println!("No saved chat matches {}", chat_ref);
+                    return Ok(());
+                };
+                self.print_listing(listing);
+                horizontal_line();
+                let saved = load_session(listing.id)?;
+                let messages: Vec<_> = saved
+                    .chat_history
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .take(HISTORY_LEN)
+                    .rev()
+                    .collect();
+                print_messages(&messages);
+            }
+            ChatsCommand::Resume(chat_ref) => self.resume_chat(Some(chat_ref))?,
+            ChatsCommand::Rename(id, title) => {
+                update_meta(&listings, id, |meta| meta.title = Some(title.clone()))?;
+            }
+            ChatsCommand::Tag(id, tag) => {
+                update_meta(&listings, id, |meta| {
+                    if !meta.has_tag(&tag) {
+                        meta.tags.push(tag.clone());
+                    }
+                })?;
+            }
+            ChatsCommand::Untag(id, tag) => {
+                update_meta(&listings, id, |meta| {
+                    meta.tags.retain(|t| !t.eq_ignore_ascii_case(&tag))
+                })?;
+            }
+            ChatsCommand::Delete(id) => {
+                let Some(listing) = listings.iter().find(|l| l.id == id) else {
+                    println!("No saved chat with ID: {}", id);
+                    return Ok(());
+                };
+                if id == self.id() {
+                    println!(
+                        "Chat (ID = {}) is the active chat and cannot be deleted",
+                        id
+                    );
+                    return Ok(());
+                }
+                self.print_listing(listing);
+                horizontal_line();
+                if confirm("Delete this chat permanently?") {
+                    delete_session(id)?;
+                    println!("Deleted chat (ID = {})", id);
+                } else {
+                    println!("Nothing deleted");
+                }
+            }
+            ChatsCommand::Usage => print_usage(),
+        }
+        Ok(())
+    }
+}
+
+impl Chat {
+    fn print_listing(&self, listing: &SessionListing) {
+        let model = listing
+            .meta
+            .model
+            .as_deref()
+            .map(|id| {
+                self.model_options()
+                    .iter()
+                    .find(|model| model.id == id)
+                    .map(|model| model.display_name.as_str())
+                    .unwrap_or(id)
+            })
+            .unwrap_or("unknown model");
+        println!(
+            "[{}] {} | {} | {} | {} messages",
+            listing.id,
+            listing.meta.display_title(),
+            listing
+                .meta
+                .updated_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            model,
+            listing.message_count
+        );
+        if !listing.meta.tags.is_empty() {
+            println!("    tags: {}", listing.meta.tags.join(", "));
+        }
+        if let Some(summary) = &listing.meta.summary {
+            let end = match summary.char_indices().nth(SUMMARY_PREVIEW_LEN) {
+                Some((i, _)) => i,
+                None => summary.len(),
+            };
+            let ellipsis = if end < summary.len() { "..." } else { "" };
+            println!("    {}{}", &summary[..end], ellipsis);
+        } else if let Some(preview) = &listing.preview {
+            println!("    \"{}\"", preview);
+        }
+    }
+}
+
+fn update_meta(
+    listings: &[SessionListing],
+    id: u16,
+    update: impl FnOnce(&mut SessionMeta),
+) -> anyhow::Result<()> {
+    if !listings.iter().any(|l| l.id == id) {
+        println!("No saved chat with ID: {}", id);
+        return Ok(());
+    }
+    let meta = update_session_meta(id, update)?;
+    println!("Updated chat (ID = {}): \"{}\"", id, meta.display_title());
+    if !meta.tags.is_empty() {
+        println!("    tags: {}", meta.tags.join(", "));
+    }
+    Ok(())
+}
+
+fn print_usage() {
+    println!("Usage:");
+    println!("  /chats [tag:<tag>] [since:<YYYY-MM-DD>] [until:<YYYY-MM-DD>]");
+    println!("  /chats open <id|title>    - preview a saved chat");
+    println!("  /chats resume <id|title>  - continue a saved chat");
+    println!("  /chats rename <id> <title>");
+    println!("  /chats tag <id> <tag>");
+    println!("  /chats untag <id> <tag>");
+    println!("  /chats delete <id>");
+}
@@ -10,6 +10,10 @@ impl ClearContext for Chat {
         println!("Chat history cleared");
         self.clear_chat_history();
         self.clear_input();
+        let pins = self.chat_history().pins().len();
+        if pins > 0 {
+            println!("{} pins kept, /pins lists them", pins);
+        }
         Ok(())
     }
 }
@@ -1,22 +1,80 @@
-use crate::{chat::Chat, ui::horizontal_line};
-use rig::message::Message;
+use crate::{
+    chat::Chat,
+    sessions::{archive::archive_session, update_session_meta, SavedSession},
+    ui::horizontal_line,
+    user_commands::show_token_usage::Formatted,
+};
+
+/// Exchanges kept word for word when no count is given
+const KEEP_EXCHANGES: usize = 2;
+const COMPACT_PROMPT: &str = "Provide a concise context summary of our conversation so far, including any earlier recap, that could be used to continue this chat. Include key decisions made, current task state, and any important details. Write it as a brief paragraph, not a list.";
 
 pub trait CompactContext {
-    fn compact_context(&mut self) -> impl Future<Output = anyhow::Result<()>>;
+    fn compact_context(
+        &mut self,
+        keep_exchanges: Option<usize>,
+    ) -> impl Future<Output = anyhow::Result<()>>;
 }
 
 impl CompactContext for Chat {
-    async fn compact_context(&mut self) -> anyhow::Result<()> {
+    /// Summarizes everything before the last `keep_exchanges` prompts into a recap sent with
+    /// the system prompt. The chat as it was is archived first.
+    async fn compact_context(&mut self, keep_exchanges: Option<usize>) -> anyhow::Result<()> {
         horizontal_line();
         self.clear_input();
-        let prompt = "Provide a concise context summary of our conversation that could be used to continue this chat. Include key decisions made, current task state, and any important details. Write it as a brief paragraph, not a list.";
-        let response = self.send(Message::user(prompt)).await?;
-        self.clear_chat_history();
-        self.add_to_chat_history(Message::assistant(response.clone()));
+        let keep_exchanges = keep_exchanges.unwrap_or(KEEP_EXCHANGES);
+        let prompts: Vec<usize> = self
+            .chat_history()
+            .prompts()
+            .into_iter()
+            .map(|(position, _)| position)
+            .collect();
+        let Some(position) = prompts
+            .len()
+            .checked_sub(keep_exchanges)
+            .filter(|i| *i > 0)
+            .map(|i| prompts.get(i).copied().unwrap_or(self.chat_history().len()))
+        else {
+            println!(
+                "Nothing to compact, the chat has {} exchanges and the last {} are kept",
+                prompts.len(),
+                keep_exchanges
+            );
+            return Ok(());
+        };
+        let before = self.context_usage().await;
+        let older = self.chat_history().iter().take(position).cloned().collect();
+        let Some(recap) = self.ask_aside(COMPACT_PROMPT, older).await? else {
+            println!("The chat was not compacted");
+            return Ok(());
+        };
+        let archive = archive_session(&SavedSession::new(
+            self.id(),
+            self.session_meta()?,
+            self.chat_history().clone(),
+        ))?;
+        self.compact_chat_history(position, recap.clone());
+        self.save_chat_history_to_file()?;
+        update_session_meta(self.id(), |meta| meta.archives.push(archive.clone()))?;
+        let after = self.context_usage().await;
+        println!(
+            "Compacted {} messages into a {} word recap, keeping the last {} exchanges",
+            position,
+            recap.split_whitespace().count(),
+            keep_exchanges.min(prompts.len())
+        );
         println!(
-            "Compaction completed in {} words",
-            response.split_whitespace().count()
+            "Saved {}{} tokens ({} before, {} after)",
+            if before.estimated || after.estimated {
+                "~"
+            } else {
+                ""
+            },
+            before.used.saturating_sub(after.used).formatted(),
+            before.used.formatted(),
+            after.used.formatted()
         );
+        println!("The original chat is archived at {}", archive.display());
         Ok(())
     }
 }
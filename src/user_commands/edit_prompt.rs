@@ -0,0 +1,65 @@
+use crate::{
+    chat::Chat,
+    ui::{horizontal_line, prompt},
+};
+use rig::message::Message;
+
+const PREVIEW_LEN: usize = 60;
+
+pub trait EditPrompt {
+    fn edit_prompt(&mut self, n: Option<usize>) -> impl Future<Output = anyhow::Result<()>>;
+}
+
+impl EditPrompt for Chat {
+    /// Rewrites the `n`th prompt of the active branch and continues on a new branch from there
+    async fn edit_prompt(&mut self, n: Option<usize>) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        let prompts: Vec<(usize, String)> = self
+            .chat_history()
+            .prompts()
+            .into_iter()
+            .map(|(position, text)| (position, text.to_string()))
+            .collect();
+        if prompts.is_empty() {
+            println!("No prompts to edit");
+            return Ok(());
+        }
+        let n = match n {
+            Some(n) => n,
+            None => {
+                for (i, (_, text)) in prompts.iter().enumerate() {
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    let preview: String = text.chars().take(PREVIEW_LEN).collect();
+                    let end = if text.chars().count() > PREVIEW_LEN {
+                        "..."
+                    } else {
+                        ""
+                    };
+                    println!("{}. \"{}{}\"", i + 1, preview, end);
+                }
+                horizontal_line();
+                match prompt("Prompt to edit (number), or nothing to cancel:").parse() {
+                    Ok(n) => n,
+                    Err(_) => return Ok(()),
+                }
+            }
+        };
+        let Some((position, text)) = n.checked_sub(1).and_then(|i| prompts.get(i)) else {
+            println!("No prompt {}, there are {}", n, prompts.len());
+            return Ok(());
+        };
+        println!("Editing: \"{}\"", text);
+        let edited = prompt("New message (nothing to cancel):");
+        if edited.is_empty() {
+            println!("Edit cancelled");
+            return Ok(());
+        }
+        if !self.within_budget() {
+            return Ok(());
+        }
+        self.truncate_chat_history(*position);
+        self.stream(Message::user(edited)).await;
+        Ok(())
+    }
+}
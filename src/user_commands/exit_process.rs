@@ -1,4 +1,8 @@
 use crate::{chat::Chat, ui::horizontal_line, user_commands::save_chat_history::SaveChatHistory};
+use std::time::Duration;
+
+/// How long `/exit` waits for a background title to land before leaving it for next time
+const TITLE_WAIT: Duration = Duration::from_secs(5);
 
 pub trait ExitProcess {
     fn exit_process(&mut self) -> anyhow::Result<()>;
@@ -6,7 +10,9 @@ pub trait ExitProcess {
 
 impl ExitProcess for Chat {
     fn exit_process(&mut self) -> anyhow::Result<()> {
-        self.save_chat_history()?;
+        self.await_title(TITLE_WAIT);
+        self.save_chat_history(None)?;
+        self.close_session()?;
         horizontal_line();
         println!("Farewell!");
         Ok(())
@@ -0,0 +1,40 @@
+use crate::{
+    chat::Chat, sessions::export::ExportFormat, ui::horizontal_line,
+    user_commands::search_chats::export_hit_or_report,
+};
+use std::path::PathBuf;
+
+pub trait ExportChat {
+    fn export_chat(
+        &mut self,
+        format: Option<ExportFormat>,
+        path: Option<PathBuf>,
+        plaintext: bool,
+    ) -> anyhow::Result<()>;
+}
+
+impl ExportChat for Chat {
+    fn export_chat(
+        &mut self,
+        format: Option<ExportFormat>,
+        path: Option<PathBuf>,
+        plaintext: bool,
+    ) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        let Some(format) = format else {
+            println!("Usage: /export <md|html|txt> [path] [--plaintext]");
+            return Ok(());
+        };
+        if self.chat_history().is_empty() {
+            println!("Nothing to export");
+            return Ok(());
+        }
+        if let Err(e) = self.save_chat_history_to_file() {
+            println!("Failed to save the chat before exporting it: {:#}", e);
+            return Ok(());
+        }
+        export_hit_or_report(self.id(), format, path, plaintext);
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    chat::Chat,
+    sessions::{load_session, next_session_id, write_session, SavedSession},
+    ui::horizontal_line,
+};
+
+pub trait ForkChat {
+    fn fork_chat(&mut self, title: Option<String>) -> anyhow::Result<()>;
+}
+
+impl ForkChat for Chat {
+    /// Copies this chat with all its branches into a new one and continues in the copy
+    fn fork_chat(&mut self, title: Option<String>) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        if self.chat_history().is_empty() {
+            println!("Nothing to fork");
+            return Ok(());
+        }
+        self.save_chat_history_to_file()?;
+        let original_id = self.id();
+        let mut meta = self.session_meta()?;
+        meta.title = Some(title.unwrap_or_else(|| format!("{} (fork)", meta.display_title())));
+        meta.created_at = meta.updated_at;
+        // archives stay with the chat they were taken from
+        meta.archives.clear();
+        let id = next_session_id(Some(original_id))?;
+        write_session(&SavedSession::new(id, meta, self.chat_history().clone()))?;
+        self.resume_session(load_session(id)?)?;
+        self.autosave();
+        println!(
+            "Forked chat (ID = {}) into chat (ID = {}), continuing in the fork",
+            original_id, id
+        );
+        println!("Use /resume {} to go back to the original", original_id);
+        Ok(())
+    }
+}
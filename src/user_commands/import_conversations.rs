@@ -0,0 +1,167 @@
+use crate::{
+    chat::Chat,
+    sessions::{
+        import::{read_conversations, ImportSource, ImportedConversation},
+        next_session_id, write_session, SavedSession, SessionMeta,
+    },
+    ui::{horizontal_line, prompt},
+    user_commands::show_chat_history::{print_messages, HISTORY_LEN},
+};
+use chrono::Local;
+use std::path::PathBuf;
+
+const PAGE_LEN: usize = 15;
+
+pub trait ImportConversations {
+    fn import_conversations(&mut self, path: PathBuf) -> anyhow::Result<()>;
+}
+
+impl ImportConversations for Chat {
+    fn import_conversations(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        let (source, mut conversations) = match read_conversations(&path) {
+            Ok(read) => read,
+            Err(e) => {
+                println!("Failed to import {}: {:#}", path.display(), e);
+                return Ok(());
+            }
+        };
+        conversations.retain(|conversation| !conversation.is_empty());
+        conversations.sort_by_key(|conversation| std::cmp::Reverse(conversation.created_at));
+        println!(
+            "Found {} {} conversations in {}",
+            conversations.len(),
+            source.tag(),
+            path.display()
+        );
+        let mut filter = String::new();
+        loop {
+            horizontal_line();
+            let matching: Vec<_> = conversations
+                .iter()
+                .filter(|conversation| {
+                    filter.is_empty()
+                        || conversation
+                            .title
+                            .as_deref()
+                            .is_some_and(|title| title.to_lowercase().contains(&filter))
+                })
+                .collect();
+            if matching.is_empty() {
+                println!("No conversations match \"{}\"", filter);
+            }
+            for (i, conversation) in matching.iter().take(PAGE_LEN).enumerate() {
+                print_conversation(i + 1, conversation);
+            }
+            if matching.len() > PAGE_LEN {
+                println!(
+                    "...and {} more, type part of a title to narrow down",
+                    matching.len() - PAGE_LEN
+                );
+            }
+            horizontal_line();
+            let answer =
+                prompt("Enter a number to preview, text to filter by title, or nothing to cancel:");
+            if answer.is_empty() {
+                println!("Import cancelled");
+                return Ok(());
+            }
+            let Some(conversation) = answer
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .filter(|i| *i < PAGE_LEN)
+                .and_then(|i| matching.get(i))
+            else {
+                filter = answer.to_lowercase();
+                continue;
+            };
+            horizontal_line();
+            let chat_history = conversation.to_chat_history();
+            let messages: Vec<_> = chat_history
+                .iter()
+                .enumerate()
+                .rev()
+                .take(HISTORY_LEN)
+                .rev()
+                .collect();
+            print_messages(&messages);
+            horizontal_line();
+            match prompt(
+                "Load into (c)urrent context, save as a (n)ew chat, or nothing to go back:",
+            )
+            .to_lowercase()
+            .as_str()
+            {
+                "c" | "current" => {
+                    let count = chat_history.len();
+                    self.extend_chat_history(chat_history);
+                    self.autosave();
+                    println!("Loaded {} messages into the current chat", count);
+                    return Ok(());
+                }
+                "n" | "new" => {
+                    let id = match save_as_new_chat(source, conversation, self.id()) {
+                        Ok(id) => id,
+                        Err(e) => {
+                            println!("Failed to save the imported chat: {:#}", e);
+                            return Ok(());
+                        }
+                    };
+                    println!(
+                        "Saved as chat (ID = {}), use /resume {} to continue it",
+                        id, id
+                    );
+                    return Ok(());
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+fn print_conversation(n: usize, conversation: &ImportedConversation) {
+    let date = conversation
+        .created_at
+        .map(|created_at| {
+            created_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .unwrap_or_else(|| "unknown date".to_string());
+    println!(
+        "[{}] {} | {} | {} messages",
+        n,
+        conversation.title.as_deref().unwrap_or("(untitled)"),
+        date,
+        conversation.len()
+    );
+    if let Some(prompt) = conversation.first_prompt() {
+        let prompt = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+        let preview: String = prompt.chars().take(60).collect();
+        let end = if prompt.chars().count() > 60 {
+            "..."
+        } else {
+            ""
+        };
+        println!("    \"{}{}\"", preview, end);
+    }
+}
+
+fn save_as_new_chat(
+    source: ImportSource,
+    conversation: &ImportedConversation,
+    active_id: u16,
+) -> anyhow::Result<u16> {
+    let id = next_session_id(Some(active_id))?;
+    let mut meta = SessionMeta::new();
+    meta.title = conversation.title.clone();
+    meta.tags = vec!["imported".to_string(), source.tag().to_string()];
+    if let Some(created_at) = conversation.created_at {
+        meta.created_at = created_at;
+    }
+    write_session(&SavedSession::new(id, meta, conversation.to_chat_history()))?;
+    Ok(id)
+}
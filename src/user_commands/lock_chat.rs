@@ -0,0 +1,41 @@
+use crate::{
+    chat::Chat,
+    sessions::{load_session, vault},
+    ui::horizontal_line,
+};
+
+pub trait LockChat {
+    fn lock_chat(&mut self) -> anyhow::Result<()>;
+}
+
+impl LockChat for Chat {
+    /// Saves the chat, forgets the storage key and the conversation held in memory, then
+    /// waits for the passphrase or keyfile before picking the chat back up
+    fn lock_chat(&mut self) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        if !vault::is_encrypted() {
+            println!("Chat storage is not encrypted, run `marvin rekey` to protect it with a passphrase or keyfile");
+            return Ok(());
+        }
+        let saved = !self.chat_history().is_empty() || !self.chat_history().pins().is_empty();
+        if saved {
+            self.save_chat_history_to_file()?;
+        }
+        self.forget_chat_history();
+        vault::lock();
+        println!("Chat storage locked");
+        horizontal_line();
+        if !vault::prompt_unlock()? {
+            self.close_session()?;
+            println!("Farewell!");
+            std::process::exit(0);
+        }
+        if saved {
+            self.resume_session(load_session(self.id())?)?;
+        }
+        horizontal_line();
+        println!("Chat storage unlocked");
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{chat::Chat, user_commands::compact_context::CompactContext};
+
+/// Prompts whose tool results survive when old ones are dropped to save context
+const KEEP_TOOL_RESULTS_FOR: usize = 2;
+/// Exchanges kept word for word by automatic compaction, fewer when the chat is shorter
+const KEEP_EXCHANGES: usize = 2;
+
+pub trait ManageContext {
+    fn manage_context(&mut self) -> impl Future<Output = anyhow::Result<()>>;
+}
+
+impl ManageContext for Chat {
+    /// Shows the context meter after a turn. Past `MARVIN_COMPACT_AT` of the window, old tool
+    /// results are dropped first, then the chat is compacted, then the remaining tool results
+    /// are dropped. If that is still not enough the user is told to start over.
+    async fn manage_context(&mut self) -> anyhow::Result<()> {
+        // `stream` saved the turn, so this only saves again when it changes the history
+        let usage = self.context_usage().await;
+        println!("{}", usage.meter());
+        let compact_at = self.config().compact_at();
+        if usage.fraction() < compact_at {
+            return Ok(());
+        }
+        let elided = self.elide_tool_results(KEEP_TOOL_RESULTS_FOR);
+        if elided > 0 {
+            self.autosave();
+            let usage = self.context_usage().await;
+            println!(
+                "Dropped {} old tool results to save context, now {:.0}% full",
+                elided,
+                usage.fraction() * 100.0
+            );
+            if usage.fraction() < compact_at {
+                return Ok(());
+            }
+        }
+        let exchanges = self.chat_history().prompts().len();
+        if exchanges > 1 {
+            println!(
+                "Context is over {:.0}% full, compacting automatically",
+                compact_at * 100.0
+            );
+            // keep at least one exchange out of the recap so there is something to compact
+            self.compact_context(Some(KEEP_EXCHANGES.min(exchanges - 1)))
+                .await?;
+            let usage = self.context_usage().await;
+            println!("{}", usage.meter());
+            if usage.fraction() < compact_at {
+                return Ok(());
+            }
+        }
+        let elided = self.elide_tool_results(0);
+        if elided > 0 {
+            self.autosave();
+            let usage = self.context_usage().await;
+            println!(
+                "Dropped the {} remaining tool results, now {:.0}% full",
+                elided,
+                usage.fraction() * 100.0
+            );
+            if usage.fraction() < compact_at {
+                return Ok(());
+            }
+        }
+        println!(
+            "Context is still over {:.0}% full and there is too little history left to compact. Start a new chat with /clear, or the next request may not fit the context window.",
+            compact_at * 100.0
+        );
+        Ok(())
+    }
+}
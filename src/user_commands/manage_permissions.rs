@@ -0,0 +1,108 @@
+use crate::{agent_tools::permissions::rules::Rule, chat::Chat, ui::horizontal_line};
+
+#[derive(Debug, Clone)]
+pub enum PermissionsCommand {
+    List,
+    /// A rule as typed, parsed when the command runs so errors can be shown
+    Add(String),
+    /// Position as numbered by the list, counting from 1
+    Remove(usize),
+    /// Forget what was allowed for the session
+    Forget,
+    Reset,
+    Usage,
+}
+
+impl From<&str> for PermissionsCommand {
+    /// Parses everything after `/permissions`
+    fn from(args: &str) -> Self {
+        let args = args.trim();
+        let (subcommand, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        match (subcommand.to_lowercase().as_str(), rest.trim()) {
+            ("" | "list", "") => Self::List,
+            ("add", rule) if !rule.is_empty() => Self::Add(rule.to_string()),
+            ("remove" | "rm", n) => match n.parse() {
+                Ok(n) => Self::Remove(n),
+                Err(_) => Self::Usage,
+            },
+            ("forget", "") => Self::Forget,
+            ("reset", "") => Self::Reset,
+            _ => Self::Usage,
+        }
+    }
+}
+
+pub trait ManagePermissions {
+    fn manage_permissions(&mut self, command: PermissionsCommand);
+}
+
+impl ManagePermissions for Chat {
+    fn manage_permissions(&mut self, command: PermissionsCommand) {
+        self.clear_input();
+        horizontal_line();
+        let permissions = self.permissions();
+        let result = match command {
+            PermissionsCommand::List => {
+                let rules = permissions.rules();
+                if rules.is_empty() {
+                    println!("No rules, every tool call is allowed");
+                }
+                for (i, rule) in rules.iter().enumerate() {
+                    println!("{}. {}", i + 1, rule);
+                }
+                let remembered = permissions.remembered();
+                if !remembered.is_empty() {
+                    println!("Allowed for this session:");
+                    for (tool, rule) in remembered {
+                        println!("  {} calls matching '{}'", tool, rule);
+                    }
+                }
+                horizontal_line();
+                println!("The last matching rule decides, calls no rule matches are allowed.");
+                println!(
+                    "Rules: allow|ask|deny <tool or glob> [domain=example.com] [path=src/**] [field=glob] [field>n] [field<n]"
+                );
+                Ok(())
+            }
+            PermissionsCommand::Add(text) => match text.parse::<Rule>() {
+                Ok(rule) => permissions
+                    .add(rule)
+                    .map(|_| println!("Added rule {}", permissions.rules().len())),
+                Err(e) => {
+                    println!("{}", e);
+                    Ok(())
+                }
+            },
+            PermissionsCommand::Remove(n) => {
+                match n.checked_sub(1).map(|i| permissions.remove(i)) {
+                    Some(Ok(Some(rule))) => {
+                        println!("Removed '{}'", rule);
+                        Ok(())
+                    }
+                    Some(Err(e)) => Err(e),
+                    _ => {
+                        println!("No rule {}, there are {}", n, permissions.rules().len());
+                        Ok(())
+                    }
+                }
+            }
+            PermissionsCommand::Forget => {
+                permissions.start_session();
+                println!("Tools allowed for this session will ask again");
+                Ok(())
+            }
+            PermissionsCommand::Reset => permissions
+                .reset()
+                .map(|_| println!("Restored the default rules")),
+            PermissionsCommand::Usage => {
+                println!(
+                    "Usage: /permissions | /permissions add <rule> | /permissions remove <n> | /permissions forget | /permissions reset"
+                );
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to save the permissions: {:#}", e);
+        }
+    }
+}
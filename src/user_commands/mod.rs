@@ -1,21 +1,55 @@
+pub mod browse_chats;
 pub mod clear_context;
 pub mod compact_context;
+pub mod edit_prompt;
 pub mod exit_process;
+pub mod export_chat;
+pub mod fork_chat;
 pub mod import_chat_history;
+pub mod import_conversations;
+pub mod lock_chat;
+pub mod manage_context;
+pub mod manage_permissions;
+pub mod pin_message;
+pub mod resume_chat;
+pub mod retry_turn;
 pub mod save_chat_history;
+pub mod search_chats;
 pub mod show_chat_history;
 pub mod show_context_summary;
+pub mod show_cost;
+pub mod show_edits;
 pub mod show_help_message;
+pub mod show_pins;
 pub mod show_token_usage;
+pub mod switch_branch;
 pub mod switch_model;
+pub mod undo_turn;
 
+pub use browse_chats::BrowseChats;
 pub use clear_context::ClearContext;
 pub use compact_context::CompactContext;
+pub use edit_prompt::EditPrompt;
 pub use exit_process::ExitProcess;
+pub use export_chat::ExportChat;
+pub use fork_chat::ForkChat;
 pub use import_chat_history::ImportChatHistory;
+pub use import_conversations::ImportConversations;
+pub use lock_chat::LockChat;
+pub use manage_context::ManageContext;
+pub use manage_permissions::ManagePermissions;
+pub use pin_message::PinMessage;
+pub use resume_chat::ResumeChat;
+pub use retry_turn::RetryTurn;
 pub use save_chat_history::SaveChatHistory;
+pub use search_chats::SearchChats;
 pub use show_chat_history::ShowChatHistory;
 pub use show_context_summary::ShowContextSummary;
+pub use show_cost::ShowCost;
+pub use show_edits::ShowEdits;
 pub use show_help_message::ShowHelpMessage;
+pub use show_pins::ShowPins;
 pub use show_token_usage::ShowTokenUsage;
+pub use switch_branch::SwitchBranch;
 pub use switch_model::SwitchModel;
+pub use undo_turn::UndoTurn;
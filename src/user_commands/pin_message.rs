@@ -0,0 +1,73 @@
+use crate::{
+    chat::{context::countable_text, history::PinSource, Chat},
+    ui::horizontal_line,
+};
+use rig::message::Message;
+
+#[derive(Debug, Clone)]
+pub enum PinTarget {
+    /// Position of a message as numbered by `/history`, counting from 1
+    Message(usize),
+    Note(String),
+    Usage,
+}
+
+impl From<&str> for PinTarget {
+    /// Parses everything after `/pin`, a number picks a message and anything else is a note
+    fn from(args: &str) -> Self {
+        let args = args.trim();
+        if args.is_empty() {
+            Self::Usage
+        } else if let Ok(n) = args.parse() {
+            Self::Message(n)
+        } else {
+            Self::Note(args.to_string())
+        }
+    }
+}
+
+pub trait PinMessage {
+    fn pin_message(&mut self, target: PinTarget);
+}
+
+impl PinMessage for Chat {
+    fn pin_message(&mut self, target: PinTarget) {
+        self.clear_input();
+        horizontal_line();
+        match target {
+            PinTarget::Message(n) => {
+                let Some(message) = n
+                    .checked_sub(1)
+                    .and_then(|i| self.chat_history().iter().nth(i))
+                else {
+                    println!("No message {}, there are {}", n, self.chat_history().len());
+                    return;
+                };
+                let source = match message {
+                    Message::User { .. } => PinSource::User,
+                    Message::Assistant { .. } => PinSource::Assistant,
+                };
+                let text = countable_text(message);
+                if text.trim().is_empty() {
+                    println!("Message {} has no text to pin", n);
+                    return;
+                }
+                self.pin(source, text);
+                println!("Pinned message {}", n);
+            }
+            PinTarget::Note(note) => {
+                self.pin(PinSource::Note, note);
+                println!("Pinned note");
+            }
+            PinTarget::Usage => {
+                println!("Usage: /pin <message number from /history> | /pin <note>");
+                return;
+            }
+        }
+        self.autosave();
+        println!(
+            "{} pins are sent with every request, /pins lists them",
+            self.chat_history().pins().len()
+        );
+    }
+}
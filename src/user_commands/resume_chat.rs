@@ -0,0 +1,69 @@
+use crate::{
+    chat::{settings::AgentSettings, Chat},
+    sessions::{list_sessions, load_session, most_recent_session, ChatRef},
+    ui::horizontal_line,
+};
+
+pub trait ResumeChat {
+    fn resume_chat(&mut self, chat_ref: Option<ChatRef>) -> anyhow::Result<()>;
+}
+
+impl ResumeChat for Chat {
+    /// Without a reference, resumes the most recently updated chat other than this one
+    fn resume_chat(&mut self, chat_ref: Option<ChatRef>) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        if let Err(e) = resume(self, chat_ref) {
+            println!("Failed to resume the chat: {:#}", e);
+        }
+        Ok(())
+    }
+}
+
+/// Switches `chat` to the saved chat `chat_ref` refers to, failing on storage errors
+fn resume(chat: &mut Chat, chat_ref: Option<ChatRef>) -> anyhow::Result<()> {
+    let id = match chat_ref {
+        Some(chat_ref) => {
+            let listings = list_sessions()?;
+            let Some(listing) = chat_ref.resolve(&listings) else {
+                println!("No saved chat matches {}", chat_ref);
+                return Ok(());
+            };
+            listing.id
+        }
+        None => match most_recent_session(Some(chat.id()))? {
+            Some(id) => id,
+            None => {
+                println!("No saved chat to resume");
+                return Ok(());
+            }
+        },
+    };
+    if id != chat.id() && !chat.chat_history().is_empty() {
+        chat.save_chat_history_to_file()?;
+        println!("Saved current chat (ID = {})", chat.id());
+    }
+    let saved = load_session(id)?;
+    let title = saved.meta.display_title().to_string();
+    let summary = saved.meta.summary.clone();
+    let configured_preamble_hash = AgentSettings::from_config(chat.config())?.preamble_hash();
+    if saved
+        .preamble_hash
+        .as_ref()
+        .is_some_and(|hash| *hash != configured_preamble_hash)
+    {
+        println!("This chat was saved with a different persona, restoring it");
+    }
+    chat.resume_session(saved)?;
+    println!(
+        "Resumed chat (ID = {}) \"{}\" with {} messages",
+        id,
+        title,
+        chat.chat_history().len()
+    );
+    if let Some(summary) = summary {
+        println!("Summary: {}", summary);
+    }
+    println!("Model: {}", chat.model());
+    Ok(())
+}
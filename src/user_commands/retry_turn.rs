@@ -0,0 +1,74 @@
+use crate::{chat::Chat, ui::horizontal_line};
+
+pub trait RetryTurn {
+    fn retry_turn(&mut self, model: Option<String>) -> impl Future<Output = anyhow::Result<()>>;
+}
+
+impl RetryTurn for Chat {
+    /// Regenerates the answer to the last prompt on a new branch, optionally once with
+    /// another model (matched by list number, name or ID)
+    async fn retry_turn(&mut self, model: Option<String>) -> anyhow::Result<()> {
+        self.clear_input();
+        let Some(position) = self
+            .chat_history()
+            .prompts()
+            .last()
+            .map(|(position, _)| *position)
+        else {
+            horizontal_line();
+            println!("Nothing to retry");
+            return Ok(());
+        };
+        let retry_model = match model {
+            Some(model) => {
+                let query = model.to_lowercase();
+                let found = self
+                    .model_options()
+                    .iter()
+                    .enumerate()
+                    .find(|(i, option)| {
+                        (i + 1).to_string() == query
+                            || option.id.to_lowercase() == query
+                            || option.display_name.to_lowercase().contains(&query)
+                    })
+                    .map(|(_, option)| option.clone());
+                match found {
+                    Some(found) => Some(found),
+                    None => {
+                        horizontal_line();
+                        println!("No model matches \"{}\", available models:", model);
+                        for (i, option) in self.model_options().iter().enumerate() {
+                            println!("{}. {}", i + 1, option.display_name);
+                        }
+                        return Ok(());
+                    }
+                }
+            }
+            None => None,
+        };
+        let Some(message) = self.chat_history().iter().nth(position).cloned() else {
+            return Ok(());
+        };
+        if !self.within_budget() {
+            return Ok(());
+        }
+        self.truncate_chat_history(position);
+        let current_model = self
+            .model_options()
+            .iter()
+            .find(|option| option.id == self.model_id())
+            .cloned();
+        if let Some(retry_model) = retry_model {
+            self.set_agent(retry_model)?;
+            horizontal_line();
+            println!("Retrying with {}", self.model());
+            self.stream(message).await;
+            if let Some(current_model) = current_model {
+                self.set_agent(current_model)?;
+            }
+        } else {
+            self.stream(message).await;
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,23 @@
 use crate::{
     chat::{Chat, CHATS_DIR_NAME},
+    sessions::update_session_meta,
     ui::horizontal_line,
 };
 
 pub trait SaveChatHistory {
-    fn save_chat_history(&mut self) -> anyhow::Result<()>;
+    fn save_chat_history(&mut self, title: Option<String>) -> anyhow::Result<()>;
 }
 
 impl SaveChatHistory for Chat {
-    fn save_chat_history(&mut self) -> anyhow::Result<()> {
+    /// Chats are autosaved after every turn, so this mostly labels the chat
+    fn save_chat_history(&mut self, title: Option<String>) -> anyhow::Result<()> {
         self.clear_input();
         self.save_chat_history_to_file()?;
         horizontal_line();
+        if let Some(title) = title {
+            update_session_meta(self.id(), |meta| meta.title = Some(title.clone()))?;
+            println!("Titled chat (ID = {}): \"{}\"", self.id(), title);
+        }
         println!(
             "Saved chat (ID = {}) history to the {}/ directory",
             self.id(),
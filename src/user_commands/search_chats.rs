@@ -0,0 +1,162 @@
+use crate::{
+    chat::Chat,
+    sessions::{
+        export::{export_session, ExportFormat},
+        load_session,
+        search::{search_sessions, SearchHit},
+        vault, ChatRef,
+    },
+    ui::{horizontal_line, prompt},
+    user_commands::resume_chat::ResumeChat,
+};
+use chrono::Local;
+use std::{io::IsTerminal, path::PathBuf};
+
+const RESULTS_LEN: usize = 10;
+
+/// What to do with a picked search result
+pub enum SearchAction {
+    Resume(u16),
+    /// The chat, the format and whether to export while storage is encrypted
+    Export(u16, ExportFormat, bool),
+}
+
+pub trait SearchChats {
+    fn search_chats(&mut self, query: String) -> anyhow::Result<()>;
+}
+
+impl SearchChats for Chat {
+    fn search_chats(&mut self, query: String) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        if query.is_empty() {
+            println!("Usage: /search <query>");
+            return Ok(());
+        }
+        let hits = match search_sessions(&query, RESULTS_LEN) {
+            Ok(hits) => hits,
+            Err(e) => {
+                println!("Search failed: {:#}", e);
+                return Ok(());
+            }
+        };
+        print_hits(&query, &hits);
+        match pick_hit(&hits) {
+            Some(SearchAction::Resume(id)) => self.resume_chat(Some(ChatRef::Id(id)))?,
+            Some(SearchAction::Export(id, format, plaintext)) => {
+                export_hit_or_report(id, format, None, plaintext)
+            }
+            None => (),
+        }
+        Ok(())
+    }
+}
+
+/// Matched words in bold on a terminal, between `**` otherwise
+fn highlight(hit: &SearchHit) -> String {
+    let (open, close) = if std::io::stdout().is_terminal() {
+        ("\x1b[1m", "\x1b[0m")
+    } else {
+        ("**", "**")
+    };
+    let mut highlighted = String::new();
+    let mut last = 0;
+    for range in &hit.highlights {
+        highlighted.push_str(&hit.snippet[last..range.start]);
+        highlighted.push_str(open);
+        highlighted.push_str(&hit.snippet[range.clone()]);
+        highlighted.push_str(close);
+        last = range.end;
+    }
+    highlighted.push_str(&hit.snippet[last..]);
+    highlighted
+}
+
+pub fn print_hits(query: &str, hits: &[SearchHit]) {
+    if hits.is_empty() {
+        println!("No saved chats match \"{}\"", query);
+        return;
+    }
+    for (i, hit) in hits.iter().enumerate() {
+        println!(
+            "[{}] ID: {} | \"{}\" | {} | score {:.1}",
+            i + 1,
+            hit.id,
+            hit.title,
+            hit.updated_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            hit.score
+        );
+        println!("    {}: {}", hit.role, highlight(hit));
+    }
+}
+
+/// Asks which result to resume or export, `None` when the user backs out
+pub fn pick_hit(hits: &[SearchHit]) -> Option<SearchAction> {
+    if hits.is_empty() {
+        return None;
+    }
+    horizontal_line();
+    let answer = prompt(
+        "Enter a number to resume, e<number> [md|html|txt] [--plaintext] to export, or nothing to go back:",
+    );
+    let mut args = answer.split_whitespace();
+    let selection = args.next()?.to_lowercase();
+    let (export, n) = match selection.strip_prefix('e') {
+        Some(n) => (true, n),
+        None => (false, selection.as_str()),
+    };
+    let Some(hit) = n
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| hits.get(i))
+    else {
+        println!("No result {}", n);
+        return None;
+    };
+    if !export {
+        return Some(SearchAction::Resume(hit.id));
+    }
+    let (flags, rest): (Vec<_>, Vec<_>) = args.partition(|arg| arg.starts_with("--"));
+    let plaintext = flags.contains(&"--plaintext");
+    match rest
+        .first()
+        .map(|format| format.parse())
+        .unwrap_or(Ok(ExportFormat::Markdown))
+    {
+        Ok(format) => Some(SearchAction::Export(hit.id, format, plaintext)),
+        Err(e) => {
+            println!("{}", e);
+            None
+        }
+    }
+}
+
+pub fn export_hit(
+    id: u16,
+    format: ExportFormat,
+    path: Option<PathBuf>,
+    plaintext: bool,
+) -> anyhow::Result<()> {
+    let path = match export_session(&load_session(id)?, format, path, plaintext) {
+        Err(e) if matches!(e.downcast_ref(), Some(vault::VaultError::PlaintextExport)) => {
+            println!("Not exported, {}", e);
+            return Ok(());
+        }
+        path => path?,
+    };
+    println!("Exported chat (ID = {}) to {}", id, path.display());
+    if vault::is_encrypted() {
+        println!("Note: exported transcripts are not encrypted");
+    }
+    Ok(())
+}
+
+/// `export_hit` from within a chat, where a bad path is only a typo and must not end it
+pub fn export_hit_or_report(id: u16, format: ExportFormat, path: Option<PathBuf>, plaintext: bool) {
+    if let Err(e) = export_hit(id, format, path, plaintext) {
+        println!("Failed to export chat (ID = {}): {:#}", id, e);
+    }
+}
@@ -3,6 +3,7 @@ use rig::message::{AssistantContent, Message, UserContent};
 use crate::{chat::Chat, ui::horizontal_line};
 
 pub const HISTORY_LEN: usize = 10;
+const TRUNCATE_AT: usize = 300;
 
 pub trait ShowChatHistory {
     fn show_chat_history(&mut self);
@@ -10,7 +11,6 @@ pub trait ShowChatHistory {
 
 impl ShowChatHistory for Chat {
     fn show_chat_history(&mut self) {
-        const TRUNCATE_AT: usize = 300;
         if self.chat_history().is_empty() {
             self.clear_input();
             horizontal_line();
@@ -24,52 +24,59 @@ impl ShowChatHistory for Chat {
         let messages: Vec<_> = self
             .chat_history()
             .iter()
+            .enumerate()
             .rev()
             .take(HISTORY_LEN)
             .rev()
             .collect();
-        for (i, message) in messages.iter().enumerate() {
-            println!();
-            match message {
-                Message::User { content } => match content.first() {
-                    UserContent::Text(text) => {
-                        let text = text.text();
-                        let truncated = text.chars().take(TRUNCATE_AT).collect::<String>();
-                        let end = if text.len() > TRUNCATE_AT {
-                            String::from("...")
-                        } else {
-                            String::new()
-                        };
-                        println!("*User*: \"{}{}\"", truncated.trim(), end);
-                    }
-                    UserContent::Image(_) => println!("*User*: *image*"),
-                    UserContent::Audio(_) => println!("*User*: *audio*"),
-                    UserContent::Video(_) => println!("*User*: *video*"),
-                    UserContent::Document(_) => println!("*User*: *document*"),
-                    UserContent::ToolResult(_) => println!("*User*: *tool result*"),
-                },
-                Message::Assistant { content, .. } => match content.first() {
-                    AssistantContent::Text(text) => {
-                        let text = text.text();
-                        let truncated = text.chars().take(TRUNCATE_AT).collect::<String>();
-                        let end = if text.len() > TRUNCATE_AT {
-                            String::from("...")
-                        } else {
-                            String::new()
-                        };
-                        println!("*Assistant*: \"{}{}\"", truncated.trim(), end);
-                    }
-                    AssistantContent::Image(_) => println!("*Assistant*: *image*"),
-                    AssistantContent::ToolCall(_) => println!("*Assistant*: *tool call*"),
-                    AssistantContent::Reasoning(_) => println!("*Assistant*: *reasoning*"),
-                },
-            }
-            println!();
-            if let Some(final_i) = messages.len().checked_sub(1)
-                && i != final_i
-            {
-                println!("---");
-            }
+        print_messages(&messages);
+    }
+}
+
+/// Prints messages with their position in the chat, counting from 1
+pub fn print_messages(messages: &[(usize, &Message)]) {
+    for (i, (position, message)) in messages.iter().enumerate() {
+        println!();
+        print!("[{}] ", position + 1);
+        match message {
+            Message::User { content } => match content.first() {
+                UserContent::Text(text) => {
+                    let text = text.text();
+                    let truncated = text.chars().take(TRUNCATE_AT).collect::<String>();
+                    let end = if text.len() > TRUNCATE_AT {
+                        String::from("...")
+                    } else {
+                        String::new()
+                    };
+                    println!("*User*: \"{}{}\"", truncated.trim(), end);
+                }
+                UserContent::Image(_) => println!("*User*: *image*"),
+                UserContent::Audio(_) => println!("*User*: *audio*"),
+                UserContent::Video(_) => println!("*User*: *video*"),
+                UserContent::Document(_) => println!("*User*: *document*"),
+                UserContent::ToolResult(_) => println!("*User*: *tool result*"),
+            },
+            Message::Assistant { content, .. } => match content.first() {
+                AssistantContent::Text(text) => {
+                    let text = text.text();
+                    let truncated = text.chars().take(TRUNCATE_AT).collect::<String>();
+                    let end = if text.len() > TRUNCATE_AT {
+                        String::from("...")
+                    } else {
+                        String::new()
+                    };
+                    println!("*Assistant*: \"{}{}\"", truncated.trim(), end);
+                }
+                AssistantContent::Image(_) => println!("*Assistant*: *image*"),
+                AssistantContent::ToolCall(_) => println!("*Assistant*: *tool call*"),
+                AssistantContent::Reasoning(_) => println!("*Assistant*: *reasoning*"),
+            },
+        }
+        println!();
+        if let Some(final_i) = messages.len().checked_sub(1)
+            && i != final_i
+        {
+            println!("---");
         }
     }
 }
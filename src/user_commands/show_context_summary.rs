@@ -2,6 +2,8 @@ use rig::message::Message;
 
 use crate::chat::Chat;
 
+pub const SUMMARIZE_PROMPT: &str = "Summarize our conversation so far in 2-4 sentences. Focus on the key topics discussed and any conclusions reached.";
+
 pub trait ShowContextSummary {
     fn show_context_summary(&mut self) -> impl Future<Output = anyhow::Result<()>>;
 }
@@ -12,8 +14,7 @@ impl ShowContextSummary for Chat {
         if self.chat_history().is_empty() {
             println!("Nothing to summarize");
         } else {
-            let prompt = "Summarize our conversation so far in 2-4 sentences. Focus on the key topics discussed and any conclusions reached.";
-            self.stream(Message::user(prompt)).await;
+            self.stream(Message::user(SUMMARIZE_PROMPT)).await;
         }
         Ok(())
     }
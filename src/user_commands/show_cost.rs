@@ -0,0 +1,128 @@
+use crate::{
+    chat::Chat,
+    costs::{
+        ledger::{read_ledger, Spend},
+        pricing::model_pricing,
+    },
+    ui::horizontal_line,
+    user_commands::show_token_usage::Formatted,
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy)]
+pub enum CostView {
+    Spend,
+    Prices,
+}
+
+impl From<&str> for CostView {
+    /// Parses everything after `/cost`
+    fn from(args: &str) -> Self {
+        match args.trim().to_lowercase().as_str() {
+            "prices" | "pricing" => Self::Prices,
+            _ => Self::Spend,
+        }
+    }
+}
+
+pub trait ShowCost {
+    fn show_cost(&mut self, view: CostView) -> anyhow::Result<()>;
+}
+
+impl ShowCost for Chat {
+    fn show_cost(&mut self, view: CostView) -> anyhow::Result<()> {
+        self.clear_input();
+        horizontal_line();
+        match view {
+            CostView::Spend => self.print_spend(),
+            CostView::Prices => {
+                self.print_prices();
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Chat {
+    fn print_spend(&self) -> anyhow::Result<()> {
+        let entries = read_ledger()?;
+        let spend = Spend::from_entries(&entries, self.id(), chrono::Local::now());
+        let config = self.config();
+        for (period, spent, budget) in [
+            ("This chat", spend.session, config.session_budget()),
+            ("Today", spend.today, config.daily_budget()),
+            ("This month", spend.month, config.monthly_budget()),
+        ] {
+            match budget {
+                Some(budget) => println!(
+                    "{:<11} ${:.4} of ${:.2} ({:.0}%)",
+                    period,
+                    spent,
+                    budget,
+                    spent / budget * 100.0
+                ),
+                None => println!("{:<11} ${:.4}", period, spent),
+            }
+        }
+        // (input, output, cache tokens, Tavily credits, cost) of this chat by model or tool
+        let mut by_model: BTreeMap<&str, (u64, u64, u64, f64, f64)> = BTreeMap::new();
+        for entry in entries.iter().filter(|entry| entry.session == self.id()) {
+            let totals = by_model.entry(&entry.model).or_default();
+            totals.0 += entry.input_tokens;
+            totals.1 += entry.output_tokens;
+            totals.2 += entry.cache_write_tokens + entry.cache_read_tokens;
+            totals.3 += entry.tavily_credits;
+            totals.4 += entry.cost;
+        }
+        if !by_model.is_empty() {
+            horizontal_line();
+            for (model, (input, output, cache, credits, cost)) in by_model {
+                if credits > 0.0 {
+                    println!("{}: ${:.4} ({} Tavily credits)", model, cost, credits);
+                } else {
+                    println!(
+                        "{}: ${:.4} ({} in, {} out, {} cached tokens)",
+                        model,
+                        cost,
+                        (input as usize).formatted(),
+                        (output as usize).formatted(),
+                        (cache as usize).formatted()
+                    );
+                }
+            }
+        }
+        let tavily_client = self.tavily_client();
+        let used = tavily_client.credit_usage().total();
+        match tavily_client.credit_limit() {
+            Some(limit) => println!("Tavily credits: {} of {} for this chat", used, limit),
+            None if used > 0 => println!("Tavily credits: {} for this chat", used),
+            None => (),
+        }
+        Ok(())
+    }
+    fn print_prices(&self) {
+        println!("USD per million tokens: input / output / cache write / cache read");
+        for model in self.model_options() {
+            match model_pricing(&model.id) {
+                Some((pricing, guessed)) => println!(
+                    "{}: ${} / ${} / ${} / ${}{}",
+                    model.display_name,
+                    pricing.input,
+                    pricing.output,
+                    pricing.cache_write,
+                    pricing.cache_read,
+                    if guessed {
+                        " (assumed from the model family)"
+                    } else {
+                        ""
+                    }
+                ),
+                None => println!("{}: unknown, not counted", model.display_name),
+            }
+        }
+        println!(
+            "Tavily: ${} per credit",
+            self.config().tavily_credit_price()
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    agent_tools::files::workspace::WorkspaceError,
+    chat::Chat,
+    ui::{confirm, horizontal_line},
+};
+
+#[derive(Debug, Clone)]
+pub enum EditsCommand {
+    List,
+    /// Position as numbered by the list, counting from 1, the latest edit when `None`
+    Revert(Option<usize>),
+    Usage,
+}
+
+impl From<&str> for EditsCommand {
+    /// Parses everything after `/edits`
+    fn from(args: &str) -> Self {
+        let args = args.trim();
+        let (subcommand, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        match (subcommand.to_lowercase().as_str(), rest.trim()) {
+            ("" | "list", "") => Self::List,
+            ("revert" | "undo", "") => Self::Revert(None),
+            ("revert" | "undo", n) => match n.parse() {
+                Ok(n) => Self::Revert(Some(n)),
+                Err(_) => Self::Usage,
+            },
+            _ => Self::Usage,
+        }
+    }
+}
+
+pub trait ShowEdits {
+    fn show_edits(&mut self, command: EditsCommand);
+}
+
+impl ShowEdits for Chat {
+    fn show_edits(&mut self, command: EditsCommand) {
+        self.clear_input();
+        horizontal_line();
+        let workspace = self.workspace();
+        let edits = workspace.edits();
+        match command {
+            EditsCommand::List => {
+                let list = edits.edits();
+                if list.is_empty() {
+                    println!("No files edited since Marvin started");
+                    return;
+                }
+                for (i, edit) in list.iter().enumerate() {
+                    println!(
+                        "{}. {} {} by {} ({})",
+                        i + 1,
+                        if edit.before.is_some() {
+                            "changed"
+                        } else {
+                            "created"
+                        },
+                        workspace.relative(&edit.path),
+                        edit.tool,
+                        edit.applied_at
+                            .with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M")
+                    );
+                }
+                horizontal_line();
+                println!("/edits revert [n] undoes the latest edit or edit n");
+            }
+            EditsCommand::Revert(n) => {
+                let count = edits.edits().len();
+                let Some(index) = n.unwrap_or(count).checked_sub(1).filter(|&i| i < count) else {
+                    match n {
+                        Some(n) => println!("No edit {}, there are {}", n, count),
+                        None => println!("No files edited since Marvin started"),
+                    }
+                    return;
+                };
+                let reverted = match edits.revert(index, false) {
+                    Err(WorkspaceError::ChangedSinceEdit(path)) => {
+                        if !confirm(&format!(
+                            "{} changed since the edit, revert it anyway and lose those changes?",
+                            path
+                        )) {
+                            return;
+                        }
+                        edits.revert(index, true)
+                    }
+                    result => result,
+                };
+                match reverted {
+                    Ok(edit) if edit.before.is_some() => {
+                        println!("Restored {}", workspace.relative(&edit.path))
+                    }
+                    Ok(edit) => println!("Deleted {}", workspace.relative(&edit.path)),
+                    Err(e) => eprintln!("Failed to revert the edit: {}", e),
+                }
+            }
+            EditsCommand::Usage => println!("Usage: /edits | /edits revert [n]"),
+        }
+    }
+}
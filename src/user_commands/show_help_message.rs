@@ -10,16 +10,35 @@ impl ShowHelpMessage for Chat {
         horizontal_line();
         println!("Commands:");
         println!("  /model     - switch between available Claude models");
-        println!("  /tokens    - show token usage");
+        println!("  /tokens    - show token usage and the context meter");
+        println!("  /cost      - show spend for this chat, today and this month, /cost prices lists model prices");
         println!(
             "  /history   - show last {} messages from chat history",
             HISTORY_LEN
         );
-        println!("  /save      - save chat history to file");
-        println!("  /import    - import chat history from a saved file");
+        println!("  /save      - save chat history to file, optionally with a title");
+        println!("  /import    - import chat history from a saved chat ID, or a ChatGPT, Claude.ai or JSONL export path");
+        println!("  /chats     - list, search, open, resume, rename, tag and delete saved chats");
+        println!("  /resume    - continue the latest (or a given) saved chat with its model and settings");
+        println!("  /export    - export this chat as a md, html or txt transcript");
+        println!("  /search    - search all saved chats, then resume or export a result");
+        println!(
+            "  /lock      - lock encrypted chat storage until the passphrase is entered again"
+        );
         println!("  /summarize - ask the agent to summarize the conversation");
-        println!("  /compact   - condense conversation history");
-        println!("  /clear     - clear chat history");
+        println!("  /compact   - condense older history into a recap, /compact <n> keeps the last n exchanges (default 2)");
+        println!("  /retry     - regenerate the last answer, optionally with another model");
+        println!("  /undo      - drop the last exchange");
+        println!("  /edit      - rewrite an earlier prompt and continue on a new branch from it");
+        println!("  /branches  - list this chat's branches, /branches <n> switches to one");
+        println!("  /fork      - copy this chat and its branches into a new chat");
+        println!(
+            "  /pin       - pin a message by its /history number, or a note, to every request"
+        );
+        println!("  /pins      - list pins, /pins remove <n> unpins one");
+        println!("  /edits     - list file edits made by the agent, /edits revert [n] undoes one");
+        println!("  /permissions - show the tool permission rules, add <rule>, remove <n>, forget or reset them");
+        println!("  /clear     - clear chat history (pins are kept)");
         println!("  /help      - list all available commands");
         println!("  /exit      - print farewell message and end the session");
     }
@@ -0,0 +1,79 @@
+use crate::{chat::Chat, ui::horizontal_line};
+
+const PREVIEW_LEN: usize = 120;
+
+#[derive(Debug, Clone)]
+pub enum PinsCommand {
+    List,
+    /// Position as numbered by the list, counting from 1
+    Remove(usize),
+    Usage,
+}
+
+impl From<&str> for PinsCommand {
+    /// Parses everything after `/pins`
+    fn from(args: &str) -> Self {
+        let args = args.trim();
+        let (subcommand, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        match subcommand.to_lowercase().as_str() {
+            "" | "list" => Self::List,
+            "remove" | "rm" | "unpin" => match rest.trim().parse() {
+                Ok(n) => Self::Remove(n),
+                Err(_) => Self::Usage,
+            },
+            _ => Self::Usage,
+        }
+    }
+}
+
+pub trait ShowPins {
+    fn show_pins(&mut self, command: PinsCommand);
+}
+
+impl ShowPins for Chat {
+    fn show_pins(&mut self, command: PinsCommand) {
+        self.clear_input();
+        horizontal_line();
+        match command {
+            PinsCommand::List => {
+                if self.chat_history().pins().is_empty() {
+                    println!("Nothing pinned, use /pin <message number> or /pin <note>");
+                    return;
+                }
+                for (i, pin) in self.chat_history().pins().iter().enumerate() {
+                    let text = pin.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    let preview: String = text.chars().take(PREVIEW_LEN).collect();
+                    let end = if text.chars().count() > PREVIEW_LEN {
+                        "..."
+                    } else {
+                        ""
+                    };
+                    println!(
+                        "{}. [{:?}] \"{}{}\" (pinned {})",
+                        i + 1,
+                        pin.source,
+                        preview,
+                        end,
+                        pin.pinned_at
+                            .with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M")
+                    );
+                }
+                horizontal_line();
+                println!("/pins remove <n> unpins one");
+            }
+            PinsCommand::Remove(n) => match n.checked_sub(1).and_then(|i| self.unpin(i)) {
+                Some(_) => {
+                    self.autosave();
+                    println!("Unpinned {}", n);
+                }
+                None => println!(
+                    "No pin {}, there are {}",
+                    n,
+                    self.chat_history().pins().len()
+                ),
+            },
+            PinsCommand::Usage => println!("Usage: /pins | /pins remove <n>"),
+        }
+    }
+}
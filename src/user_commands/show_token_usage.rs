@@ -4,7 +4,7 @@ pub trait ShowTokenUsage {
     fn show_token_usage(&mut self);
 }
 
-trait Formatted {
+pub trait Formatted {
     fn formatted(self) -> String;
 }
 
@@ -32,6 +32,9 @@ impl ShowTokenUsage for Chat {
             "Total Output Tokens Used: {}",
             self.total_output_tokens_used().formatted()
         );
+        if let Some(usage) = self.last_context_usage() {
+            println!("{}", usage.meter());
+        }
     }
 }
 
@@ -0,0 +1,77 @@
+use crate::{
+    chat::{history::prompt_text, Chat},
+    ui::horizontal_line,
+};
+use chrono::Local;
+
+const PREVIEW_LEN: usize = 60;
+
+pub trait SwitchBranch {
+    fn switch_branch_to(&mut self, n: Option<usize>);
+}
+
+impl SwitchBranch for Chat {
+    /// Lists the branches of this chat, or makes the `n`th one active
+    fn switch_branch_to(&mut self, n: Option<usize>) {
+        self.clear_input();
+        horizontal_line();
+        let tips = self.chat_history().branch_tips();
+        let Some(n) = n else {
+            for (i, tip) in tips.iter().enumerate() {
+                let entries: Vec<_> = self.chat_history().branch(*tip).collect();
+                let marker = if *tip == self.chat_history().head() {
+                    "*"
+                } else {
+                    " "
+                };
+                let last_prompt = entries
+                    .iter()
+                    .rev()
+                    .find_map(|entry| prompt_text(&entry.message))
+                    .map(|text| {
+                        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                        let preview: String = text.chars().take(PREVIEW_LEN).collect();
+                        let end = if text.chars().count() > PREVIEW_LEN {
+                            "..."
+                        } else {
+                            ""
+                        };
+                        format!("\"{}{}\"", preview, end)
+                    })
+                    .unwrap_or_else(|| "(empty)".to_string());
+                let updated = entries
+                    .last()
+                    .and_then(|entry| entry.timestamp)
+                    .map(|timestamp| {
+                        timestamp
+                            .with_timezone(&Local)
+                            .format(" | %Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                println!(
+                    "{}{}. {} messages{} | {}",
+                    marker,
+                    i + 1,
+                    entries.len(),
+                    updated,
+                    last_prompt
+                );
+            }
+            horizontal_line();
+            println!("* marks the active branch, /branches <n> switches to another");
+            return;
+        };
+        let Some(tip) = n.checked_sub(1).and_then(|i| tips.get(i)) else {
+            println!("No branch {}, there are {}", n, tips.len());
+            return;
+        };
+        self.switch_branch(*tip);
+        self.autosave();
+        println!(
+            "Switched to branch {} with {} messages",
+            n,
+            self.chat_history().len()
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{chat::Chat, ui::horizontal_line};
+
+pub trait UndoTurn {
+    fn undo_turn(&mut self);
+}
+
+impl UndoTurn for Chat {
+    /// Drops the last exchange from the active branch, it stays listed under `/branches`
+    fn undo_turn(&mut self) {
+        self.clear_input();
+        horizontal_line();
+        let Some(position) = self
+            .chat_history()
+            .prompts()
+            .last()
+            .map(|(position, _)| *position)
+        else {
+            println!("Nothing to undo");
+            return;
+        };
+        self.truncate_chat_history(position);
+        self.autosave();
+        println!(
+            "Dropped the last exchange, {} messages left (use /branches to bring it back)",
+            self.chat_history().len()
+        );
+    }
+}